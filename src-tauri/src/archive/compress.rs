@@ -0,0 +1,374 @@
+// Creating archives from files and folders.
+// The archive is written to a hidden staging file next to its final location
+// and only renamed into place once complete, so a failed or cancelled job
+// leaves nothing behind.
+
+use crate::error::{AppError, PathContext};
+use crate::jobs::{job_error, Job};
+use serde::Deserialize;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Archive settings chosen by the user
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct CompressOptions {
+    pub format: String, // "zip" (default), "tar", "tar.gz", "tar.xz" or "tar.zst"
+    pub method: Option<String>, // Zip only: "deflate" (default), "zstd" or "store"
+    pub level: Option<i64>, // Format default when missing
+    pub password: Option<String>, // Zip only, AES-256
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Zip,
+    Tar,
+    TarGz,
+    TarXz,
+    TarZst,
+}
+
+impl Format {
+    fn parse(format: &str) -> Result<Self, AppError> {
+        match format {
+            "" | "zip" => Ok(Format::Zip),
+            "tar" => Ok(Format::Tar),
+            "tar.gz" | "tgz" => Ok(Format::TarGz),
+            "tar.xz" | "txz" => Ok(Format::TarXz),
+            "tar.zst" | "tzst" => Ok(Format::TarZst),
+            other => Err(AppError::invalid_input(format!(
+                "Unsupported archive format: {}",
+                other
+            ))),
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Zip => ".zip",
+            Format::Tar => ".tar",
+            Format::TarGz => ".tar.gz",
+            Format::TarXz => ".tar.xz",
+            Format::TarZst => ".tar.zst",
+        }
+    }
+}
+
+/// One file system entry to store, in archive order
+struct Input {
+    path: PathBuf,
+    name: String, // '/'-separated name inside the archive
+    metadata: fs::Metadata,
+}
+
+/// Compress files and folders into a new archive in the destination folder.
+/// The format's extension is added to name if missing, and the name is made
+/// unique like copies are. Returns the path of the archive.
+#[tauri::command]
+pub async fn compress_items(
+    app: tauri::AppHandle,
+    job_id: String,
+    sources: Vec<String>,
+    destination: String,
+    name: String,
+    options: Option<CompressOptions>,
+) -> Result<String, AppError> {
+    let job = Job::start(&app, job_id)?;
+    let options = options.unwrap_or_default();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        compress(&job, &sources, Path::new(&destination), &name, &options)
+            .map(|path| path.to_string_lossy().to_string())
+            .map_err(|e| job_error(&job, e))
+    })
+    .await
+    .map_err(AppError::other)?
+}
+
+fn compress(
+    job: &Job,
+    sources: &[String],
+    dest_dir: &Path,
+    name: &str,
+    options: &CompressOptions,
+) -> Result<PathBuf, AppError> {
+    let format = Format::parse(&options.format)?;
+
+    if !dest_dir.is_dir() {
+        return Err(AppError::not_a_directory(dest_dir));
+    }
+    if sources.is_empty() {
+        return Err(AppError::invalid_input("Nothing to compress"));
+    }
+    crate::validation::check_path(dest_dir)?;
+    crate::validation::check_name(name)?;
+    if options.password.is_some() && format != Format::Zip {
+        return Err(AppError::invalid_input(
+            "Only zip archives can be password protected",
+        ));
+    }
+
+    let extension = format.extension();
+    let stem = if name.to_lowercase().ends_with(extension) {
+        &name[..name.len() - extension.len()]
+    } else {
+        name
+    };
+    let file_name = format!("{}{}", stem, extension);
+
+    let staging = crate::get_unique_path(
+        dest_dir,
+        &format!("{}{}", crate::transfer::PARTIAL_PREFIX, file_name),
+        false,
+    );
+
+    let result = collect_inputs(sources, &staging).and_then(|inputs| {
+        let total_bytes = inputs
+            .iter()
+            .filter(|i| i.metadata.is_file())
+            .map(|i| i.metadata.len())
+            .sum();
+        job.set_total(total_bytes, inputs.len() as u64);
+
+        let file = File::create(&staging).at(&staging)?;
+        let file = write_archive(
+            job,
+            format,
+            options,
+            &inputs,
+            &staging,
+            BufWriter::new(file),
+        )?;
+        file.sync_all().at(&staging)
+    });
+
+    if let Err(e) = result {
+        let _ = fs::remove_file(&staging);
+        return Err(e);
+    }
+
+    // Pick the final name last, in case it was taken while we were working
+    let dest = unique_archive_path(dest_dir, stem, extension);
+    if let Err(e) = fs::rename(&staging, &dest) {
+        let _ = fs::remove_file(&staging);
+        return Err(AppError::io(e, &dest));
+    }
+
+    job.finish();
+    Ok(dest)
+}
+
+/// Like get_unique_path, but keeps compound extensions such as ".tar.gz"
+/// together: "photos (1).tar.gz" rather than "photos.tar (1).gz"
+fn unique_archive_path(dest_dir: &Path, stem: &str, extension: &str) -> PathBuf {
+    let mut path = dest_dir.join(format!("{}{}", stem, extension));
+    let mut counter = 1;
+
+    while fs::symlink_metadata(&path).is_ok() {
+        path = dest_dir.join(format!("{} ({}){}", stem, counter, extension));
+        counter += 1;
+    }
+
+    path
+}
+
+/// Walk the sources; each one is stored under its own name at the archive root
+fn collect_inputs(sources: &[String], staging: &Path) -> Result<Vec<Input>, AppError> {
+    let mut inputs = Vec::new();
+
+    for source in sources {
+        let source = Path::new(source);
+        fs::symlink_metadata(source).at(source)?;
+        let base = source.parent().unwrap_or(source);
+
+        for entry in WalkDir::new(source).follow_links(false) {
+            let entry = entry?;
+
+            // Don't pack the archive into itself when it's created inside a source
+            if entry.path() == staging {
+                continue;
+            }
+
+            let relative = entry.path().strip_prefix(base).unwrap_or(entry.path());
+            let name = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            if name.is_empty() {
+                continue; // A root like "/" has no name of its own
+            }
+
+            inputs.push(Input {
+                path: entry.path().to_path_buf(),
+                name,
+                metadata: entry.metadata()?,
+            });
+        }
+    }
+
+    Ok(inputs)
+}
+
+fn write_archive(
+    job: &Job,
+    format: Format,
+    options: &CompressOptions,
+    inputs: &[Input],
+    staging: &Path,
+    out: BufWriter<File>,
+) -> Result<File, AppError> {
+    let check_level = |min: i64, max: i64, default: i64| match options.level {
+        None => Ok(default),
+        Some(level) if (min..=max).contains(&level) => Ok(level),
+        Some(level) => Err(AppError::invalid_input(format!(
+            "Compression level {} is out of range ({} to {})",
+            level, min, max
+        ))),
+    };
+
+    let out = match format {
+        Format::Zip => write_zip(job, options, inputs, staging, out)?,
+        Format::Tar => write_tar(job, inputs, out).at(staging)?,
+        Format::TarGz => {
+            let level = check_level(0, 9, 6)? as u32;
+            let encoder = flate2::write::GzEncoder::new(out, flate2::Compression::new(level));
+            write_tar(job, inputs, encoder)
+                .and_then(|encoder| encoder.finish())
+                .at(staging)?
+        }
+        Format::TarXz => {
+            let level = check_level(0, 9, 6)? as u32;
+            let options = lzma_rust2::XzOptions::with_preset(level);
+            let encoder = lzma_rust2::XzWriter::new(out, options).at(staging)?;
+            write_tar(job, inputs, encoder)
+                .and_then(|encoder| encoder.finish())
+                .at(staging)?
+        }
+        Format::TarZst => {
+            let level = check_level(1, 22, 3)? as i32;
+            let encoder = zstd::stream::write::Encoder::new(out, level).at(staging)?;
+            write_tar(job, inputs, encoder)
+                .and_then(|encoder| encoder.finish())
+                .at(staging)?
+        }
+    };
+
+    out.into_inner()
+        .map_err(|e| AppError::io(e.into_error(), staging))
+}
+
+fn write_tar<W: Write>(job: &Job, inputs: &[Input], out: W) -> io::Result<W> {
+    let mut builder = tar::Builder::new(out);
+
+    for input in inputs {
+        job.check_cancelled()?;
+        job.begin_item(&input.name);
+
+        let mut header = tar::Header::new_gnu();
+        header.set_metadata(&input.metadata);
+
+        if input.metadata.is_dir() {
+            builder.append_data(&mut header, format!("{}/", input.name), io::empty())?;
+        } else if input.metadata.is_symlink() {
+            let target = fs::read_link(&input.path)?;
+            header.set_size(0);
+            builder.append_link(&mut header, &input.name, target)?;
+        } else {
+            // Store exactly the size in the header even if the file grows meanwhile
+            let file = File::open(&input.path)?;
+            let content = job.track(io::Read::take(file, input.metadata.len()));
+            builder.append_data(&mut header, &input.name, content)?;
+        }
+    }
+
+    builder.into_inner()
+}
+
+fn write_zip(
+    job: &Job,
+    options: &CompressOptions,
+    inputs: &[Input],
+    staging: &Path,
+    out: BufWriter<File>,
+) -> Result<BufWriter<File>, AppError> {
+    use zip::result::ZipError;
+    use zip::write::{FileOptions, SimpleFileOptions};
+    use zip::CompressionMethod;
+
+    let zip_error = |error: ZipError| match error {
+        ZipError::Io(e) => AppError::io(e, staging),
+        other => AppError::other(other),
+    };
+
+    let (method, levels) = match options.method.as_deref().unwrap_or("deflate") {
+        "deflate" => (CompressionMethod::Deflated, Some((0, 9))),
+        "zstd" => (CompressionMethod::Zstd, Some((1, 22))),
+        "store" => (CompressionMethod::Stored, None),
+        other => {
+            return Err(AppError::invalid_input(format!(
+                "Unsupported compression method: {}",
+                other
+            )))
+        }
+    };
+
+    let level = match (options.level, levels) {
+        (Some(level), Some((min, max))) if !(min..=max).contains(&level) => {
+            return Err(AppError::invalid_input(format!(
+                "Compression level {} is out of range ({} to {})",
+                level, min, max
+            )))
+        }
+        (level, Some(_)) => level,
+        (_, None) => None,
+    };
+
+    let base = SimpleFileOptions::default()
+        .compression_method(method)
+        .compression_level(level);
+    let mut zip = zip::ZipWriter::new(out);
+
+    for input in inputs {
+        job.check_cancelled().at(staging)?;
+        job.begin_item(&input.name);
+
+        let mut file_options: FileOptions<()> =
+            base.large_file(input.metadata.len() >= u32::MAX as u64);
+        if let Some(time) = input
+            .metadata
+            .modified()
+            .ok()
+            .map(|t| chrono::DateTime::<chrono::Local>::from(t).naive_local())
+            .and_then(|t| zip::DateTime::try_from(t).ok())
+        {
+            file_options = file_options.last_modified_time(time);
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file_options = file_options.unix_permissions(input.metadata.permissions().mode());
+        }
+        if let Some(password) = &options.password {
+            file_options = file_options.with_aes_encryption(zip::AesMode::Aes256, password);
+        }
+
+        if input.metadata.is_dir() {
+            zip.add_directory(format!("{}/", input.name), file_options)
+                .map_err(zip_error)?;
+        } else if input.metadata.is_symlink() {
+            let target = fs::read_link(&input.path).at(&input.path)?;
+            zip.add_symlink(&input.name, target.to_string_lossy(), file_options)
+                .map_err(zip_error)?;
+        } else {
+            zip.start_file(&input.name, file_options)
+                .map_err(zip_error)?;
+            let file = File::open(&input.path).at(&input.path)?;
+            io::copy(&mut job.track(file), &mut zip).at(staging)?;
+        }
+    }
+
+    zip.finish().map_err(zip_error)
+}
@@ -0,0 +1,247 @@
+// Extracting whole archives to disk.
+// Every file is written to a hidden staging file and renamed into place, so
+// existing files are only replaced by complete ones. Members whose names
+// would land outside the destination are refused.
+
+use super::reader::{self, ArchiveFormat, Member};
+use crate::error::{AppError, PathContext};
+use crate::jobs::{job_error, Job};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+/// What to do when a file to extract already exists
+#[derive(Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictPolicy {
+    #[default]
+    Rename, // Keep both, naming the new file like copies are
+    Overwrite,
+    Skip,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct ExtractOptions {
+    pub password: Option<String>,
+    pub on_conflict: ConflictPolicy,
+    pub extract_here: bool, // Extract into destination instead of a new folder named after the archive
+}
+
+#[derive(Serialize)]
+pub struct ExtractReport {
+    pub path: String, // Folder the members were extracted into
+    pub files_extracted: u64,
+    pub skipped: Vec<String>, // Existing files kept because of ConflictPolicy::Skip
+    pub rejected: Vec<String>, // Member names that would escape the destination
+}
+
+/// Extract all members of an archive into the destination folder
+#[tauri::command]
+pub async fn extract_archive(
+    app: tauri::AppHandle,
+    job_id: String,
+    archive: String,
+    destination: String,
+    options: Option<ExtractOptions>,
+) -> Result<ExtractReport, AppError> {
+    let job = Job::start(&app, job_id)?;
+    let options = options.unwrap_or_default();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        extract(&job, Path::new(&archive), Path::new(&destination), &options)
+            .map_err(|e| job_error(&job, e))
+    })
+    .await
+    .map_err(AppError::other)?
+}
+
+fn extract(
+    job: &Job,
+    archive: &Path,
+    dest_dir: &Path,
+    options: &ExtractOptions,
+) -> Result<ExtractReport, AppError> {
+    let format = ArchiveFormat::from_path(archive)
+        .ok_or_else(|| AppError::unsupported(archive, "Not a supported archive"))?;
+
+    if !dest_dir.is_dir() {
+        return Err(AppError::not_a_directory(dest_dir));
+    }
+
+    let entries = super::listing(archive)?;
+    job.set_total(
+        entries.iter().filter(|e| !e.is_dir).map(|e| e.size).sum(),
+        entries.len() as u64,
+    );
+
+    let root = if options.extract_here {
+        dest_dir.to_path_buf()
+    } else {
+        let root = crate::get_unique_path(dest_dir, &folder_name(archive), true);
+        fs::create_dir(&root).at(&root)?;
+        root
+    };
+    // Resolved once so members can be checked against it after symlinks
+    let canonical_root = root.canonicalize().at(&root)?;
+
+    let mut report = ExtractReport {
+        path: root.to_string_lossy().to_string(),
+        files_extracted: 0,
+        skipped: Vec::new(),
+        rejected: Vec::new(),
+    };
+
+    let result = reader::for_each_member(
+        archive,
+        format,
+        options.password.as_deref(),
+        &|_| true,
+        &mut |member, content| {
+            job.check_cancelled().at(archive)?;
+            extract_member(
+                job,
+                member,
+                content,
+                &root,
+                &canonical_root,
+                options,
+                &mut report,
+            )?;
+            Ok(true)
+        },
+    );
+
+    if let Err(e) = result {
+        // A folder we created ourselves goes away entirely; "extract here" keeps
+        // the files that were already complete
+        if !options.extract_here {
+            let _ = fs::remove_dir_all(&root);
+        }
+        return Err(e);
+    }
+
+    job.finish();
+    Ok(report)
+}
+
+fn extract_member(
+    job: &Job,
+    member: &Member,
+    content: &mut dyn Read,
+    root: &Path,
+    canonical_root: &Path,
+    options: &ExtractOptions,
+    report: &mut ExtractReport,
+) -> Result<(), AppError> {
+    let Some(path) = &member.path else {
+        report.rejected.push(member.name.clone());
+        return Ok(());
+    };
+    job.begin_item(path);
+
+    let target = path.split('/').fold(root.to_path_buf(), |p, s| p.join(s));
+
+    let parent = target.parent().unwrap_or(root).to_path_buf();
+
+    // A folder already in the way may be a symlink to somewhere else
+    let checked = if member.is_dir { &target } else { &parent };
+    if !is_inside(checked, canonical_root).at(checked)? {
+        report.rejected.push(member.name.clone());
+        return Ok(());
+    }
+
+    if member.is_dir {
+        return fs::create_dir_all(&target).at(&target);
+    }
+    fs::create_dir_all(&parent).at(&parent)?;
+
+    let file_name = target
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let target = if fs::symlink_metadata(&target).is_ok() {
+        match options.on_conflict {
+            ConflictPolicy::Skip => {
+                report.skipped.push(path.clone());
+                return Ok(());
+            }
+            ConflictPolicy::Overwrite => target,
+            ConflictPolicy::Rename => crate::get_unique_path(&parent, &file_name, false),
+        }
+    } else {
+        target
+    };
+
+    let staging = crate::get_unique_path(
+        &parent,
+        &format!("{}{}", crate::transfer::PARTIAL_PREFIX, file_name),
+        false,
+    );
+
+    let written = write_file(job, content, &staging).and_then(|file| {
+        if let Some(time) = member
+            .modified
+            .and_then(|t| t.and_local_timezone(chrono::Local).earliest())
+        {
+            let time = filetime::FileTime::from_system_time(time.into());
+            filetime::set_file_handle_times(&file, Some(time), Some(time))?;
+        }
+        #[cfg(unix)]
+        if let Some(mode) = member.mode {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(fs::Permissions::from_mode(mode & 0o777))?;
+        }
+        drop(file);
+        fs::rename(&staging, &target)
+    });
+
+    if let Err(e) = written {
+        let _ = fs::remove_file(&staging);
+        return Err(AppError::io(e, &target));
+    }
+
+    report.files_extracted += 1;
+    Ok(())
+}
+
+/// Check that path, or the part of it that exists so far, resolves inside root
+fn is_inside(path: &Path, canonical_root: &Path) -> io::Result<bool> {
+    let mut existing = path;
+    while fs::symlink_metadata(existing).is_err() {
+        match existing.parent() {
+            Some(parent) => existing = parent,
+            None => return Ok(false),
+        }
+    }
+    Ok(existing.canonicalize()?.starts_with(canonical_root))
+}
+
+fn write_file(job: &Job, content: &mut dyn Read, path: &Path) -> io::Result<File> {
+    let mut file = File::create(path)?;
+    io::copy(&mut job.track(content), &mut file)?;
+    file.flush()?;
+    Ok(file)
+}
+
+/// "photos.tar.gz" -> "photos"
+fn folder_name(archive: &Path) -> String {
+    let name = archive
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let stem = reader::FORMAT_SUFFIXES
+        .iter()
+        .find(|(suffix, _)| name.to_lowercase().ends_with(suffix))
+        .map(|(suffix, _)| &name[..name.len() - suffix.len()])
+        .unwrap_or(&name);
+
+    if stem.is_empty() {
+        "Extracted".to_string()
+    } else {
+        stem.to_string()
+    }
+}
@@ -0,0 +1,332 @@
+// Archives as virtual folders.
+// A path like "C:\data\set.zip\images\a.png" addresses the member
+// "images/a.png" of "C:\data\set.zip"; listing and reading members is done
+// straight from the archive file without extracting it to disk.
+// Creating and fully extracting archives are background jobs, see compress
+// and extract.
+
+pub mod compress;
+pub mod extract;
+mod reader;
+
+use crate::error::{AppError, PathContext};
+use crate::FileEntry;
+use reader::{ArchiveEntry, ArchiveFormat, Member};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Largest member read into memory for previews and thumbnails
+pub const MEMBER_READ_LIMIT: u64 = 64 * 1024 * 1024;
+
+/// Number of archive listings kept in memory
+const LISTING_CACHE_SIZE: usize = 8;
+
+struct CachedListing {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    len: u64,
+    entries: Arc<Vec<ArchiveEntry>>,
+}
+
+/// Recently read listings, most recently used last
+static LISTING_CACHE: Mutex<Vec<CachedListing>> = Mutex::new(Vec::new());
+
+/// Check if a file is an archive that can be browsed like a folder
+pub fn is_archive(path: &Path) -> bool {
+    ArchiveFormat::from_path(path).is_some()
+}
+
+/// Split a path into (archive file, member path inside it).
+/// Returns None for paths that don't go through an archive.
+/// The member path is '/'-separated and empty for the archive root.
+pub fn split_archive_path(path: &Path) -> Option<(PathBuf, String)> {
+    let mut inner = Vec::new();
+    let mut current = path;
+
+    // Walk up to the first ancestor that exists on disk
+    loop {
+        match fs::metadata(current) {
+            Ok(metadata) => {
+                if metadata.is_file() && is_archive(current) {
+                    inner.reverse();
+                    return Some((current.to_path_buf(), inner.join("/")));
+                }
+                return None;
+            }
+            Err(_) => {
+                inner.push(current.file_name()?.to_string_lossy().to_string());
+                current = current.parent()?;
+            }
+        }
+    }
+}
+
+/// Like split_archive_path, but only for paths that point inside an archive
+pub fn member_path(path: &Path) -> Option<(PathBuf, String)> {
+    split_archive_path(path).filter(|(_, inner)| !inner.is_empty())
+}
+
+/// List the members directly inside `inner` ("" for the archive root)
+pub fn list_directory(
+    archive: &Path,
+    inner: &str,
+    dirs_only: bool,
+) -> Result<Vec<FileEntry>, AppError> {
+    let entries = listing(archive)?;
+
+    if !inner.is_empty() {
+        let entry = find_entry(&entries, archive, inner)?;
+        if !entry.is_dir {
+            return Err(AppError::not_a_directory(virtual_path(archive, inner)));
+        }
+    }
+
+    let mut result: Vec<FileEntry> = entries
+        .iter()
+        .filter(|e| parent_of(&e.path) == inner && (e.is_dir || !dirs_only))
+        .map(|e| to_file_entry(archive, e))
+        .collect();
+
+    crate::sort_entries(&mut result);
+
+    Ok(result)
+}
+
+/// Read a member's content into memory, up to `limit` bytes. Of a path
+/// recorded more than once, the last copy is read, as it's the one listed.
+pub fn read_member(archive: &Path, inner: &str, limit: u64) -> Result<Vec<u8>, AppError> {
+    let format = archive_format(archive)?;
+    let entries = listing(archive)?;
+    let entry = find_entry(&entries, archive, inner)?;
+
+    if entry.is_dir {
+        return Err(AppError::is_a_directory(virtual_path(archive, inner)));
+    }
+
+    let mut data = None;
+    let mut seen = 0;
+    let wanted = |member: &Member| member.path.as_deref() == Some(inner);
+    reader::for_each_member(archive, format, None, &wanted, &mut |_, content| {
+        seen += 1;
+        if seen < entry.copies {
+            return Ok(true);
+        }
+        let mut buffer = Vec::new();
+        content
+            .take(limit)
+            .read_to_end(&mut buffer)
+            .at(virtual_path(archive, inner))?;
+        data = Some(buffer);
+        Ok(false)
+    })?;
+
+    data.ok_or_else(|| AppError::not_found(virtual_path(archive, inner)))
+}
+
+/// Read all of a member into memory; an error rather than a cut-off copy if
+/// it's larger than MEMBER_READ_LIMIT
+pub fn read_whole_member(archive: &Path, inner: &str) -> Result<Vec<u8>, AppError> {
+    let too_large = || {
+        AppError::unsupported(
+            virtual_path(archive, inner),
+            "Too large to read from inside the archive; extract it first",
+        )
+    };
+    let entries = listing(archive)?;
+    if find_entry(&entries, archive, inner)?.size > MEMBER_READ_LIMIT {
+        return Err(too_large());
+    }
+    // The size in the header may not be the truth
+    let data = read_member(archive, inner, MEMBER_READ_LIMIT + 1)?;
+    if data.len() as u64 > MEMBER_READ_LIMIT {
+        return Err(too_large());
+    }
+    Ok(data)
+}
+
+/// Extract a member (file or folder) into dest_dir, returning the created path.
+/// The name is made unique the same way copies are. Members recorded more
+/// than once end up as their last copy, as listed.
+pub fn extract_member(archive: &Path, inner: &str, dest_dir: &Path) -> Result<PathBuf, AppError> {
+    let format = archive_format(archive)?;
+    let entries = listing(archive)?;
+    let entry = find_entry(&entries, archive, inner)?;
+    let is_dir = entry.is_dir;
+
+    let name = inner.rsplit('/').next().unwrap_or(inner);
+    let dest = crate::get_unique_path(dest_dir, name, is_dir);
+    let prefix = format!("{}/", inner);
+
+    if is_dir {
+        fs::create_dir_all(&dest).at(&dest)?;
+    }
+
+    let wanted = |member: &Member| {
+        member
+            .path
+            .as_deref()
+            .is_some_and(|path| path == inner || path.starts_with(&prefix))
+    };
+    let mut seen = 0;
+    reader::for_each_member(archive, format, None, &wanted, &mut |member, content| {
+        let Some(path) = member.path.as_deref() else {
+            return Ok(true);
+        };
+        let relative = if path == inner {
+            ""
+        } else if let Some(rest) = path.strip_prefix(&prefix) {
+            rest
+        } else {
+            return Ok(true);
+        };
+        // A file recorded more than once comes from its last copy; inside a
+        // folder, later copies overwrite earlier ones as they come
+        if !is_dir {
+            seen += 1;
+            if seen < entry.copies {
+                return Ok(true);
+            }
+        }
+
+        // Member paths are normalized, so joining them can't leave dest
+        let target = relative
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .fold(dest.clone(), |p, s| p.join(s));
+
+        if member.is_dir {
+            fs::create_dir_all(&target).at(&target)?;
+        } else {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).at(parent)?;
+            }
+            let mut file = fs::File::create(&target).at(&target)?;
+            io::copy(content, &mut file).at(&target)?;
+        }
+
+        // A single file is done as soon as it's written
+        Ok(is_dir)
+    })?;
+
+    Ok(dest)
+}
+
+/// Get the listing of an archive, reading it only if it changed since last time
+fn listing(archive: &Path) -> Result<Arc<Vec<ArchiveEntry>>, AppError> {
+    let format = archive_format(archive)?;
+    let metadata = fs::metadata(archive).at(archive)?;
+    let modified = metadata.modified().ok();
+    let len = metadata.len();
+
+    {
+        let mut cache = LISTING_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(pos) = cache.iter().position(|c| c.path == archive) {
+            let cached = cache.remove(pos);
+            if cached.modified == modified && cached.len == len {
+                let entries = cached.entries.clone();
+                cache.push(cached);
+                return Ok(entries);
+            }
+        }
+    }
+
+    let entries = Arc::new(complete_directories(reader::read_entries(archive, format)?));
+
+    let mut cache = LISTING_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    cache.push(CachedListing {
+        path: archive.to_path_buf(),
+        modified,
+        len,
+        entries: entries.clone(),
+    });
+    if cache.len() > LISTING_CACHE_SIZE {
+        cache.remove(0);
+    }
+
+    Ok(entries)
+}
+
+fn archive_format(archive: &Path) -> Result<ArchiveFormat, AppError> {
+    ArchiveFormat::from_path(archive)
+        .ok_or_else(|| AppError::unsupported(archive, "Not a supported archive"))
+}
+
+/// Add the parent folders that many archives don't record explicitly.
+/// Later duplicates win, matching how tar appends updated members.
+fn complete_directories(entries: Vec<ArchiveEntry>) -> Vec<ArchiveEntry> {
+    let mut by_path: HashMap<String, ArchiveEntry> = HashMap::new();
+
+    for entry in entries {
+        let mut parent = parent_of(&entry.path);
+        while !parent.is_empty() && !by_path.contains_key(parent) {
+            by_path.insert(
+                parent.to_string(),
+                ArchiveEntry {
+                    path: parent.to_string(),
+                    is_dir: true,
+                    size: 0,
+                    modified: None,
+                    copies: 0,
+                },
+            );
+            parent = parent_of(parent);
+        }
+        let copies = by_path.get(&entry.path).map_or(0, |e| e.copies) + entry.copies;
+        by_path.insert(entry.path.clone(), ArchiveEntry { copies, ..entry });
+    }
+
+    by_path.into_values().collect()
+}
+
+fn find_entry<'a>(
+    entries: &'a [ArchiveEntry],
+    archive: &Path,
+    inner: &str,
+) -> Result<&'a ArchiveEntry, AppError> {
+    entries
+        .iter()
+        .find(|e| e.path == inner)
+        .ok_or_else(|| AppError::not_found(virtual_path(archive, inner)))
+}
+
+/// "a/b/c" -> "a/b", "a" -> ""
+fn parent_of(path: &str) -> &str {
+    path.rsplit_once('/')
+        .map(|(parent, _)| parent)
+        .unwrap_or("")
+}
+
+/// Build the path the frontend uses for a member
+fn virtual_path(archive: &Path, inner: &str) -> PathBuf {
+    inner
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .fold(archive.to_path_buf(), |p, s| p.join(s))
+}
+
+fn to_file_entry(archive: &Path, entry: &ArchiveEntry) -> FileEntry {
+    let name = entry.path.rsplit('/').next().unwrap_or(&entry.path);
+
+    FileEntry {
+        name: name.to_string(),
+        path: virtual_path(archive, &entry.path)
+            .to_string_lossy()
+            .to_string(),
+        is_dir: entry.is_dir,
+        size: if entry.is_dir { 0 } else { entry.size },
+        modified: entry
+            .modified
+            .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| String::from("-")),
+        extension: Path::new(name)
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default(),
+        is_cloud_placeholder: false,
+        metadata: None,
+    }
+}
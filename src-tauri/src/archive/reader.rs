@@ -0,0 +1,350 @@
+// Format-specific access to archive contents.
+// Everything is streamed from the archive file; nothing is unpacked to disk.
+
+use crate::error::{AppError, PathContext};
+use chrono::{DateTime, Local, NaiveDateTime};
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::{Component, Path};
+
+/// Supported archive formats
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+    TarXz,
+    TarZst,
+    SevenZ,
+}
+
+/// File name suffixes for each format, longest first so ".tar.gz" wins over ".gz"
+pub const FORMAT_SUFFIXES: &[(&str, ArchiveFormat)] = &[
+    (".tar.gz", ArchiveFormat::TarGz),
+    (".tar.xz", ArchiveFormat::TarXz),
+    (".tar.zst", ArchiveFormat::TarZst),
+    (".tgz", ArchiveFormat::TarGz),
+    (".txz", ArchiveFormat::TarXz),
+    (".tzst", ArchiveFormat::TarZst),
+    (".tar", ArchiveFormat::Tar),
+    (".zip", ArchiveFormat::Zip),
+    (".7z", ArchiveFormat::SevenZ),
+];
+
+impl ArchiveFormat {
+    /// Detect the format from the file name
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        FORMAT_SUFFIXES
+            .iter()
+            .find(|(suffix, _)| name.ends_with(suffix) && name.len() > suffix.len())
+            .map(|(_, format)| *format)
+    }
+}
+
+/// A single entry as recorded in the archive
+#[derive(Clone)]
+pub struct ArchiveEntry {
+    pub path: String, // '/'-separated, no leading or trailing slash
+    pub is_dir: bool,
+    pub size: u64,
+    pub modified: Option<NaiveDateTime>, // Local time
+    pub copies: usize,                   // Members recorded under this path; the last one counts
+}
+
+/// A member as met while streaming through the archive
+pub struct Member {
+    pub name: String,         // As recorded in the archive
+    pub path: Option<String>, // Normalized; None if the name would escape the root
+    pub is_dir: bool,
+    pub modified: Option<NaiveDateTime>, // Local time
+    pub mode: Option<u32>,               // Unix permission bits
+}
+
+/// Normalize a member name to "a/b/c": unify separators, drop "." and empty parts.
+/// Returns None for names that would escape the archive root, including
+/// drive prefixes like "C:" that would make a joined path absolute.
+pub fn normalize_member_path(name: &str) -> Option<String> {
+    let mut parts = Vec::new();
+
+    for part in name.split(['/', '\\']) {
+        match part {
+            "" | "." => continue,
+            ".." => return None,
+            _ if part.contains('\0') => return None,
+            _ => {
+                let mut components = Path::new(part).components();
+                match (components.next(), components.next()) {
+                    (Some(Component::Normal(_)), None) => parts.push(part),
+                    _ => return None,
+                }
+            }
+        }
+    }
+
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join("/"))
+    }
+}
+
+fn local_time(time: std::time::SystemTime) -> NaiveDateTime {
+    DateTime::<Local>::from(time).naive_local()
+}
+
+/// Open a tar-based archive behind the matching decompressor
+fn open_tar(path: &Path, format: ArchiveFormat) -> io::Result<tar::Archive<Box<dyn Read>>> {
+    let file = BufReader::new(File::open(path)?);
+
+    let reader: Box<dyn Read> = match format {
+        ArchiveFormat::TarGz => Box::new(flate2::read::MultiGzDecoder::new(file)),
+        ArchiveFormat::TarXz => Box::new(lzma_rust2::XzReader::new(file, true)),
+        ArchiveFormat::TarZst => Box::new(zstd::stream::read::Decoder::with_buffer(file)?),
+        _ => Box::new(file),
+    };
+
+    Ok(tar::Archive::new(reader))
+}
+
+/// Read the table of contents of an archive
+pub fn read_entries(path: &Path, format: ArchiveFormat) -> Result<Vec<ArchiveEntry>, AppError> {
+    let mut entries = Vec::new();
+
+    match format {
+        ArchiveFormat::Zip => {
+            let file = BufReader::new(File::open(path).at(path)?);
+            let mut zip = zip::ZipArchive::new(file).map_err(|e| zip_error(e, path))?;
+
+            for i in 0..zip.len() {
+                // Raw access reads headers only, so encrypted entries can still be listed
+                let entry = zip.by_index_raw(i).map_err(|e| zip_error(e, path))?;
+                if let Some(member_path) = normalize_member_path(entry.name()) {
+                    entries.push(ArchiveEntry {
+                        path: member_path,
+                        is_dir: entry.is_dir(),
+                        size: entry.size(),
+                        modified: entry
+                            .last_modified()
+                            .and_then(|t| NaiveDateTime::try_from(t).ok()),
+                        copies: 1,
+                    });
+                }
+            }
+        }
+        ArchiveFormat::Tar
+        | ArchiveFormat::TarGz
+        | ArchiveFormat::TarXz
+        | ArchiveFormat::TarZst => {
+            let mut tar = open_tar(path, format).at(path)?;
+
+            for entry in tar.entries().at(path)? {
+                let entry = entry.at(path)?;
+                let header = entry.header();
+                let entry_type = header.entry_type();
+
+                if !entry_type.is_file() && !entry_type.is_dir() {
+                    continue; // Links, devices, ...
+                }
+
+                let name = entry.path().at(path)?;
+                if let Some(member_path) = normalize_member_path(&name.to_string_lossy()) {
+                    entries.push(ArchiveEntry {
+                        path: member_path,
+                        is_dir: entry_type.is_dir(),
+                        size: header.size().unwrap_or(0),
+                        modified: header
+                            .mtime()
+                            .ok()
+                            .and_then(|t| DateTime::from_timestamp(t as i64, 0))
+                            .map(|t| t.with_timezone(&Local).naive_local()),
+                        copies: 1,
+                    });
+                }
+            }
+        }
+        ArchiveFormat::SevenZ => {
+            let reader = sevenz_rust::SevenZReader::open(path, sevenz_rust::Password::empty())
+                .map_err(|e| sevenz_error(e, path))?;
+
+            for entry in &reader.archive().files {
+                if let Some(member_path) = normalize_member_path(entry.name()) {
+                    entries.push(ArchiveEntry {
+                        path: member_path,
+                        is_dir: entry.is_directory(),
+                        size: entry.size(),
+                        modified: entry
+                            .has_last_modified_date
+                            .then(|| local_time(entry.last_modified_date().into())),
+                        copies: 1,
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Stream the members wanted returns true for in archive order, calling
+/// visit(member, content). Return Ok(false) from visit to stop early; an error
+/// from visit is passed through. The password is used for encrypted zip and
+/// 7z members. Zip members that aren't wanted aren't opened, so an encrypted
+/// one doesn't keep the others from being read without a password.
+pub fn for_each_member(
+    path: &Path,
+    format: ArchiveFormat,
+    password: Option<&str>,
+    wanted: &dyn Fn(&Member) -> bool,
+    visit: &mut dyn FnMut(&Member, &mut dyn Read) -> Result<bool, AppError>,
+) -> Result<(), AppError> {
+    match format {
+        ArchiveFormat::Zip => {
+            let file = BufReader::new(File::open(path).at(path)?);
+            let mut zip = zip::ZipArchive::new(file).map_err(|e| zip_error(e, path))?;
+
+            for i in 0..zip.len() {
+                // The header alone, without decrypting or decompressing
+                let member = {
+                    let entry = zip.by_index_raw(i).map_err(|e| zip_error(e, path))?;
+                    Member {
+                        name: entry.name().to_string(),
+                        path: normalize_member_path(entry.name()),
+                        is_dir: entry.is_dir(),
+                        modified: entry
+                            .last_modified()
+                            .and_then(|t| NaiveDateTime::try_from(t).ok()),
+                        mode: entry.unix_mode(),
+                    }
+                };
+                if !wanted(&member) {
+                    continue;
+                }
+
+                let mut entry = match password {
+                    Some(password) => zip.by_index_decrypt(i, password.as_bytes()),
+                    None => zip.by_index(i),
+                }
+                .map_err(|e| zip_error(e, path))?;
+                if !visit(&member, &mut entry)? {
+                    break;
+                }
+            }
+        }
+        ArchiveFormat::Tar
+        | ArchiveFormat::TarGz
+        | ArchiveFormat::TarXz
+        | ArchiveFormat::TarZst => {
+            let mut tar = open_tar(path, format).at(path)?;
+
+            for entry in tar.entries().at(path)? {
+                let mut entry = entry.at(path)?;
+                let header = entry.header();
+                let entry_type = header.entry_type();
+
+                if !entry_type.is_file() && !entry_type.is_dir() {
+                    continue;
+                }
+
+                let name = entry.path().at(path)?.to_string_lossy().to_string();
+                let member = Member {
+                    path: normalize_member_path(&name),
+                    name,
+                    is_dir: entry_type.is_dir(),
+                    modified: header
+                        .mtime()
+                        .ok()
+                        .and_then(|t| DateTime::from_timestamp(t as i64, 0))
+                        .map(|t| t.with_timezone(&Local).naive_local()),
+                    mode: header.mode().ok(),
+                };
+                if !wanted(&member) {
+                    continue;
+                }
+                if !visit(&member, &mut entry)? {
+                    break;
+                }
+            }
+        }
+        ArchiveFormat::SevenZ => {
+            let password = password
+                .map(sevenz_rust::Password::from)
+                .unwrap_or_else(sevenz_rust::Password::empty);
+            let mut reader = sevenz_rust::SevenZReader::open(path, password)
+                .map_err(|e| sevenz_error(e, path))?;
+            let mut stopped = false;
+            let mut failure = None; // Error from visit, which 7z can't carry
+
+            reader
+                .for_each_entries(|entry, content| {
+                    if stopped {
+                        return Ok(false);
+                    }
+                    let member = Member {
+                        name: entry.name().to_string(),
+                        path: normalize_member_path(entry.name()),
+                        is_dir: entry.is_directory(),
+                        modified: entry
+                            .has_last_modified_date
+                            .then(|| local_time(entry.last_modified_date().into())),
+                        mode: None,
+                    };
+                    if !wanted(&member) {
+                        io::copy(content, &mut io::sink())?;
+                        return Ok(true);
+                    }
+                    match visit(&member, content) {
+                        Ok(more) => stopped = !more,
+                        Err(e) => {
+                            failure = Some(e);
+                            return Ok(false);
+                        }
+                    }
+                    // Members of a solid block share one stream: skip what wasn't read
+                    if !stopped {
+                        io::copy(content, &mut io::sink())?;
+                    }
+                    Ok(!stopped)
+                })
+                .map_err(|e| sevenz_error(e, path))?;
+
+            if let Some(e) = failure {
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn zip_error(error: zip::result::ZipError, path: &Path) -> AppError {
+    use zip::result::ZipError;
+
+    let path_string = path.to_string_lossy().to_string();
+    match error {
+        ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED) => {
+            AppError::PasswordRequired { path: path_string }
+        }
+        ZipError::InvalidPassword => AppError::WrongPassword { path: path_string },
+        ZipError::Io(e) => AppError::io(e, path),
+        other => AppError::InvalidArchive {
+            path: path_string,
+            message: other.to_string(),
+        },
+    }
+}
+
+fn sevenz_error(error: sevenz_rust::Error, path: &Path) -> AppError {
+    use sevenz_rust::Error;
+
+    let path_string = path.to_string_lossy().to_string();
+    match error {
+        Error::PasswordRequired => AppError::PasswordRequired { path: path_string },
+        Error::MaybeBadPassword(_) => AppError::WrongPassword { path: path_string },
+        Error::Io(e, _) | Error::FileOpen(e, _) => AppError::io(e, path),
+        other => AppError::InvalidArchive {
+            path: path_string,
+            message: other.to_string(),
+        },
+    }
+}
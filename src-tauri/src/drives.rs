@@ -0,0 +1,397 @@
+// Drives for the sidebar: the mounted file systems on Linux (from
+// /proc/self/mountinfo) and macOS (getmntinfo), and the drive letters on
+// Windows, each with its size and free space. Kernel and container plumbing
+// (proc, sysfs, cgroups, Docker's overlay layers, snap images...) is left out,
+// as are the mounts under /proc, /sys, /dev and /run that only back it.
+// Network shares aren't asked for their sizes: one that has gone away would
+// hold up the whole list.
+
+use serde::Serialize;
+
+/// File systems that aren't storage anyone browses
+#[cfg(target_os = "linux")]
+const PSEUDO_FILESYSTEMS: &[&str] = &[
+    "autofs",
+    "binfmt_misc",
+    "bpf",
+    "cgroup",
+    "cgroup2",
+    "configfs",
+    "debugfs",
+    "devpts",
+    "devtmpfs",
+    "efivarfs",
+    "fuse.gvfsd-fuse",
+    "fuse.portal",
+    "fusectl",
+    "hugetlbfs",
+    "mqueue",
+    "nsfs",
+    "overlay", // Except as the root of a container
+    "proc",
+    "pstore",
+    "ramfs",
+    "rpc_pipefs",
+    "securityfs",
+    "selinuxfs",
+    "squashfs", // Snap packages
+    "sysfs",
+    "tracefs",
+];
+
+/// File systems on another machine, whose sizes may never come back
+#[cfg(target_os = "linux")]
+const REMOTE_FILESYSTEMS: &[&str] = &[
+    "9p",
+    "afs",
+    "ceph",
+    "cifs",
+    "davfs",
+    "fuse.rclone",
+    "fuse.s3fs",
+    "fuse.sshfs",
+    "glusterfs",
+    "ncpfs",
+    "nfs",
+    "nfs4",
+    "smb3",
+    "smbfs",
+];
+
+/// Mounts under these belong to the system, apart from removable media under
+/// /run/media; tmpfs elsewhere (/tmp, a RAM disk) is the user's
+#[cfg(target_os = "linux")]
+const SYSTEM_MOUNT_DIRS: &[&str] = &[
+    "/proc",
+    "/sys",
+    "/dev",
+    "/run",
+    "/snap",
+    "/var/lib/docker",
+    "/var/lib/containers",
+];
+
+/// Represents a drive on the system
+#[derive(Serialize)]
+pub struct DriveInfo {
+    pub name: String,
+    pub path: String,
+    pub device: String, // Block device, network share or drive letter
+    pub file_system: String,
+    pub label: Option<String>,
+    pub read_only: bool,
+    // None when unknown, as for network shares
+    pub total_space: Option<u64>,
+    pub free_space: Option<u64>,
+    pub available_space: Option<u64>, // Free space usable without privileges
+}
+
+/// Get the mounted drives, the root file system first
+#[tauri::command]
+pub async fn get_drives() -> Vec<DriveInfo> {
+    // statvfs can wait on a network share that has gone away
+    tauri::async_runtime::spawn_blocking(drives)
+        .await
+        .unwrap_or_default()
+}
+
+#[cfg(target_os = "linux")]
+fn drives() -> Vec<DriveInfo> {
+    let Ok(mountinfo) = std::fs::read_to_string("/proc/self/mountinfo") else {
+        return Vec::new();
+    };
+    let labels = labels();
+
+    let mut drives: Vec<DriveInfo> = Vec::new();
+    for line in mountinfo.lines() {
+        // ID, parent ID, major:minor, root, mount point, options, optional
+        // fields, then after the separator type, source and superblock options
+        let Some((mount, filesystem)) = line.split_once(" - ") else {
+            continue;
+        };
+        let mount: Vec<&str> = mount.split(' ').collect();
+        let filesystem: Vec<&str> = filesystem.split(' ').collect();
+        let (Some(path), Some(options), Some(file_system), Some(device)) = (
+            mount.get(4).map(|p| unescape(p)),
+            mount.get(5),
+            filesystem.first(),
+            filesystem.get(1).map(|d| unescape(d)),
+        ) else {
+            continue;
+        };
+
+        let system_dir = SYSTEM_MOUNT_DIRS.iter().any(|dir| {
+            (path == *dir || path.starts_with(&format!("{dir}/")))
+                && !path.starts_with("/run/media/")
+        });
+        if system_dir || (PSEUDO_FILESYSTEMS.contains(file_system) && path != "/") {
+            continue;
+        }
+        // Mounted over an earlier mount: only the last one is visible
+        drives.retain(|d| d.path != path);
+
+        let label = Some(&device)
+            .filter(|device| device.starts_with('/'))
+            .and_then(|device| std::fs::canonicalize(device).ok())
+            .and_then(|device| labels.iter().find(|(d, _)| *d == device))
+            .map(|(_, label)| label.clone());
+        let read_only = options.split(',').any(|o| o == "ro");
+        let remote = REMOTE_FILESYSTEMS.contains(file_system);
+        drives.push(drive(
+            path,
+            device,
+            file_system.to_string(),
+            label,
+            read_only,
+            remote,
+        ));
+    }
+    drives.sort_by(|a, b| (a.path != "/", &a.path).cmp(&(b.path != "/", &b.path)));
+    drives
+}
+
+/// Devices by file system label, from udev's /dev/disk/by-label links
+#[cfg(target_os = "linux")]
+fn labels() -> Vec<(std::path::PathBuf, String)> {
+    let Ok(entries) = std::fs::read_dir("/dev/disk/by-label") else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let device = std::fs::canonicalize(entry.path()).ok()?;
+            // udev escapes spaces and slashes as \x20 and \x2f
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let mut label = String::new();
+            let mut rest = name.as_str();
+            while let Some(at) = rest.find("\\x") {
+                label.push_str(&rest[..at]);
+                match rest
+                    .get(at + 2..at + 4)
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    Some(byte) => {
+                        label.push(byte as char);
+                        rest = &rest[at + 4..];
+                    }
+                    None => {
+                        label.push_str("\\x");
+                        rest = &rest[at + 2..];
+                    }
+                }
+            }
+            label.push_str(rest);
+            Some((device, label))
+        })
+        .collect()
+}
+
+/// Undo mountinfo's octal escapes (\040 for a space, and so on)
+#[cfg(target_os = "linux")]
+fn unescape(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let octal = bytes
+            .get(i + 1..i + 4)
+            .filter(|_| bytes[i] == b'\\')
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u8::from_str_radix(digits, 8).ok());
+        match octal {
+            Some(byte) => {
+                out.push(byte);
+                i += 4;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(target_os = "macos")]
+fn drives() -> Vec<DriveInfo> {
+    use std::ffi::CStr;
+
+    let mut mounts: *mut libc::statfs = std::ptr::null_mut();
+    let count = unsafe { libc::getmntinfo(&mut mounts, libc::MNT_NOWAIT) };
+    if count <= 0 || mounts.is_null() {
+        return Vec::new();
+    }
+    // Owned by the system and valid until the next call
+    let mounts = unsafe { std::slice::from_raw_parts(mounts, count as usize) };
+
+    let mut drives: Vec<DriveInfo> = mounts
+        .iter()
+        .filter(|m| {
+            // System volumes (VM, Preboot, Update...) are marked not to browse
+            let file_system = unsafe { CStr::from_ptr(m.f_fstypename.as_ptr()) };
+            m.f_flags & libc::MNT_DONTBROWSE as u32 == 0
+                && !matches!(file_system.to_bytes(), b"devfs" | b"autofs")
+        })
+        .map(|m| {
+            let text = |field: &[libc::c_char]| {
+                unsafe { CStr::from_ptr(field.as_ptr()) }
+                    .to_string_lossy()
+                    .into_owned()
+            };
+            let path = text(&m.f_mntonname);
+            // Volumes are mounted under their name
+            let label = path.strip_prefix("/Volumes/").map(str::to_string);
+            drive(
+                path,
+                text(&m.f_mntfromname),
+                text(&m.f_fstypename),
+                label,
+                m.f_flags & libc::MNT_RDONLY as u32 != 0,
+                m.f_flags & libc::MNT_LOCAL as u32 == 0,
+            )
+        })
+        .collect();
+    drives.sort_by(|a, b| (a.path != "/", &a.path).cmp(&(b.path != "/", &b.path)));
+    drives
+}
+
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+fn drives() -> Vec<DriveInfo> {
+    vec![drive(
+        "/".to_string(),
+        String::new(),
+        String::new(),
+        None,
+        false,
+        false,
+    )]
+}
+
+/// A mounted file system, with its sizes from statvfs unless it's remote
+#[cfg(unix)]
+fn drive(
+    path: String,
+    device: String,
+    file_system: String,
+    label: Option<String>,
+    read_only: bool,
+    remote: bool,
+) -> DriveInfo {
+    let name = match (&label, path.rsplit('/').find(|c| !c.is_empty())) {
+        (Some(label), _) => label.clone(),
+        (None, Some(last)) => last.to_string(),
+        (None, None) => "File System".to_string(),
+    };
+
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    let statted = !remote
+        && std::ffi::CString::new(path.as_str())
+            .is_ok_and(|c_path| unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } == 0);
+    let (total_space, free_space, available_space, read_only) = if statted {
+        let block = stat.f_frsize as u64;
+        (
+            Some(stat.f_blocks as u64 * block),
+            Some(stat.f_bfree as u64 * block),
+            Some(stat.f_bavail as u64 * block),
+            read_only || stat.f_flag & libc::ST_RDONLY != 0,
+        )
+    } else {
+        (None, None, None, read_only)
+    };
+
+    DriveInfo {
+        name,
+        path,
+        device,
+        file_system,
+        label,
+        read_only,
+        total_space,
+        free_space,
+        available_space,
+    }
+}
+
+#[cfg(windows)]
+fn drives() -> Vec<DriveInfo> {
+    use windows::core::PCWSTR;
+    use windows::Win32::Storage::FileSystem::{
+        GetDiskFreeSpaceExW, GetDriveTypeW, GetLogicalDrives, GetVolumeInformationW,
+    };
+
+    const DRIVE_NO_ROOT_DIR: u32 = 1;
+    const DRIVE_REMOTE: u32 = 4;
+    const FILE_READ_ONLY_VOLUME: u32 = 0x00080000;
+
+    let letters = unsafe { GetLogicalDrives() };
+    let mut drives = Vec::new();
+    for (bit, letter) in ('A'..='Z').enumerate() {
+        if letters & (1 << bit) == 0 {
+            continue;
+        }
+        let path = format!("{letter}:\\");
+        let wide: Vec<u16> = path.encode_utf16().chain(Some(0)).collect();
+        let root = PCWSTR(wide.as_ptr());
+        let drive_type = unsafe { GetDriveTypeW(root) };
+        if drive_type == DRIVE_NO_ROOT_DIR {
+            continue;
+        }
+
+        // Both fail for a card reader or optical drive with nothing in it, and
+        // a mapped network drive isn't asked, since it may not answer
+        let remote = drive_type == DRIVE_REMOTE;
+        let mut label = [0u16; 261];
+        let mut file_system = [0u16; 261];
+        let mut flags = 0u32;
+        let has_volume = !remote
+            && unsafe {
+                GetVolumeInformationW(
+                    root,
+                    Some(&mut label),
+                    None,
+                    None,
+                    Some(&mut flags),
+                    Some(&mut file_system),
+                )
+            }
+            .is_ok();
+        let (mut available_space, mut total_space, mut free_space) = (0u64, 0u64, 0u64);
+        let sized = !remote
+            && unsafe {
+                GetDiskFreeSpaceExW(
+                    root,
+                    Some(&mut available_space),
+                    Some(&mut total_space),
+                    Some(&mut free_space),
+                )
+            }
+            .is_ok();
+
+        let text = |buffer: &[u16]| {
+            let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+            String::from_utf16_lossy(&buffer[..len])
+        };
+        let label = Some(text(&label)).filter(|l| has_volume && !l.is_empty());
+        let kind = if remote {
+            "Network Drive"
+        } else {
+            "Local Disk"
+        };
+        drives.push(DriveInfo {
+            name: format!("{} ({letter}:)", label.as_deref().unwrap_or(kind)),
+            path,
+            device: format!("{letter}:"),
+            file_system: if has_volume {
+                text(&file_system)
+            } else {
+                String::new()
+            },
+            label,
+            read_only: has_volume && flags & FILE_READ_ONLY_VOLUME != 0,
+            total_space: sized.then_some(total_space),
+            free_space: sized.then_some(free_space),
+            available_space: sized.then_some(available_space),
+        });
+    }
+    drives
+}
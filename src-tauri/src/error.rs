@@ -0,0 +1,318 @@
+// The error type returned by every command.
+// Serialized as { kind, message, path?, ... }: the UI picks a localized text
+// or a recovery action by kind and falls back to the English message.
+
+use crate::validation::{ValidationCode, ValidationError};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use std::fmt;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+#[derive(Debug, Clone)]
+pub enum AppError {
+    NotFound {
+        path: String,
+    },
+    AlreadyExists {
+        path: String,
+    },
+    PermissionDenied {
+        path: String,
+    },
+    NotADirectory {
+        path: String,
+    },
+    IsADirectory {
+        path: String,
+    },
+    DirectoryNotEmpty {
+        path: String,
+    },
+    ReadOnly {
+        path: String,
+    }, // Read-only file system
+    DiskFull {
+        path: String,
+    }, // No space left on the device
+    Busy {
+        path: String,
+    }, // In use by another process
+    InvalidName {
+        path: String,
+        code: ValidationCode,
+        message: String,
+    },
+    Unsupported {
+        path: String,
+        message: String,
+    }, // File type or operation not supported
+    InvalidArchive {
+        path: String,
+        message: String,
+    },
+    PasswordRequired {
+        path: String,
+    },
+    WrongPassword {
+        path: String,
+    },
+    SourceNotRemoved {
+        path: String,
+        destination: String,
+    }, // Move completed except deleting the source
+    InvalidInput {
+        message: String,
+    }, // Bad argument, e.g. an unknown format
+    Cancelled,
+    Io {
+        path: Option<String>,
+        errno: Option<i32>,
+        message: String,
+    },
+    Other {
+        message: String,
+    },
+}
+
+fn display(path: impl AsRef<Path>) -> String {
+    path.as_ref().to_string_lossy().to_string()
+}
+
+impl AppError {
+    /// Classify an I/O error that happened on path
+    pub fn io(error: io::Error, path: impl AsRef<Path>) -> AppError {
+        let path = display(path);
+
+        match error.kind() {
+            io::ErrorKind::NotFound => AppError::NotFound { path },
+            io::ErrorKind::AlreadyExists => AppError::AlreadyExists { path },
+            io::ErrorKind::PermissionDenied => AppError::PermissionDenied { path },
+            io::ErrorKind::NotADirectory => AppError::NotADirectory { path },
+            io::ErrorKind::IsADirectory => AppError::IsADirectory { path },
+            io::ErrorKind::DirectoryNotEmpty => AppError::DirectoryNotEmpty { path },
+            io::ErrorKind::ReadOnlyFilesystem => AppError::ReadOnly { path },
+            io::ErrorKind::StorageFull => AppError::DiskFull { path },
+            io::ErrorKind::ResourceBusy => AppError::Busy { path },
+            _ => AppError::Io {
+                path: Some(path),
+                errno: error.raw_os_error(),
+                message: error.to_string(),
+            },
+        }
+    }
+
+    pub fn not_found(path: impl AsRef<Path>) -> AppError {
+        AppError::NotFound {
+            path: display(path),
+        }
+    }
+
+    pub fn already_exists(path: impl AsRef<Path>) -> AppError {
+        AppError::AlreadyExists {
+            path: display(path),
+        }
+    }
+
+    pub fn not_a_directory(path: impl AsRef<Path>) -> AppError {
+        AppError::NotADirectory {
+            path: display(path),
+        }
+    }
+
+    pub fn is_a_directory(path: impl AsRef<Path>) -> AppError {
+        AppError::IsADirectory {
+            path: display(path),
+        }
+    }
+
+    pub fn unsupported(path: impl AsRef<Path>, message: impl Into<String>) -> AppError {
+        AppError::Unsupported {
+            path: display(path),
+            message: message.into(),
+        }
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> AppError {
+        AppError::InvalidInput {
+            message: message.into(),
+        }
+    }
+
+    pub fn other(message: impl ToString) -> AppError {
+        AppError::Other {
+            message: message.to_string(),
+        }
+    }
+
+    /// Run f with a panic turned into an error, so a decoder tripping over a
+    /// malformed file fails that file instead of taking its thread down
+    pub fn catch<T>(f: impl FnOnce() -> Result<T, AppError>) -> Result<T, AppError> {
+        panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            Err(AppError::other(format!("Internal error: {message}")))
+        })
+    }
+
+    /// Stable identifier of the variant, sent as "kind"
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AppError::NotFound { .. } => "not_found",
+            AppError::AlreadyExists { .. } => "already_exists",
+            AppError::PermissionDenied { .. } => "permission_denied",
+            AppError::NotADirectory { .. } => "not_a_directory",
+            AppError::IsADirectory { .. } => "is_a_directory",
+            AppError::DirectoryNotEmpty { .. } => "directory_not_empty",
+            AppError::ReadOnly { .. } => "read_only",
+            AppError::DiskFull { .. } => "disk_full",
+            AppError::Busy { .. } => "busy",
+            AppError::InvalidName { .. } => "invalid_name",
+            AppError::Unsupported { .. } => "unsupported",
+            AppError::InvalidArchive { .. } => "invalid_archive",
+            AppError::PasswordRequired { .. } => "password_required",
+            AppError::WrongPassword { .. } => "wrong_password",
+            AppError::SourceNotRemoved { .. } => "source_not_removed",
+            AppError::InvalidInput { .. } => "invalid_input",
+            AppError::Cancelled => "cancelled",
+            AppError::Io { .. } => "io",
+            AppError::Other { .. } => "other",
+        }
+    }
+
+    /// The affected path, if any
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            AppError::NotFound { path }
+            | AppError::AlreadyExists { path }
+            | AppError::PermissionDenied { path }
+            | AppError::NotADirectory { path }
+            | AppError::IsADirectory { path }
+            | AppError::DirectoryNotEmpty { path }
+            | AppError::ReadOnly { path }
+            | AppError::DiskFull { path }
+            | AppError::Busy { path }
+            | AppError::InvalidName { path, .. }
+            | AppError::Unsupported { path, .. }
+            | AppError::InvalidArchive { path, .. }
+            | AppError::PasswordRequired { path }
+            | AppError::WrongPassword { path }
+            | AppError::SourceNotRemoved { path, .. } => Some(path),
+            AppError::Io { path, .. } => path.as_deref(),
+            AppError::InvalidInput { .. } | AppError::Cancelled | AppError::Other { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::NotFound { path } => write!(f, "Path does not exist: {}", path),
+            AppError::AlreadyExists { path } => write!(f, "Item already exists: {}", path),
+            AppError::PermissionDenied { path } => write!(f, "Permission denied: {}", path),
+            AppError::NotADirectory { path } => write!(f, "Path is not a directory: {}", path),
+            AppError::IsADirectory { path } => write!(f, "Path is a directory: {}", path),
+            AppError::DirectoryNotEmpty { path } => write!(f, "Folder is not empty: {}", path),
+            AppError::ReadOnly { path } => write!(f, "Location is read-only: {}", path),
+            AppError::DiskFull { path } => write!(f, "Not enough disk space: {}", path),
+            AppError::Busy { path } => write!(f, "Item is in use: {}", path),
+            AppError::InvalidName { message, .. } => f.write_str(message),
+            AppError::Unsupported { message, .. } => f.write_str(message),
+            AppError::InvalidArchive { path, message } => {
+                write!(f, "Cannot read archive {}: {}", path, message)
+            }
+            AppError::PasswordRequired { path } => write!(f, "Password required: {}", path),
+            AppError::WrongPassword { path } => write!(f, "Wrong password: {}", path),
+            AppError::SourceNotRemoved { path, destination } => write!(
+                f,
+                "Moved to {}, but the source could not be fully removed. \
+                 The destination is complete; delete {} manually.",
+                destination, path
+            ),
+            AppError::InvalidInput { message } => f.write_str(message),
+            AppError::Cancelled => f.write_str("Cancelled"),
+            AppError::Io {
+                path: Some(path),
+                message,
+                ..
+            } => write!(f, "{}: {}", message, path),
+            AppError::Io { message, .. } => f.write_str(message),
+            AppError::Other { message } => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("kind", self.kind())?;
+        map.serialize_entry("message", &self.to_string())?;
+        if let Some(path) = self.path() {
+            map.serialize_entry("path", path)?;
+        }
+
+        match self {
+            AppError::InvalidName { code, .. } => map.serialize_entry("code", code)?,
+            AppError::SourceNotRemoved { destination, .. } => {
+                map.serialize_entry("destination", destination)?
+            }
+            AppError::Io { errno, .. } => map.serialize_entry("errno", errno)?,
+            _ => {}
+        }
+
+        map.end()
+    }
+}
+
+/// Attach the affected path to I/O results: `fs::read_dir(p).at(p)?`
+pub trait PathContext<T> {
+    fn at(self, path: impl AsRef<Path>) -> Result<T, AppError>;
+}
+
+impl<T> PathContext<T> for io::Result<T> {
+    fn at(self, path: impl AsRef<Path>) -> Result<T, AppError> {
+        self.map_err(|e| AppError::io(e, path))
+    }
+}
+
+impl From<ValidationError> for AppError {
+    fn from(error: ValidationError) -> Self {
+        AppError::InvalidName {
+            path: error.value,
+            code: error.code,
+            message: error.message,
+        }
+    }
+}
+
+impl From<walkdir::Error> for AppError {
+    fn from(error: walkdir::Error) -> Self {
+        let path = error.path().map(Path::to_path_buf).unwrap_or_default();
+        match error.into_io_error() {
+            Some(io_error) => AppError::io(io_error, path),
+            // Symlink loop
+            None => AppError::Io {
+                path: Some(display(path)),
+                errno: None,
+                message: "File system loop found".to_string(),
+            },
+        }
+    }
+}
+
+impl From<tauri::Error> for AppError {
+    fn from(error: tauri::Error) -> Self {
+        AppError::other(error)
+    }
+}
+
+impl From<image::ImageError> for AppError {
+    fn from(error: image::ImageError) -> Self {
+        AppError::other(error)
+    }
+}
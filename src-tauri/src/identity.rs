@@ -0,0 +1,233 @@
+// File identity and case sensitivity.
+// Two paths name the same entry when they lead to the same inode (Unix) or
+// file ID (Windows). That's how "README.md" is recognised as "readme.md" on
+// case-insensitive volumes, where a case-only rename has to go through a
+// temporary name.
+
+use crate::error::{AppError, PathContext};
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Identifies a file or folder on its volume. A final symlink is not followed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FileId {
+    volume: u64,
+    index: u64,
+}
+
+#[cfg(unix)]
+pub fn file_id(path: &Path) -> io::Result<FileId> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = fs::symlink_metadata(path)?;
+    Ok(FileId {
+        volume: metadata.dev(),
+        index: metadata.ino(),
+    })
+}
+
+#[cfg(windows)]
+pub fn file_id(path: &Path) -> io::Result<FileId> {
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::os::windows::io::AsRawHandle;
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::Storage::FileSystem::{
+        GetFileInformationByHandle, BY_HANDLE_FILE_INFORMATION, FILE_FLAG_BACKUP_SEMANTICS,
+        FILE_FLAG_OPEN_REPARSE_POINT,
+    };
+
+    // No access rights needed just to query; backup semantics allow folders
+    let file = fs::OpenOptions::new()
+        .access_mode(0)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS.0 | FILE_FLAG_OPEN_REPARSE_POINT.0)
+        .open(path)?;
+
+    let mut info = BY_HANDLE_FILE_INFORMATION::default();
+    unsafe { GetFileInformationByHandle(HANDLE(file.as_raw_handle()), &mut info) }
+        .map_err(io::Error::from)?;
+
+    Ok(FileId {
+        volume: info.dwVolumeSerialNumber as u64,
+        index: ((info.nFileIndexHigh as u64) << 32) | info.nFileIndexLow as u64,
+    })
+}
+
+/// How a destination path relates to the entry about to be renamed or moved there
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Target {
+    Free,     // Nothing there yet
+    Itself,   // src itself under the same name: nothing to do
+    CaseOnly, // src itself, spelled differently on a case-insensitive folder
+    Taken,    // Another entry
+}
+
+/// Find out whether dest is free, src itself, or taken by something else
+pub fn classify(src: &Path, dest: &Path) -> Target {
+    if fs::symlink_metadata(dest).is_err() {
+        return Target::Free;
+    }
+
+    let same_name = src.file_name() == dest.file_name();
+    if is_same_entry(src, dest) {
+        return if same_name {
+            Target::Itself
+        } else {
+            Target::CaseOnly
+        };
+    }
+
+    // File IDs aren't reliable everywhere (some network and FAT volumes), so
+    // fall back to the names when the folder ignores case
+    if let (Some(dir), Some(name), Some(src_name)) =
+        (dest.parent(), dest.file_name(), src.file_name())
+    {
+        if src.parent() == Some(dir)
+            && differ_only_in_case(src_name, name)
+            && !has_entry_named(dir, name)
+            && !case_sensitive(dir)
+        {
+            return Target::CaseOnly;
+        }
+    }
+
+    Target::Taken
+}
+
+/// Check if dest is src's own entry, possibly spelled differently: "README.md"
+/// for "readme.md" on a case-insensitive volume. A hard link to the same file
+/// under a name of its own is a separate entry and doesn't count.
+fn is_same_entry(src: &Path, dest: &Path) -> bool {
+    if src == dest {
+        return fs::symlink_metadata(src).is_ok();
+    }
+
+    let (Some(src_dir), Some(src_name), Some(dir), Some(name)) = (
+        src.parent(),
+        src.file_name(),
+        dest.parent(),
+        dest.file_name(),
+    ) else {
+        return false;
+    };
+
+    let same = |a: &Path, b: &Path| matches!((file_id(a), file_id(b)), (Ok(a), Ok(b)) if a == b);
+    if !same(src, dest) || !same(src_dir, dir) {
+        return false;
+    }
+
+    name == src_name || !has_entry_named(dir, name)
+}
+
+/// Check if a folder contains an entry with exactly this name, byte for byte
+fn has_entry_named(dir: &Path, name: &OsStr) -> bool {
+    fs::read_dir(dir)
+        .map(|entries| entries.flatten().any(|e| e.file_name() == name))
+        .unwrap_or(false)
+}
+
+/// Check whether names in a folder are case-sensitive.
+/// Looks for an existing entry under a case-swapped name, or failing that
+/// creates and removes a probe file. Unreadable folders get the usual
+/// behaviour of the platform.
+pub fn case_sensitive(dir: &Path) -> bool {
+    let platform_default = !cfg!(any(windows, target_os = "macos"));
+
+    let Ok(entries) = fs::read_dir(dir) else {
+        return platform_default;
+    };
+
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let swapped = swap_case(&name);
+        if swapped == name {
+            continue; // No cased letters
+        }
+
+        return match file_id(&dir.join(&swapped)) {
+            Ok(id) => file_id(&entry.path()).map_or(platform_default, |own| own != id),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => true,
+            Err(_) => platform_default,
+        };
+    }
+
+    let probe = dir.join(format!(
+        "{}case-probe-{}",
+        crate::transfer::PARTIAL_PREFIX,
+        std::process::id()
+    ));
+    if fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)
+        .is_err()
+    {
+        return platform_default;
+    }
+    let upper = dir.join(
+        probe
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_uppercase(),
+    );
+    let sensitive = fs::symlink_metadata(&upper).is_err();
+    let _ = fs::remove_file(&probe);
+
+    sensitive
+}
+
+fn swap_case(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_lowercase() {
+                c.to_uppercase().next().unwrap_or(c)
+            } else {
+                c.to_lowercase().next().unwrap_or(c)
+            }
+        })
+        .collect()
+}
+
+/// Check if two names would be the same entry in a case-insensitive folder
+fn differ_only_in_case(a: &OsStr, b: &OsStr) -> bool {
+    a != b && a.to_string_lossy().to_lowercase() == b.to_string_lossy().to_lowercase()
+}
+
+/// Rename src to another spelling of its own name ("readme.md" to
+/// "README.md"). Goes through a temporary name, since a direct rename is a
+/// no-op or an error on some case-insensitive file systems.
+pub fn rename_case_only(src: &Path, dest: &Path) -> Result<(), AppError> {
+    let dir = src.parent().unwrap_or(Path::new(""));
+    let temp: PathBuf = crate::get_unique_path(
+        dir,
+        &format!(
+            "{}{}-{}",
+            crate::transfer::PARTIAL_PREFIX,
+            std::process::id(),
+            src.file_name().unwrap_or_default().to_string_lossy()
+        ),
+        true,
+    );
+
+    fs::rename(src, &temp).at(src)?;
+    if let Err(e) = fs::rename(&temp, dest) {
+        // Put it back under its old name
+        let _ = fs::rename(&temp, src);
+        return Err(AppError::io(e, dest));
+    }
+
+    Ok(())
+}
+
+/// Check whether names in a folder are case-sensitive, so the UI can warn
+/// about names that would collide
+#[tauri::command]
+pub fn is_case_sensitive(path: String) -> Result<bool, AppError> {
+    let dir = Path::new(&path);
+    if !dir.is_dir() {
+        return Err(AppError::not_a_directory(dir));
+    }
+    Ok(case_sensitive(dir))
+}
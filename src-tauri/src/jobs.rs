@@ -0,0 +1,191 @@
+// Long-running background jobs (compressing, extracting, ...).
+// The frontend picks a job id, listens for "job-progress" events carrying it
+// and can call cancel_job at any time; the command itself resolves when the
+// job is done.
+
+use crate::error::AppError;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Minimum time between two progress events of the same job
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Error message of a job stopped by cancel_job
+pub const CANCELLED: &str = "Cancelled";
+
+/// Cancellation flags of the running jobs, by job id
+#[derive(Default)]
+pub struct JobRegistry {
+    jobs: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+/// Progress event payload
+#[derive(Serialize, Clone, Default)]
+pub struct JobProgress {
+    pub job_id: String,
+    pub processed_bytes: u64,
+    pub total_bytes: u64,
+    pub processed_items: u64,
+    pub total_items: u64,
+    pub current: String, // Item being processed
+}
+
+/// A running job: reports progress and tells the worker when to stop.
+/// Unregisters itself when dropped.
+pub struct Job {
+    app: AppHandle,
+    cancelled: Arc<AtomicBool>,
+    progress: Mutex<(JobProgress, Option<Instant>)>,
+}
+
+impl Job {
+    /// Register a new job under the id chosen by the frontend
+    pub fn start(app: &AppHandle, job_id: String) -> Result<Job, AppError> {
+        let registry = app.state::<JobRegistry>();
+        let mut jobs = registry.jobs.lock().unwrap_or_else(|e| e.into_inner());
+
+        if jobs.contains_key(&job_id) {
+            return Err(AppError::invalid_input(format!(
+                "Job is already running: {}",
+                job_id
+            )));
+        }
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        jobs.insert(job_id.clone(), cancelled.clone());
+
+        Ok(Job {
+            app: app.clone(),
+            cancelled,
+            progress: Mutex::new((
+                JobProgress {
+                    job_id,
+                    ..Default::default()
+                },
+                None,
+            )),
+        })
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Fail with an error once the job has been cancelled.
+    /// Not ErrorKind::Interrupted: io::copy and friends retry on that.
+    pub fn check_cancelled(&self) -> io::Result<()> {
+        if self.is_cancelled() {
+            Err(io::Error::other(CANCELLED))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Set the amount of work, once it is known
+    pub fn set_total(&self, bytes: u64, items: u64) {
+        self.update(true, |p| {
+            p.total_bytes = bytes;
+            p.total_items = items;
+        });
+    }
+
+    /// Start working on the next item
+    pub fn begin_item(&self, name: &str) {
+        self.update(false, |p| {
+            p.processed_items += 1;
+            p.current = name.to_string();
+        });
+    }
+
+    /// Count processed bytes of the current item
+    pub fn advance(&self, bytes: u64) {
+        self.update(false, |p| p.processed_bytes += bytes);
+    }
+
+    /// Wrap a reader so everything read through it counts as progress
+    /// and a cancelled job stops at the next read
+    pub fn track<R: Read>(&self, inner: R) -> Tracked<'_, R> {
+        Tracked { job: self, inner }
+    }
+
+    /// Send the final state, regardless of throttling
+    pub fn finish(&self) {
+        self.update(true, |_| {});
+    }
+
+    fn update(&self, force: bool, change: impl FnOnce(&mut JobProgress)) {
+        let mut guard = self.progress.lock().unwrap_or_else(|e| e.into_inner());
+        let (progress, last_emit) = &mut *guard;
+        change(progress);
+
+        let due = last_emit.is_none_or(|t| t.elapsed() >= PROGRESS_INTERVAL);
+        if force || due {
+            let _ = self.app.emit("job-progress", progress.clone());
+            *last_emit = Some(Instant::now());
+        }
+    }
+}
+
+impl Drop for Job {
+    fn drop(&mut self) {
+        let job_id = self
+            .progress
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .0
+            .job_id
+            .clone();
+        let registry = self.app.state::<JobRegistry>();
+        registry
+            .jobs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&job_id);
+    }
+}
+
+/// Reader that reports progress to its job, see Job::track
+pub struct Tracked<'a, R> {
+    job: &'a Job,
+    inner: R,
+}
+
+impl<R: Read> Read for Tracked<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.job.check_cancelled()?;
+        let n = self.inner.read(buf)?;
+        self.job.advance(n as u64);
+        Ok(n)
+    }
+}
+
+/// Turn a job's failure into the command error, reporting cancellation uniformly
+pub fn job_error(job: &Job, error: AppError) -> AppError {
+    if job.is_cancelled() {
+        AppError::Cancelled
+    } else {
+        error
+    }
+}
+
+/// Ask a running job to stop. Returns false if no such job is running.
+#[tauri::command]
+pub fn cancel_job(registry: tauri::State<JobRegistry>, job_id: String) -> bool {
+    match registry
+        .jobs
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&job_id)
+    {
+        Some(cancelled) => {
+            cancelled.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
@@ -0,0 +1,184 @@
+// Link creation: symbolic links, hard links and .desktop / .url shortcut files.
+// New links are named after their target and made unique like copies are.
+
+use crate::error::{AppError, PathContext};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Create a symbolic link to target inside the destination folder
+#[tauri::command]
+pub fn create_symlink(
+    target: String,
+    destination: String,
+    relative: Option<bool>,
+) -> Result<String, AppError> {
+    let (target_path, dest_dir, name) = validate_link_request(&target, &destination)?;
+    let is_dir = target_path.is_dir();
+    let link_path = crate::get_unique_path(dest_dir, &name, is_dir);
+
+    let link_target = if relative.unwrap_or(false) {
+        relative_path(target_path, dest_dir).ok_or_else(|| {
+            AppError::unsupported(
+                target_path,
+                "Cannot create a relative link between different drives",
+            )
+        })?
+    } else {
+        target_path.to_path_buf()
+    };
+
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(&link_target, &link_path).at(&link_path)?;
+
+    #[cfg(windows)]
+    {
+        // Windows needs to know up front whether the link points at a directory
+        let result = if is_dir {
+            std::os::windows::fs::symlink_dir(&link_target, &link_path)
+        } else {
+            std::os::windows::fs::symlink_file(&link_target, &link_path)
+        };
+        result.at(&link_path)?;
+    }
+
+    Ok(link_path.to_string_lossy().to_string())
+}
+
+/// Create a hard link to a file inside the destination folder
+#[tauri::command]
+pub fn create_hard_link(target: String, destination: String) -> Result<String, AppError> {
+    let (target_path, dest_dir, name) = validate_link_request(&target, &destination)?;
+
+    if target_path.is_dir() {
+        return Err(AppError::unsupported(
+            target_path,
+            "Hard links can only point to files",
+        ));
+    }
+
+    let link_path = crate::get_unique_path(dest_dir, &name, false);
+    fs::hard_link(target_path, &link_path).at(&link_path)?;
+
+    Ok(link_path.to_string_lossy().to_string())
+}
+
+/// Create a shortcut file to target inside the destination folder.
+/// `kind` is "desktop" (freedesktop .desktop) or "url" (Windows .url);
+/// defaults to the native format of the platform.
+#[tauri::command]
+pub fn create_shortcut(
+    target: String,
+    destination: String,
+    kind: Option<String>,
+) -> Result<String, AppError> {
+    let (target_path, dest_dir, name) = validate_link_request(&target, &destination)?;
+
+    let kind = kind.unwrap_or_else(|| {
+        if cfg!(target_os = "windows") {
+            "url".to_string()
+        } else {
+            "desktop".to_string()
+        }
+    });
+
+    let url = url::Url::from_file_path(target_path)
+        .map_err(|_| AppError::unsupported(target_path, "Cannot build a file URL"))?;
+
+    let (file_name, content) = match kind.as_str() {
+        "desktop" => {
+            let icon = if target_path.is_dir() {
+                "Icon=folder\n"
+            } else {
+                ""
+            };
+            (
+                format!("{}.desktop", name),
+                format!(
+                    "[Desktop Entry]\nVersion=1.0\nType=Link\nName={}\nURL={}\n{}",
+                    name, url, icon
+                ),
+            )
+        }
+        "url" => (
+            format!("{}.url", name),
+            format!("[InternetShortcut]\r\nURL={}\r\n", url),
+        ),
+        other => {
+            return Err(AppError::invalid_input(format!(
+                "Unknown shortcut kind: {}",
+                other
+            )))
+        }
+    };
+
+    let shortcut_path = crate::get_unique_path(dest_dir, &file_name, false);
+    fs::write(&shortcut_path, content).at(&shortcut_path)?;
+
+    Ok(shortcut_path.to_string_lossy().to_string())
+}
+
+/// Check that target exists and destination is a folder; returns the link's base name
+fn validate_link_request<'a>(
+    target: &'a str,
+    destination: &'a str,
+) -> Result<(&'a Path, &'a Path, String), AppError> {
+    let target_path = Path::new(target);
+    let dest_dir = Path::new(destination);
+
+    if !target_path.is_absolute() {
+        return Err(AppError::invalid_input(format!(
+            "Link target must be an absolute path: {}",
+            target
+        )));
+    }
+
+    if !target_path.exists() {
+        return Err(AppError::not_found(target_path));
+    }
+
+    if !dest_dir.is_dir() {
+        return Err(AppError::not_a_directory(dest_dir));
+    }
+
+    let name = target_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        // Roots like "C:\" or "/" have no file name
+        .unwrap_or_else(|| "Link".to_string());
+
+    Ok((target_path, dest_dir, name))
+}
+
+/// Express target relative to base_dir (e.g. "../data/set.csv").
+/// Returns None when the two paths share no root, e.g. different drives.
+fn relative_path(target: &Path, base_dir: &Path) -> Option<PathBuf> {
+    let target = target.canonicalize().ok()?;
+    let base = base_dir.canonicalize().ok()?;
+
+    let target_parts: Vec<Component> = target.components().collect();
+    let base_parts: Vec<Component> = base.components().collect();
+
+    if target_parts.first() != base_parts.first() {
+        return None;
+    }
+
+    let common = target_parts
+        .iter()
+        .zip(&base_parts)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut relative = PathBuf::new();
+    for _ in common..base_parts.len() {
+        relative.push("..");
+    }
+    for part in &target_parts[common..] {
+        relative.push(part.as_os_str());
+    }
+
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
+    }
+
+    Some(relative)
+}
@@ -1,15 +1,28 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use base64::{engine::general_purpose, Engine as _};
 use chrono::{DateTime, Local};
-use image::ImageFormat;
+use error::{AppError, PathContext};
 use serde::Serialize;
 use std::fs;
-use std::io::Cursor;
 use std::path::Path;
 use walkdir::WalkDir;
 
+mod archive;
+mod drives;
+mod error;
+mod identity;
+mod jobs;
+mod links;
+mod metadata;
+mod preview;
+mod protocol;
+mod templates;
+mod thumbnails;
+mod transfer;
+mod validation;
+mod viewer;
+
 /// Represents a file or directory entry
 #[derive(Serialize, Clone)]
 pub struct FileEntry {
@@ -20,15 +33,7 @@ pub struct FileEntry {
     pub modified: String,
     pub extension: String,
     pub is_cloud_placeholder: bool, // Cloud file not yet downloaded
-}
-
-/// Represents a drive on the system
-#[derive(Serialize)]
-pub struct DriveInfo {
-    pub name: String,
-    pub path: String,
-    pub total_space: u64,
-    pub free_space: u64,
+    pub metadata: Option<metadata::Columns>, // Extra columns, when asked for
 }
 
 /// Check if a file is a cloud placeholder (not fully downloaded)
@@ -45,25 +50,44 @@ fn is_cloud_placeholder(_metadata: &std::fs::Metadata) -> bool {
     false
 }
 
-/// Read the contents of a directory
+/// Sort entries: directories first, then by name (case-insensitive)
+fn sort_entries(entries: &mut [FileEntry]) {
+    entries.sort_by(|a, b| match (a.is_dir, b.is_dir) {
+        (true, false) => std::cmp::Ordering::Less,
+        (false, true) => std::cmp::Ordering::Greater,
+        _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+    });
+}
+
+/// Read the contents of a directory, with any extra metadata columns asked for.
+/// Runs off the main thread, since the columns mean opening every file.
 #[tauri::command]
-fn read_directory(path: String) -> Result<Vec<FileEntry>, String> {
-    let dir_path = Path::new(&path);
+async fn read_directory(
+    path: String,
+    columns: Option<Vec<metadata::Column>>,
+) -> Result<Vec<FileEntry>, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let dir_path = Path::new(&path);
+        let columns = columns.unwrap_or_default();
+
+        // Archives are browsed like folders
+        if let Some((archive_path, inner)) = archive::split_archive_path(dir_path) {
+            return archive::list_directory(&archive_path, &inner, false);
+        }
 
-    if !dir_path.exists() {
-        return Err(format!("Path does not exist: {}", path));
-    }
+        if !dir_path.exists() {
+            return Err(AppError::not_found(dir_path));
+        }
 
-    if !dir_path.is_dir() {
-        return Err(format!("Path is not a directory: {}", path));
-    }
+        if !dir_path.is_dir() {
+            return Err(AppError::not_a_directory(dir_path));
+        }
 
-    let mut entries = Vec::new();
+        let mut entries = Vec::new();
 
-    let read_result = fs::read_dir(dir_path).map_err(|e| e.to_string())?;
+        let read_result = fs::read_dir(dir_path).at(dir_path)?;
 
-    for entry in read_result {
-        if let Ok(entry) = entry {
+        for entry in read_result.flatten() {
             if let Ok(metadata) = entry.metadata() {
                 let modified = metadata
                     .modified()
@@ -79,6 +103,8 @@ fn read_directory(path: String) -> Result<Vec<FileEntry>, String> {
                     .map(|e| e.to_string_lossy().to_lowercase())
                     .unwrap_or_default();
 
+                // Reading a placeholder would download it
+                let placeholder = is_cloud_placeholder(&metadata);
                 entries.push(FileEntry {
                     name: entry.file_name().to_string_lossy().to_string(),
                     path: entry.path().to_string_lossy().to_string(),
@@ -86,110 +112,107 @@ fn read_directory(path: String) -> Result<Vec<FileEntry>, String> {
                     size: metadata.len(),
                     modified,
                     extension,
-                    is_cloud_placeholder: is_cloud_placeholder(&metadata),
+                    is_cloud_placeholder: placeholder,
+                    metadata: (!placeholder)
+                        .then(|| crate::metadata::columns(&entry.path(), &columns))
+                        .flatten(),
                 });
             }
         }
-    }
 
-    // Sort: directories first, then by name (case-insensitive)
-    entries.sort_by(|a, b| match (a.is_dir, b.is_dir) {
-        (true, false) => std::cmp::Ordering::Less,
-        (false, true) => std::cmp::Ordering::Greater,
-        _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
-    });
-
-    Ok(entries)
-}
+        sort_entries(&mut entries);
 
-/// Get all available drives on Windows
-#[tauri::command]
-fn get_drives() -> Vec<DriveInfo> {
-    let mut drives = Vec::new();
-
-    // Check common drive letters on Windows
-    for letter in 'A'..='Z' {
-        let path = format!("{}:\\", letter);
-        let path_obj = Path::new(&path);
-
-        if path_obj.exists() {
-            drives.push(DriveInfo {
-                name: format!("Local Disk ({}:)", letter),
-                path: path.clone(),
-                total_space: 0,
-                free_space: 0,
-            });
-        }
-    }
-
-    drives
+        Ok(entries)
+    })
+    .await
+    .map_err(AppError::other)?
 }
 
-/// Search for files matching a query
+/// Search for files whose name contains the query and that meet the metadata
+/// filter, if any
 #[tauri::command]
-fn search_files(
+async fn search_files(
     path: String,
     query: String,
     max_results: Option<usize>,
-) -> Result<Vec<FileEntry>, String> {
-    let search_path = Path::new(&path);
-
-    if !search_path.exists() {
-        return Err(format!("Path does not exist: {}", path));
-    }
-
-    let query_lower = query.to_lowercase();
-    let max = max_results.unwrap_or(100);
-    let mut results = Vec::new();
-
-    for entry in WalkDir::new(search_path)
-        .max_depth(5) // Limit depth for performance
-        .into_iter()
-        .filter_map(|e| e.ok())
-    {
-        if results.len() >= max {
-            break;
+    filter: Option<metadata::MetadataFilter>,
+    columns: Option<Vec<metadata::Column>>,
+) -> Result<Vec<FileEntry>, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let search_path = Path::new(&path);
+
+        if !search_path.exists() {
+            return Err(AppError::not_found(search_path));
         }
 
-        let file_name = entry.file_name().to_string_lossy().to_lowercase();
+        let query_lower = query.to_lowercase();
+        let max = max_results.unwrap_or(100);
+        let filter = filter.unwrap_or_default();
+        let columns = columns.unwrap_or_default();
+        let mut results = Vec::new();
 
-        if file_name.contains(&query_lower) {
-            if let Ok(metadata) = entry.metadata() {
-                let modified = metadata
-                    .modified()
-                    .map(|time| {
-                        let datetime: DateTime<Local> = time.into();
-                        datetime.format("%Y-%m-%d %H:%M").to_string()
-                    })
-                    .unwrap_or_else(|_| String::from("-"));
+        for entry in WalkDir::new(search_path)
+            .max_depth(5) // Limit depth for performance
+            .into_iter()
+            .filter_map(|e| e.ok())
+        {
+            if results.len() >= max {
+                break;
+            }
 
-                let extension = entry
-                    .path()
-                    .extension()
-                    .map(|e| e.to_string_lossy().to_lowercase())
-                    .unwrap_or_default();
+            let file_name = entry.file_name().to_string_lossy().to_lowercase();
 
-                results.push(FileEntry {
-                    name: entry.file_name().to_string_lossy().to_string(),
-                    path: entry.path().to_string_lossy().to_string(),
-                    is_dir: metadata.is_dir(),
-                    size: metadata.len(),
-                    modified,
-                    extension,
-                    is_cloud_placeholder: is_cloud_placeholder(&metadata),
-                });
+            if !file_name.contains(&query_lower) {
+                continue;
             }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+
+            // Reading a placeholder would download it, so only a search
+            // without criteria finds one
+            let placeholder = is_cloud_placeholder(&metadata);
+            let found = if placeholder {
+                filter.is_empty().then_some(None)
+            } else {
+                filter.matching_columns(entry.path(), &columns)
+            };
+            let Some(values) = found else {
+                continue;
+            };
+
+            let modified = metadata
+                .modified()
+                .map(|time| {
+                    let datetime: DateTime<Local> = time.into();
+                    datetime.format("%Y-%m-%d %H:%M").to_string()
+                })
+                .unwrap_or_else(|_| String::from("-"));
+
+            let extension = entry
+                .path()
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+
+            results.push(FileEntry {
+                name: entry.file_name().to_string_lossy().to_string(),
+                path: entry.path().to_string_lossy().to_string(),
+                is_dir: metadata.is_dir(),
+                size: metadata.len(),
+                modified,
+                extension,
+                is_cloud_placeholder: placeholder,
+                metadata: values,
+            });
         }
-    }
 
-    // Sort: directories first, then by name
-    results.sort_by(|a, b| match (a.is_dir, b.is_dir) {
-        (true, false) => std::cmp::Ordering::Less,
-        (false, true) => std::cmp::Ordering::Greater,
-        _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
-    });
+        sort_entries(&mut results);
 
-    Ok(results)
+        Ok(results)
+    })
+    .await
+    .map_err(AppError::other)?
 }
 
 /// Get the parent directory of a path
@@ -228,6 +251,7 @@ fn get_quick_access() -> Vec<FileEntry> {
                     modified: String::new(),
                     extension: String::new(),
                     is_cloud_placeholder: false, // Local folders are never cloud placeholders
+                    metadata: None,
                 });
             }
         }
@@ -271,7 +295,7 @@ fn get_cloud_drives() -> Vec<CloudDrive> {
         }
 
         // Check for OneDrive Business (pattern: "OneDrive - CompanyName")
-        if let Ok(entries) = fs::read_dir(&home_path) {
+        if let Ok(entries) = fs::read_dir(home_path) {
             for entry in entries.flatten() {
                 let name = entry.file_name().to_string_lossy().to_string();
                 if name.starts_with("OneDrive - ") && !name.contains("Personal") {
@@ -328,20 +352,26 @@ fn get_cloud_drives() -> Vec<CloudDrive> {
 
 /// Get immediate child folders for tree navigation (lazy loading)
 #[tauri::command]
-fn get_folder_children(path: String) -> Result<Vec<FileEntry>, String> {
+fn get_folder_children(path: String) -> Result<Vec<FileEntry>, AppError> {
     let dir_path = Path::new(&path);
 
+    if let Some((archive_path, inner)) = archive::split_archive_path(dir_path) {
+        let mut folders = archive::list_directory(&archive_path, &inner, true)?;
+        folders.retain(|f| !f.name.starts_with('.') && !f.name.starts_with('$'));
+        return Ok(folders);
+    }
+
     if !dir_path.exists() {
-        return Err(format!("Path does not exist: {}", path));
+        return Err(AppError::not_found(dir_path));
     }
 
     if !dir_path.is_dir() {
-        return Err(format!("Path is not a directory: {}", path));
+        return Err(AppError::not_a_directory(dir_path));
     }
 
     let mut folders = Vec::new();
 
-    let read_result = fs::read_dir(dir_path).map_err(|e| e.to_string())?;
+    let read_result = fs::read_dir(dir_path).at(dir_path)?;
 
     for entry in read_result.flatten() {
         if let Ok(metadata) = entry.metadata() {
@@ -357,6 +387,7 @@ fn get_folder_children(path: String) -> Result<Vec<FileEntry>, String> {
                         modified: String::new(),
                         extension: String::new(),
                         is_cloud_placeholder: is_cloud_placeholder(&metadata),
+                        metadata: None,
                     });
                 }
             }
@@ -364,59 +395,67 @@ fn get_folder_children(path: String) -> Result<Vec<FileEntry>, String> {
     }
 
     // Sort by name (case-insensitive)
-    folders.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    folders.sort_by_key(|a| a.name.to_lowercase());
 
     Ok(folders)
 }
 
 /// Create a new folder
 #[tauri::command]
-fn create_folder(path: String, name: String) -> Result<String, String> {
+fn create_folder(path: String, name: String) -> Result<String, AppError> {
+    validation::check_path(Path::new(&path))?;
+    validation::check_name(&name)?;
+
     let folder_path = Path::new(&path).join(&name);
 
     if folder_path.exists() {
-        return Err(format!("A folder named '{}' already exists", name));
+        return Err(AppError::already_exists(&folder_path));
     }
 
-    fs::create_dir(&folder_path).map_err(|e| e.to_string())?;
+    fs::create_dir(&folder_path).at(&folder_path)?;
 
     Ok(folder_path.to_string_lossy().to_string())
 }
 
 /// Rename a file or folder
 #[tauri::command]
-fn rename_item(old_path: String, new_name: String) -> Result<String, String> {
+fn rename_item(old_path: String, new_name: String) -> Result<String, AppError> {
     let old = Path::new(&old_path);
 
     if !old.exists() {
-        return Err(format!("Item does not exist: {}", old_path));
+        return Err(AppError::not_found(old));
     }
 
-    let parent = old.parent().ok_or("Cannot get parent directory")?;
+    validation::check_name(&new_name)?;
+
+    let parent = old
+        .parent()
+        .ok_or_else(|| AppError::invalid_input("Cannot rename a root folder"))?;
     let new_path = parent.join(&new_name);
 
-    if new_path.exists() {
-        return Err(format!("An item named '{}' already exists", new_name));
+    match identity::classify(old, &new_path) {
+        identity::Target::Free => fs::rename(&old_path, &new_path).at(old)?,
+        identity::Target::Itself => {}
+        identity::Target::CaseOnly => identity::rename_case_only(old, &new_path)?,
+        identity::Target::Taken => return Err(AppError::already_exists(&new_path)),
     }
 
-    fs::rename(&old_path, &new_path).map_err(|e| e.to_string())?;
-
     Ok(new_path.to_string_lossy().to_string())
 }
 
 /// Delete a file or folder
 #[tauri::command]
-fn delete_item(path: String) -> Result<(), String> {
+fn delete_item(path: String) -> Result<(), AppError> {
     let item_path = Path::new(&path);
 
     if !item_path.exists() {
-        return Err(format!("Item does not exist: {}", path));
+        return Err(AppError::not_found(item_path));
     }
 
     if item_path.is_dir() {
-        fs::remove_dir_all(&path).map_err(|e| e.to_string())?;
+        fs::remove_dir_all(&path).at(item_path)?;
     } else {
-        fs::remove_file(&path).map_err(|e| e.to_string())?;
+        fs::remove_file(&path).at(item_path)?;
     }
 
     Ok(())
@@ -424,22 +463,34 @@ fn delete_item(path: String) -> Result<(), String> {
 
 /// Copy a file or folder to destination with smart naming
 #[tauri::command]
-fn copy_item(source: String, destination: String) -> Result<String, String> {
+fn copy_item(source: String, destination: String) -> Result<String, AppError> {
     let src = Path::new(&source);
-    let src_name = src.file_name().ok_or("Cannot get file name")?;
+
+    // Copying a member out of an archive extracts it
+    if let Some((archive_path, inner)) = archive::member_path(src) {
+        let dest_path = archive::extract_member(&archive_path, &inner, Path::new(&destination))?;
+        return Ok(dest_path.to_string_lossy().to_string());
+    }
+
+    let src_name = src
+        .file_name()
+        .ok_or_else(|| AppError::invalid_input("Cannot copy a root folder"))?;
     let dest_dir = Path::new(&destination);
 
     if !src.exists() {
-        return Err(format!("Source does not exist: {}", source));
+        return Err(AppError::not_found(src));
     }
 
+    validation::check_path(dest_dir)?;
+    validation::check_not_inside(src, dest_dir)?;
+
     // Generate unique name if destination exists
     let dest_path = get_unique_path(dest_dir, src_name.to_str().unwrap_or(""), src.is_dir());
 
     if src.is_dir() {
         copy_dir_recursive(src, &dest_path)?;
     } else {
-        fs::copy(&source, &dest_path).map_err(|e| e.to_string())?;
+        fs::copy(&source, &dest_path).at(src)?;
     }
 
     Ok(dest_path.to_string_lossy().to_string())
@@ -487,19 +538,32 @@ fn get_unique_path(dest_dir: &Path, name: &str, is_dir: bool) -> std::path::Path
     dest_path
 }
 
+/// Where to copy a child into a folder the copy created itself. Anything
+/// already there is a sibling whose name differs only in case ("a.txt" and
+/// "A.txt") on a case-insensitive volume, so both are kept.
+fn child_path(dest: &Path, name: &std::ffi::OsStr, is_dir: bool) -> std::path::PathBuf {
+    let path = dest.join(name);
+    if fs::symlink_metadata(&path).is_ok() {
+        get_unique_path(dest, &name.to_string_lossy(), is_dir)
+    } else {
+        path
+    }
+}
+
 /// Helper function to copy directories recursively
-fn copy_dir_recursive(src: &Path, dest: &Path) -> Result<(), String> {
-    fs::create_dir_all(dest).map_err(|e| e.to_string())?;
+fn copy_dir_recursive(src: &Path, dest: &Path) -> Result<(), AppError> {
+    fs::create_dir_all(dest).at(dest)?;
 
-    for entry in fs::read_dir(src).map_err(|e| e.to_string())? {
-        let entry = entry.map_err(|e| e.to_string())?;
+    for entry in fs::read_dir(src).at(src)? {
+        let entry = entry.at(src)?;
         let src_path = entry.path();
-        let dest_path = dest.join(entry.file_name());
+        let is_dir = src_path.is_dir();
+        let dest_path = child_path(dest, &entry.file_name(), is_dir);
 
-        if src_path.is_dir() {
+        if is_dir {
             copy_dir_recursive(&src_path, &dest_path)?;
         } else {
-            fs::copy(&src_path, &dest_path).map_err(|e| e.to_string())?;
+            fs::copy(&src_path, &dest_path).at(&src_path)?;
         }
     }
 
@@ -508,34 +572,39 @@ fn copy_dir_recursive(src: &Path, dest: &Path) -> Result<(), String> {
 
 /// Move a file or folder to destination
 #[tauri::command]
-fn move_item(source: String, destination: String) -> Result<String, String> {
+fn move_item(source: String, destination: String) -> Result<String, AppError> {
     let src = Path::new(&source);
-    let src_name = src.file_name().ok_or("Cannot get file name")?;
+    let src_name = src
+        .file_name()
+        .ok_or_else(|| AppError::invalid_input("Cannot move a root folder"))?;
     let dest_path = Path::new(&destination).join(src_name);
 
     if !src.exists() {
-        return Err(format!("Source does not exist: {}", source));
+        return Err(AppError::not_found(src));
     }
 
-    if dest_path.exists() {
-        return Err(format!(
-            "Destination already exists: {}",
-            dest_path.display()
-        ));
+    let dest_dir = Path::new(&destination);
+    validation::check_path(dest_dir)?;
+    validation::check_name(&src_name.to_string_lossy())?;
+    validation::check_not_inside(src, dest_dir)?;
+
+    match identity::classify(src, &dest_path) {
+        identity::Target::Free => {}
+        // Moved into the folder it's already in
+        identity::Target::Itself | identity::Target::CaseOnly => {
+            return Ok(dest_path.to_string_lossy().to_string())
+        }
+        identity::Target::Taken => return Err(AppError::already_exists(&dest_path)),
     }
 
     // Try simple rename first (works if same filesystem)
-    if fs::rename(&source, &dest_path).is_ok() {
-        return Ok(dest_path.to_string_lossy().to_string());
-    }
-
-    // If rename fails (cross-filesystem), copy then delete
-    if src.is_dir() {
-        copy_dir_recursive(src, &dest_path)?;
-        fs::remove_dir_all(&source).map_err(|e| e.to_string())?;
-    } else {
-        fs::copy(&source, &dest_path).map_err(|e| e.to_string())?;
-        fs::remove_file(&source).map_err(|e| e.to_string())?;
+    match transfer::rename_no_replace(src, &dest_path) {
+        Ok(()) => {}
+        // Across file systems, copy to a staging entry then swap it in
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            transfer::move_across_filesystems(src, &dest_path)?
+        }
+        Err(e) => return Err(AppError::io(e, src)),
     }
 
     Ok(dest_path.to_string_lossy().to_string())
@@ -543,7 +612,7 @@ fn move_item(source: String, destination: String) -> Result<String, String> {
 
 /// Open terminal at specific path
 #[tauri::command]
-fn open_in_terminal(path: String) -> Result<(), String> {
+fn open_in_terminal(path: String) -> Result<(), AppError> {
     #[cfg(target_os = "windows")]
     {
         use std::process::Command;
@@ -557,13 +626,13 @@ fn open_in_terminal(path: String) -> Result<(), String> {
                 &format!("cd '{}'", path),
             ])
             .spawn()
-            .map_err(|e| e.to_string())?;
+            .map_err(AppError::other)?;
+        Ok(())
     }
     #[cfg(not(target_os = "windows"))]
     {
-        return Err("Not supported on this OS".to_string());
+        Err(AppError::unsupported(&path, "Not supported on this OS"))
     }
-    Ok(())
 }
 
 #[derive(serde::Serialize)]
@@ -577,8 +646,8 @@ struct FileProperties {
 
 /// Get detailed file properties
 #[tauri::command]
-fn get_file_properties(path: String) -> Result<FileProperties, String> {
-    let metadata = fs::metadata(&path).map_err(|e| e.to_string())?;
+fn get_file_properties(path: String) -> Result<FileProperties, AppError> {
+    let metadata = fs::metadata(&path).at(&path)?;
 
     let created = metadata
         .created()
@@ -633,32 +702,32 @@ fn get_file_properties(path: String) -> Result<FileProperties, String> {
 
 // Window control commands for custom title bar
 #[tauri::command]
-async fn minimize_window(window: tauri::WebviewWindow) -> Result<(), String> {
-    window.minimize().map_err(|e| e.to_string())
+async fn minimize_window(window: tauri::WebviewWindow) -> Result<(), AppError> {
+    Ok(window.minimize()?)
 }
 
 #[tauri::command]
-async fn maximize_window(window: tauri::WebviewWindow) -> Result<(), String> {
-    if window.is_maximized().map_err(|e| e.to_string())? {
-        window.unmaximize().map_err(|e| e.to_string())
+async fn maximize_window(window: tauri::WebviewWindow) -> Result<(), AppError> {
+    if window.is_maximized()? {
+        Ok(window.unmaximize()?)
     } else {
-        window.maximize().map_err(|e| e.to_string())
+        Ok(window.maximize()?)
     }
 }
 
 #[tauri::command]
-async fn close_window(window: tauri::WebviewWindow) -> Result<(), String> {
-    window.close().map_err(|e| e.to_string())
+async fn close_window(window: tauri::WebviewWindow) -> Result<(), AppError> {
+    Ok(window.close()?)
 }
 
 #[tauri::command]
-async fn is_maximized(window: tauri::WebviewWindow) -> Result<bool, String> {
-    window.is_maximized().map_err(|e| e.to_string())
+async fn is_maximized(window: tauri::WebviewWindow) -> Result<bool, AppError> {
+    Ok(window.is_maximized()?)
 }
 
 // Show native Windows properties dialog
 #[tauri::command]
-fn show_native_properties(path: String) -> Result<(), String> {
+fn show_native_properties(path: String) -> Result<(), AppError> {
     #[cfg(target_os = "windows")]
     {
         use std::ffi::OsStr;
@@ -724,8 +793,12 @@ fn show_native_properties(path: String) -> Result<(), String> {
         unsafe {
             ShellExecuteExW(&mut info);
         }
+        Ok(())
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        Err(AppError::unsupported(&path, "Not supported on this OS"))
     }
-    Ok(())
 }
 
 // Native context menu using tauri::menu
@@ -738,7 +811,7 @@ async fn show_context_menu(
     file_path: Option<String>,
     is_file: bool,
     has_clipboard: bool,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     use tauri::menu::{ContextMenu, MenuBuilder, MenuItemBuilder};
 
     let mut menu_builder = MenuBuilder::new(&app);
@@ -746,11 +819,7 @@ async fn show_context_menu(
     // If clicking on a file/folder, show "Open" first
     if file_path.is_some() && is_file {
         menu_builder = menu_builder
-            .item(
-                &MenuItemBuilder::with_id("open", "Open")
-                    .build(&app)
-                    .map_err(|e| e.to_string())?,
-            )
+            .item(&MenuItemBuilder::with_id("open", "Open").build(&app)?)
             .separator();
     }
 
@@ -760,311 +829,61 @@ async fn show_context_menu(
             &MenuItemBuilder::with_id("cut", "Cut")
                 .accelerator("Ctrl+X")
                 .enabled(file_path.is_some())
-                .build(&app)
-                .map_err(|e| e.to_string())?,
+                .build(&app)?,
         )
         .item(
             &MenuItemBuilder::with_id("copy", "Copy")
                 .accelerator("Ctrl+C")
                 .enabled(file_path.is_some())
-                .build(&app)
-                .map_err(|e| e.to_string())?,
+                .build(&app)?,
         )
         .item(
             &MenuItemBuilder::with_id("paste", "Paste")
                 .accelerator("Ctrl+V")
                 .enabled(has_clipboard)
-                .build(&app)
-                .map_err(|e| e.to_string())?,
+                .build(&app)?,
         )
-        .separator()
         .item(
-            &MenuItemBuilder::with_id("open_terminal", "Open in Terminal")
-                .build(&app)
-                .map_err(|e| e.to_string())?,
+            &MenuItemBuilder::with_id("paste_link", "Paste as link")
+                .enabled(has_clipboard)
+                .build(&app)?,
         )
         .separator()
+        .item(&MenuItemBuilder::with_id("open_terminal", "Open in Terminal").build(&app)?)
+        .separator()
         .item(
             &MenuItemBuilder::with_id("new_folder", "New folder")
                 .accelerator("Ctrl+Shift+N")
-                .build(&app)
-                .map_err(|e| e.to_string())?,
+                .build(&app)?,
         )
         .item(
             &MenuItemBuilder::with_id("rename", "Rename")
                 .accelerator("F2")
                 .enabled(file_path.is_some())
-                .build(&app)
-                .map_err(|e| e.to_string())?,
+                .build(&app)?,
         )
         .item(
             &MenuItemBuilder::with_id("delete", "Delete")
                 .accelerator("Delete")
                 .enabled(file_path.is_some())
-                .build(&app)
-                .map_err(|e| e.to_string())?,
+                .build(&app)?,
         );
 
     // Properties only for files
     if file_path.is_some() {
-        menu_builder = menu_builder.separator().item(
-            &MenuItemBuilder::with_id("properties", "Properties")
-                .build(&app)
-                .map_err(|e| e.to_string())?,
-        );
+        menu_builder = menu_builder
+            .separator()
+            .item(&MenuItemBuilder::with_id("properties", "Properties").build(&app)?);
     }
 
-    let menu = menu_builder.build().map_err(|e| e.to_string())?;
+    let menu = menu_builder.build()?;
 
     // Show menu at cursor position using popup
-    menu.popup(window.as_ref().window())
-        .map_err(|e| e.to_string())?;
+    menu.popup(window.as_ref().window())?;
 
     Ok(())
 }
 
-/// Supported extensions for thumbnail generation - images (fast, use image crate)
-const IMAGE_EXTENSIONS: &[&str] = &[
-    "jpg", "jpeg", "png", "gif", "bmp", "webp", "ico", "tiff", "tif",
-];
-
-/// Extensions that Windows Shell can generate thumbnails for (videos, PDFs, etc)
-const SHELL_THUMBNAIL_EXTENSIONS: &[&str] = &[
-    "mp4", "mkv", "avi", "mov", "wmv", "flv", "webm", "m4v", "mpeg", "mpg", "pdf", "doc", "docx",
-    "xls", "xlsx", "ppt", "pptx",
-];
-
-/// Check if file supports thumbnail via image crate
-fn is_image_thumbnail_supported(extension: &str) -> bool {
-    IMAGE_EXTENSIONS.contains(&extension.to_lowercase().as_str())
-}
-
-/// Check if file supports thumbnail via Windows Shell
-fn is_shell_thumbnail_supported(extension: &str) -> bool {
-    SHELL_THUMBNAIL_EXTENSIONS.contains(&extension.to_lowercase().as_str())
-}
-
-/// Check if file supports any thumbnail generation
-#[allow(dead_code)]
-fn is_thumbnail_supported(extension: &str) -> bool {
-    is_image_thumbnail_supported(extension) || is_shell_thumbnail_supported(extension)
-}
-
-/// Generate a thumbnail using Windows Shell API (IShellItemImageFactory)
-/// This uses the same thumbnail system as Windows Explorer
-fn generate_shell_thumbnail(path: &str, size: u32) -> Result<String, String> {
-    use windows::core::PCWSTR;
-    use windows::Win32::Graphics::Gdi::{
-        CreateCompatibleDC, DeleteDC, DeleteObject, GetDIBits, SelectObject, BITMAPINFO,
-        BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS,
-    };
-    use windows::Win32::System::Com::{CoInitializeEx, CoUninitialize, COINIT_APARTMENTTHREADED};
-    use windows::Win32::UI::Shell::{
-        IShellItemImageFactory, SHCreateItemFromParsingName, SIIGBF_THUMBNAILONLY,
-    };
-
-    unsafe {
-        // Initialize COM
-        let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
-
-        // Convert path to wide string
-        let wide_path: Vec<u16> = path.encode_utf16().chain(std::iter::once(0)).collect();
-
-        // Create shell item
-        let shell_item: IShellItemImageFactory =
-            SHCreateItemFromParsingName(PCWSTR::from_raw(wide_path.as_ptr()), None)
-                .map_err(|e| format!("Failed to create shell item: {:?}", e))?;
-
-        // Request thumbnail
-        let thumb_size = windows::Win32::Foundation::SIZE {
-            cx: size as i32,
-            cy: size as i32,
-        };
-
-        let hbitmap = shell_item
-            .GetImage(thumb_size, SIIGBF_THUMBNAILONLY)
-            .map_err(|e| format!("Failed to get thumbnail: {:?}", e))?;
-
-        // Convert HBITMAP to PNG
-        let hdc = CreateCompatibleDC(None);
-        if hdc.is_invalid() {
-            DeleteObject(hbitmap);
-            CoUninitialize();
-            return Err("Failed to create DC".to_string());
-        }
-
-        let old_bitmap = SelectObject(hdc, hbitmap);
-
-        // Get bitmap info
-        let mut bmi = BITMAPINFO {
-            bmiHeader: BITMAPINFOHEADER {
-                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
-                biWidth: size as i32,
-                biHeight: -(size as i32), // Negative for top-down
-                biPlanes: 1,
-                biBitCount: 32,
-                biCompression: BI_RGB.0,
-                biSizeImage: 0,
-                biXPelsPerMeter: 0,
-                biYPelsPerMeter: 0,
-                biClrUsed: 0,
-                biClrImportant: 0,
-            },
-            bmiColors: [Default::default()],
-        };
-
-        // Allocate buffer for pixel data
-        let mut pixels: Vec<u8> = vec![0u8; (size * size * 4) as usize];
-
-        let result = GetDIBits(
-            hdc,
-            hbitmap,
-            0,
-            size,
-            Some(pixels.as_mut_ptr() as *mut _),
-            &mut bmi,
-            DIB_RGB_COLORS,
-        );
-
-        // Cleanup GDI objects
-        SelectObject(hdc, old_bitmap);
-        DeleteDC(hdc);
-        DeleteObject(hbitmap);
-        CoUninitialize();
-
-        if result == 0 {
-            return Err("Failed to get bitmap bits".to_string());
-        }
-
-        // Convert BGRA to RGBA
-        for chunk in pixels.chunks_exact_mut(4) {
-            chunk.swap(0, 2); // Swap B and R
-        }
-
-        // Create image from raw pixels
-        let actual_width = bmi.bmiHeader.biWidth.unsigned_abs();
-        let actual_height = bmi.bmiHeader.biHeight.unsigned_abs();
-
-        let img_buffer = image::RgbaImage::from_raw(actual_width, actual_height, pixels)
-            .ok_or_else(|| "Failed to create image buffer".to_string())?;
-
-        let img = image::DynamicImage::ImageRgba8(img_buffer);
-        let thumbnail = img.thumbnail(size, size);
-
-        // Encode to PNG
-        let mut buffer = Cursor::new(Vec::new());
-        thumbnail
-            .write_to(&mut buffer, ImageFormat::Png)
-            .map_err(|e| e.to_string())?;
-
-        let base64_data = general_purpose::STANDARD.encode(buffer.get_ref());
-        Ok(format!("data:image/png;base64,{}", base64_data))
-    }
-}
-
-/// Generate a thumbnail for an image or video file
-/// Returns a base64-encoded PNG thumbnail
-#[tauri::command]
-async fn get_thumbnail(path: String, size: Option<u32>) -> Result<String, String> {
-    let file_path = Path::new(&path);
-
-    if !file_path.exists() {
-        return Err("File does not exist".to_string());
-    }
-
-    // Get extension
-    let extension = file_path
-        .extension()
-        .map(|e| e.to_string_lossy().to_lowercase())
-        .unwrap_or_default();
-
-    let thumb_size = size.unwrap_or(96);
-
-    // Use Windows Shell for videos and documents
-    if is_shell_thumbnail_supported(&extension) {
-        return generate_shell_thumbnail(&path, thumb_size);
-    }
-
-    // Use image crate for regular images (faster)
-    if !is_image_thumbnail_supported(&extension) {
-        return Err("Unsupported file type".to_string());
-    }
-
-    // Load and resize image
-    let img = image::open(&path).map_err(|e| e.to_string())?;
-
-    // Use thumbnail method for fast resizing (maintains aspect ratio)
-    let thumbnail = img.thumbnail(thumb_size, thumb_size);
-
-    // Encode to PNG in memory
-    let mut buffer = Cursor::new(Vec::new());
-    thumbnail
-        .write_to(&mut buffer, ImageFormat::Png)
-        .map_err(|e| e.to_string())?;
-
-    // Convert to base64
-    let base64_data = general_purpose::STANDARD.encode(buffer.get_ref());
-
-    Ok(format!("data:image/png;base64,{}", base64_data))
-}
-
-/// Result of reading a file preview
-#[derive(Serialize)]
-pub struct FilePreviewResult {
-    pub content: String,
-    pub line_count: usize,
-    pub is_truncated: bool,
-}
-
-/// Read the first N lines of a text file for preview
-#[tauri::command]
-fn read_file_preview(path: String, max_lines: Option<usize>) -> Result<FilePreviewResult, String> {
-    use std::io::{BufRead, BufReader};
-
-    let file_path = Path::new(&path);
-
-    if !file_path.exists() {
-        return Err("File does not exist".to_string());
-    }
-
-    if file_path.is_dir() {
-        return Err("Cannot preview directories".to_string());
-    }
-
-    let file = fs::File::open(&path).map_err(|e| e.to_string())?;
-    let reader = BufReader::new(file);
-    let max = max_lines.unwrap_or(100);
-
-    let mut lines = Vec::new();
-    let mut total_lines = 0;
-    let mut is_truncated = false;
-
-    for line in reader.lines() {
-        total_lines += 1;
-        if lines.len() < max {
-            match line {
-                Ok(l) => lines.push(l),
-                Err(_) => {
-                    // Binary file or encoding error
-                    return Err("Cannot preview binary files".to_string());
-                }
-            }
-        } else {
-            is_truncated = true;
-            // Count remaining lines for total
-            if let Ok(_) = line {
-                continue;
-            }
-        }
-    }
-
-    Ok(FilePreviewResult {
-        content: lines.join("\n"),
-        line_count: total_lines,
-        is_truncated,
-    })
-}
-
 fn main() {
     use tauri::menu::{MenuBuilder, MenuItemBuilder};
     use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
@@ -1074,6 +893,11 @@ fn main() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_clipboard_manager::init())
+        .manage(jobs::JobRegistry::default())
+        .manage(thumbnails::scheduler::ThumbnailScheduler::default())
+        .register_asynchronous_uri_scheme_protocol("thumb", protocol::thumb)
+        .register_asynchronous_uri_scheme_protocol("image-tile", protocol::image_tile)
+        .register_asynchronous_uri_scheme_protocol("file-content", protocol::file_content)
         .setup(|app| {
             // Create tray menu
             let show_item = MenuItemBuilder::with_id("show", "Show EdoriFile")
@@ -1142,17 +966,28 @@ fn main() {
         })
         .invoke_handler(tauri::generate_handler![
             read_directory,
-            get_drives,
+            drives::get_drives,
             search_files,
             get_parent_directory,
             get_quick_access,
             get_cloud_drives,
             get_folder_children,
             create_folder,
+            templates::list_templates,
+            templates::create_file,
             rename_item,
             delete_item,
             copy_item,
+            transfer::copy_item_with_options,
             move_item,
+            links::create_symlink,
+            links::create_hard_link,
+            links::create_shortcut,
+            validation::validate_name,
+            identity::is_case_sensitive,
+            archive::compress::compress_items,
+            archive::extract::extract_archive,
+            jobs::cancel_job,
             open_in_terminal,
             get_file_properties,
             minimize_window,
@@ -1161,8 +996,22 @@ fn main() {
             is_maximized,
             show_native_properties,
             show_context_menu,
-            get_thumbnail,
-            read_file_preview
+            thumbnails::get_thumbnail_extensions,
+            thumbnails::cache::clear_thumbnail_cache,
+            thumbnails::scheduler::request_thumbnails,
+            thumbnails::scheduler::cancel_thumbnails,
+            viewer::open_image,
+            preview::read_file_preview,
+            preview::range::read_text_range,
+            preview::range::read_text_at_line,
+            preview::line_index::get_line_index,
+            preview::follow::follow_file,
+            preview::hex::read_file_hex,
+            preview::hex::find_in_file,
+            preview::highlight::highlight_file,
+            preview::structured::read_structured_preview,
+            metadata::image_info::get_image_metadata,
+            metadata::media_info::get_media_metadata
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,484 @@
+// Image properties and the metadata photos carry.
+// Dimensions, colour type and bit depth come from the image header without
+// decoding any pixels. Camera, exposure, capture date, orientation and GPS
+// come from EXIF; keywords from XMP (dc:subject) and IPTC, merged.
+
+use crate::error::{AppError, PathContext};
+use exif::{In, Tag, Value};
+use image::{ExtendedColorType, ImageDecoder, ImageFormat, ImageReader};
+use quick_xml::events::Event;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Bytes searched for an XMP packet or IPTC block at the start of the file
+const METADATA_SCAN_SIZE: u64 = 1024 * 1024;
+
+/// Extensions worth opening for image metadata
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "jpe", "jfif", "png", "gif", "bmp", "webp", "tif", "tiff", "ico", "heic",
+    "heif", "avif", "dng", "cr2", "nef", "arw", "orf", "rw2", "pef", "raf", "srw",
+];
+
+#[derive(Serialize, Clone, Default)]
+pub struct ImageMetadata {
+    pub format: Option<String>, // "JPEG", "PNG", ...
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub color_type: Option<String>, // "RGB", "RGBA", "Grayscale", "CMYK", ...
+    pub bit_depth: Option<u8>,      // Bits per channel
+    pub exif: Option<ExifData>,
+    pub keywords: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Default)]
+pub struct ExifData {
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub lens_model: Option<String>,
+    pub exposure_time: Option<String>, // "1/250" or "2.5" seconds
+    pub f_number: Option<f64>,
+    pub iso: Option<u32>,
+    pub focal_length: Option<f64>,  // mm
+    pub date_taken: Option<String>, // "YYYY-MM-DD HH:MM:SS", camera local time
+    pub orientation: Option<u16>,   // 1-8, as in EXIF
+    pub gps: Option<GpsPosition>,
+}
+
+#[derive(Serialize, Clone, Copy)]
+pub struct GpsPosition {
+    pub latitude: f64,         // Degrees, negative south
+    pub longitude: f64,        // Degrees, negative west
+    pub altitude: Option<f64>, // Meters above sea level
+}
+
+/// Header fields of an image
+#[derive(Default)]
+struct ImageHeader {
+    format: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    color_type: Option<String>,
+    bit_depth: Option<u8>,
+}
+
+/// Whether a file might be an image with metadata, going by its extension
+pub fn is_image(path: &Path) -> bool {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .is_some_and(|e| IMAGE_EXTENSIONS.contains(&e.as_str()))
+}
+
+/// Get the dimensions, colour type, EXIF data and keywords of an image
+#[tauri::command]
+pub async fn get_image_metadata(path: String) -> Result<ImageMetadata, AppError> {
+    tauri::async_runtime::spawn_blocking(move || read(Path::new(&path), true))
+        .await
+        .map_err(AppError::other)?
+}
+
+/// Read everything but the keywords unless asked, which takes a larger read
+pub fn read(path: &Path, with_keywords: bool) -> Result<ImageMetadata, AppError> {
+    if path.is_dir() {
+        return Err(AppError::is_a_directory(path));
+    }
+    let header = header(path)?;
+    let exif = read_exif(path)?;
+    if header.width.is_none() && exif.is_none() {
+        return Err(AppError::unsupported(path, "Not a readable image"));
+    }
+
+    // Formats the image crate can't open (HEIC, RAW) still have EXIF sizes
+    let exif_size = exif.as_ref().and_then(exif_dimensions);
+    Ok(ImageMetadata {
+        format: header.format,
+        width: header.width.or(exif_size.map(|s| s.0)),
+        height: header.height.or(exif_size.map(|s| s.1)),
+        color_type: header.color_type,
+        bit_depth: header.bit_depth,
+        exif: exif.as_ref().map(exif_data),
+        keywords: if with_keywords {
+            keywords(path)?
+        } else {
+            Vec::new()
+        },
+    })
+}
+
+/// Read the image header. Fields are None for formats that aren't supported.
+fn header(path: &Path) -> Result<ImageHeader, AppError> {
+    let file = File::open(path).at(path)?;
+    let Ok(reader) = ImageReader::new(BufReader::new(file)).with_guessed_format() else {
+        return Ok(ImageHeader::default());
+    };
+    let Some(format) = reader.format() else {
+        return Ok(ImageHeader::default());
+    };
+    let name = format!("{:?}", format).to_uppercase();
+
+    // The JPEG decoder reads the whole file up front; the frame header is enough
+    if format == ImageFormat::Jpeg {
+        let mut reader = reader.into_inner();
+        reader.seek(SeekFrom::Start(0)).at(path)?;
+        return Ok(jpeg_header(reader).unwrap_or(ImageHeader {
+            format: Some(name),
+            ..Default::default()
+        }));
+    }
+
+    let Ok(decoder) = reader.into_decoder() else {
+        return Ok(ImageHeader {
+            format: Some(name),
+            ..Default::default()
+        });
+    };
+    let (width, height) = decoder.dimensions();
+    let color = decoder.original_color_type();
+    let channels = color.channel_count().max(1);
+    Ok(ImageHeader {
+        format: Some(name),
+        width: Some(width),
+        height: Some(height),
+        color_type: color_name(color).map(str::to_string),
+        bit_depth: Some(color.bits_per_pixel() as u8 / channels),
+    })
+}
+
+fn color_name(color: ExtendedColorType) -> Option<&'static str> {
+    use ExtendedColorType::*;
+    Some(match color {
+        A8 => "Alpha",
+        L1 | L2 | L4 | L8 | L16 => "Grayscale",
+        La1 | La2 | La4 | La8 | La16 => "Grayscale with alpha",
+        Rgb1 | Rgb2 | Rgb4 | Rgb8 | Rgb16 | Bgr8 | Rgb32F => "RGB",
+        Rgba1 | Rgba2 | Rgba4 | Rgba8 | Rgba16 | Bgra8 | Rgba32F => "RGBA",
+        Cmyk8 | Cmyk16 => "CMYK",
+        _ => return None,
+    })
+}
+
+/// Find the frame header (SOFn) of a JPEG, skipping the segments before it
+fn jpeg_header<R: Read>(mut reader: R) -> Option<ImageHeader> {
+    let mut marker = [0u8; 2];
+    reader.read_exact(&mut marker).ok()?;
+    if marker != [0xff, 0xd8] {
+        return None;
+    }
+    loop {
+        reader.read_exact(&mut marker).ok()?;
+        if marker[0] != 0xff {
+            return None;
+        }
+        // Fill bytes and markers without a length
+        if marker[1] == 0xff || marker[1] == 0x01 || (0xd0..=0xd7).contains(&marker[1]) {
+            continue;
+        }
+        let mut len = [0u8; 2];
+        reader.read_exact(&mut len).ok()?;
+        let len = u16::from_be_bytes(len).checked_sub(2)? as u64;
+
+        // SOF0-SOF15, except DHT (C4), JPG (C8) and DAC (CC)
+        if (0xc0..=0xcf).contains(&marker[1]) && ![0xc4, 0xc8, 0xcc].contains(&marker[1]) {
+            let mut frame = [0u8; 6];
+            reader.read_exact(&mut frame).ok()?;
+            return Some(ImageHeader {
+                format: Some("JPEG".to_string()),
+                width: Some(u16::from_be_bytes([frame[3], frame[4]]) as u32),
+                height: Some(u16::from_be_bytes([frame[1], frame[2]]) as u32),
+                color_type: match frame[5] {
+                    1 => Some("Grayscale".to_string()),
+                    3 => Some("RGB".to_string()),
+                    4 => Some("CMYK".to_string()),
+                    _ => None,
+                },
+                bit_depth: Some(frame[0]),
+            });
+        }
+        std::io::copy(&mut reader.by_ref().take(len), &mut std::io::sink()).ok()?;
+    }
+}
+
+fn exif_data(exif: &exif::Exif) -> ExifData {
+    let rational = |tag| match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Rational(v) if v.first()?.denom != 0 => Some(v[0].to_f64()),
+        Value::SRational(v) if v.first()?.denom != 0 => Some(v[0].to_f64()),
+        _ => None,
+    };
+    let uint = |tag| exif.get_field(tag, In::PRIMARY)?.value.get_uint(0);
+
+    let exposure_time = match &exif
+        .get_field(Tag::ExposureTime, In::PRIMARY)
+        .map(|f| &f.value)
+    {
+        Some(Value::Rational(v)) if v.first().is_some_and(|r| r.num > 0 && r.denom > 0) => {
+            let seconds = v[0].to_f64();
+            Some(if seconds < 1.0 {
+                format!("1/{}", (1.0 / seconds).round())
+            } else {
+                format!("{}", (seconds * 10.0).round() / 10.0)
+            })
+        }
+        _ => None,
+    };
+
+    ExifData {
+        camera_make: ascii(exif, Tag::Make),
+        camera_model: ascii(exif, Tag::Model),
+        lens_model: ascii(exif, Tag::LensModel),
+        exposure_time,
+        f_number: rational(Tag::FNumber),
+        iso: uint(Tag::PhotographicSensitivity),
+        focal_length: rational(Tag::FocalLength),
+        date_taken: [Tag::DateTimeOriginal, Tag::DateTimeDigitized, Tag::DateTime]
+            .into_iter()
+            .find_map(|tag| ascii(exif, tag).and_then(|d| exif_date(&d))),
+        orientation: uint(Tag::Orientation).map(|o| o as u16),
+        gps: gps(exif),
+    }
+}
+
+/// Read the EXIF data, if the file has any
+fn read_exif(path: &Path) -> Result<Option<exif::Exif>, AppError> {
+    let file = File::open(path).at(path)?;
+    match exif::Reader::new().read_from_container(&mut BufReader::new(file)) {
+        Ok(exif) => Ok(Some(exif)),
+        Err(exif::Error::Io(e)) if e.kind() != std::io::ErrorKind::UnexpectedEof => {
+            Err(AppError::io(e, path))
+        }
+        // No EXIF, or none that parses
+        Err(_) => Ok(None),
+    }
+}
+
+fn exif_dimensions(exif: &exif::Exif) -> Option<(u32, u32)> {
+    let get = |tags: [Tag; 2]| {
+        tags.into_iter()
+            .find_map(|tag| exif.get_field(tag, In::PRIMARY)?.value.get_uint(0))
+    };
+    Some((
+        get([Tag::PixelXDimension, Tag::ImageWidth])?,
+        get([Tag::PixelYDimension, Tag::ImageLength])?,
+    ))
+}
+
+fn ascii(exif: &exif::Exif, tag: Tag) -> Option<String> {
+    match &exif.get_field(tag, In::PRIMARY)?.value {
+        Value::Ascii(values) => {
+            let text = String::from_utf8_lossy(values.first()?);
+            let text = text.trim_matches(|c: char| c.is_whitespace() || c == '\0');
+            (!text.is_empty()).then(|| text.to_string())
+        }
+        _ => None,
+    }
+}
+
+/// "2024:05:01 12:30:00" to "2024-05-01 12:30:00"; blank dates are None
+fn exif_date(date: &str) -> Option<String> {
+    let bytes = date.as_bytes();
+    if bytes.len() < 19 || !date.is_ascii() || !bytes[..4].iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    if &date[..4] == "0000" {
+        return None;
+    }
+    Some(format!("{}-{}-{}", &date[..4], &date[5..7], &date[8..19]))
+}
+
+fn gps(exif: &exif::Exif) -> Option<GpsPosition> {
+    let degrees = |tag, ref_tag, negative: &str| {
+        let Value::Rational(v) = &exif.get_field(tag, In::PRIMARY)?.value else {
+            return None;
+        };
+        if v.len() < 3 || v.iter().any(|r| r.denom == 0) {
+            return None;
+        }
+        let value = v[0].to_f64() + v[1].to_f64() / 60.0 + v[2].to_f64() / 3600.0;
+        let sign = if ascii(exif, ref_tag).as_deref() == Some(negative) {
+            -1.0
+        } else {
+            1.0
+        };
+        Some(value * sign)
+    };
+
+    let altitude = match &exif.get_field(Tag::GPSAltitude, In::PRIMARY)?.value {
+        Value::Rational(v) if v.first().is_some_and(|r| r.denom != 0) => {
+            let below_sea_level = exif
+                .get_field(Tag::GPSAltitudeRef, In::PRIMARY)
+                .and_then(|f| f.value.get_uint(0))
+                == Some(1);
+            Some(if below_sea_level {
+                -v[0].to_f64()
+            } else {
+                v[0].to_f64()
+            })
+        }
+        _ => None,
+    };
+
+    Some(GpsPosition {
+        latitude: degrees(Tag::GPSLatitude, Tag::GPSLatitudeRef, "S")?,
+        longitude: degrees(Tag::GPSLongitude, Tag::GPSLongitudeRef, "W")?,
+        altitude,
+    })
+}
+
+/// Keywords from XMP and IPTC, without duplicates
+fn keywords(path: &Path) -> Result<Vec<String>, AppError> {
+    let mut head = Vec::new();
+    File::open(path)
+        .at(path)?
+        .take(METADATA_SCAN_SIZE)
+        .read_to_end(&mut head)
+        .at(path)?;
+
+    let mut keywords = xmp_keywords(&head);
+    for keyword in iptc_keywords(&head) {
+        if !keywords.contains(&keyword) {
+            keywords.push(keyword);
+        }
+    }
+    Ok(keywords)
+}
+
+/// The rdf:li items of dc:subject in the XMP packet, wherever it's embedded
+fn xmp_keywords(data: &[u8]) -> Vec<String> {
+    let Some(start) = find(data, b"<x:xmpmeta") else {
+        return Vec::new();
+    };
+    let end = find(&data[start..], b"</x:xmpmeta>").map_or(data.len(), |e| start + e);
+    let xml = String::from_utf8_lossy(&data[start..end]);
+
+    let mut reader = quick_xml::Reader::from_str(&xml);
+    let mut keywords: Vec<String> = Vec::new();
+    let mut in_subject = false;
+    let mut item: Option<String> = None;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) if e.name().as_ref() == b"dc:subject" => in_subject = true,
+            Ok(Event::End(e)) if e.name().as_ref() == b"dc:subject" => in_subject = false,
+            Ok(Event::Start(e)) if in_subject && e.name().as_ref() == b"rdf:li" => {
+                item = Some(String::new())
+            }
+            Ok(Event::Text(text)) => {
+                if let (Some(item), Ok(text)) = (item.as_mut(), text.decode()) {
+                    item.push_str(&text);
+                }
+            }
+            // Entities like &amp; come separately from the text around them
+            Ok(Event::GeneralRef(entity)) => {
+                if let Some(item) = item.as_mut() {
+                    if let Ok(Some(c)) = entity.resolve_char_ref() {
+                        item.push(c);
+                    } else if let Some(text) = entity
+                        .decode()
+                        .ok()
+                        .and_then(|name| quick_xml::escape::resolve_predefined_entity(&name))
+                    {
+                        item.push_str(text);
+                    }
+                }
+            }
+            Ok(Event::End(e)) if e.name().as_ref() == b"rdf:li" => {
+                if let Some(text) = item.take() {
+                    let text = text.trim();
+                    if !text.is_empty() && !keywords.iter().any(|k| k == text) {
+                        keywords.push(text.to_string());
+                    }
+                }
+            }
+            // The packet was cut short or is malformed; keep what was found
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    keywords
+}
+
+/// Keywords (dataset 2:25) of the IPTC block in a JPEG's Photoshop segment
+fn iptc_keywords(data: &[u8]) -> Vec<String> {
+    let Some(iptc) = jpeg_iptc(data) else {
+        return Vec::new();
+    };
+
+    let mut records = Vec::new();
+    let mut utf8 = false;
+    let mut i = 0;
+    while i + 5 <= iptc.len() && iptc[i] == 0x1c {
+        let (record, dataset) = (iptc[i + 1], iptc[i + 2]);
+        let len = u16::from_be_bytes([iptc[i + 3], iptc[i + 4]]) as usize;
+        // Extended lengths are only used for large binary datasets
+        if len & 0x8000 != 0 {
+            break;
+        }
+        let Some(value) = iptc.get(i + 5..i + 5 + len) else {
+            break;
+        };
+        match (record, dataset) {
+            (1, 90) => utf8 = value == b"\x1b%G",
+            (2, 25) => records.push(value),
+            _ => {}
+        }
+        i += 5 + len;
+    }
+
+    let mut keywords: Vec<String> = Vec::new();
+    for value in records {
+        let text = match std::str::from_utf8(value) {
+            Ok(text) => text.to_string(),
+            Err(_) if utf8 => String::from_utf8_lossy(value).to_string(),
+            Err(_) => encoding_rs::WINDOWS_1252.decode(value).0.to_string(),
+        };
+        let text = text.trim();
+        if !text.is_empty() && !keywords.iter().any(|k| k == text) {
+            keywords.push(text.to_string());
+        }
+    }
+    keywords
+}
+
+/// The IPTC resource (0x0404) of the APP13 "Photoshop 3.0" segment
+fn jpeg_iptc(data: &[u8]) -> Option<&[u8]> {
+    if !data.starts_with(&[0xff, 0xd8]) {
+        return None;
+    }
+    let mut i = 2;
+    while i + 4 <= data.len() && data[i] == 0xff {
+        let marker = data[i + 1];
+        // Start of scan: no more metadata segments
+        if marker == 0xda {
+            return None;
+        }
+        let len = u16::from_be_bytes([data[i + 2], data[i + 3]]) as usize;
+        let segment = data.get(i + 4..i + 2 + len)?;
+        if marker == 0xed {
+            if let Some(resources) = segment.strip_prefix(b"Photoshop 3.0\0") {
+                return photoshop_resource(resources, 0x0404);
+            }
+        }
+        i += 2 + len;
+    }
+    None
+}
+
+/// Find a resource in a list of Photoshop "8BIM" image resource blocks
+pub fn photoshop_resource(mut data: &[u8], id: u16) -> Option<&[u8]> {
+    while data.len() >= 12 && data.starts_with(b"8BIM") {
+        let resource = u16::from_be_bytes([data[4], data[5]]);
+        // Pascal string name, padded to an even length
+        let name_len = (data[6] as usize + 2) & !1;
+        let size_at = 6 + name_len;
+        let size = u32::from_be_bytes(data.get(size_at..size_at + 4)?.try_into().ok()?) as usize;
+        let body = data.get(size_at + 4..size_at + 4 + size)?;
+        if resource == id {
+            return Some(body);
+        }
+        data = data.get(size_at + 4 + ((size + 1) & !1)..)?;
+    }
+    None
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
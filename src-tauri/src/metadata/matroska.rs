@@ -0,0 +1,478 @@
+// Matroska and WebM files (EBML): duration, the first video and audio track,
+// and tags. The Info, Tracks and Tags elements are found through the
+// SeekHead, or by walking the segment up to the first Cluster.
+
+use super::media_info::{AudioStream, MediaMetadata, VideoStream};
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// Largest Info, Tracks or Tags element read
+const MAX_ELEMENT_SIZE: u64 = 16 * 1024 * 1024;
+
+const EBML: u32 = 0x1A45DFA3;
+const DOC_TYPE: u32 = 0x4282;
+const SEGMENT: u32 = 0x18538067;
+const SEEK_HEAD: u32 = 0x114D9B74;
+const SEEK: u32 = 0x4DBB;
+const SEEK_ID: u32 = 0x53AB;
+const SEEK_POSITION: u32 = 0x53AC;
+const INFO: u32 = 0x1549A966;
+const TIMESTAMP_SCALE: u32 = 0x2AD7B1;
+const DURATION: u32 = 0x4489;
+const SEGMENT_TITLE: u32 = 0x7BA9;
+const TRACKS: u32 = 0x1654AE6B;
+const TRACK_ENTRY: u32 = 0xAE;
+const TRACK_TYPE: u32 = 0x83;
+const CODEC_ID: u32 = 0x86;
+const DEFAULT_DURATION: u32 = 0x23E383;
+const VIDEO: u32 = 0xE0;
+const PIXEL_WIDTH: u32 = 0xB0;
+const PIXEL_HEIGHT: u32 = 0xBA;
+const AUDIO: u32 = 0xE1;
+const SAMPLING_FREQUENCY: u32 = 0xB5;
+const CHANNELS: u32 = 0x9F;
+const BIT_DEPTH: u32 = 0x6264;
+const TAGS: u32 = 0x1254C367;
+const TAG: u32 = 0x7373;
+const TARGETS: u32 = 0x63C0;
+const TARGET_TYPE_VALUE: u32 = 0x68CA;
+const TAG_TRACK_UID: u32 = 0x63C5;
+const TAG_EDITION_UID: u32 = 0x63C9;
+const TAG_CHAPTER_UID: u32 = 0x63C4;
+const TAG_ATTACHMENT_UID: u32 = 0x63C6;
+const SIMPLE_TAG: u32 = 0x67C8;
+const TAG_NAME: u32 = 0x45A3;
+const TAG_STRING: u32 = 0x4487;
+const CLUSTER: u32 = 0x1F43B675;
+
+/// Whether the start of a file is an EBML header
+pub fn is_matroska(head: &[u8]) -> bool {
+    head.starts_with(&EBML.to_be_bytes())
+}
+
+pub fn read<R: Read + Seek>(file: &mut R, len: u64) -> io::Result<Option<MediaMetadata>> {
+    // The EBML header says whether this is Matroska or WebM
+    let Some((EBML, Some(size), header_len)) = element_header(file)? else {
+        return Ok(None);
+    };
+    if size > MAX_ELEMENT_SIZE {
+        return Ok(None);
+    }
+    let header = read_body(file, size)?;
+    let doc_type = elements(&header)
+        .find(|&(id, _)| id == DOC_TYPE)
+        .map(|(_, body)| text(body));
+    let container = match doc_type.as_deref() {
+        Some("webm") => "WebM",
+        Some("matroska") => "Matroska",
+        _ => return Ok(None),
+    };
+
+    let mut pos = header_len + size;
+    file.seek(SeekFrom::Start(pos))?;
+    let Some((SEGMENT, segment_size, header_len)) = element_header(file)? else {
+        return Ok(None);
+    };
+    let segment_start = pos + header_len;
+    let segment_end = segment_size.map_or(len, |s| (segment_start + s).min(len));
+
+    // Walk the top-level elements, noting where the SeekHead says the rest are
+    let mut found: [Option<Vec<u8>>; 3] = [None, None, None];
+    let mut seeks = Vec::new();
+    pos = segment_start;
+    while pos < segment_end {
+        file.seek(SeekFrom::Start(pos))?;
+        let Some((id, Some(size), header_len)) = element_header(file)? else {
+            break;
+        };
+        match id {
+            CLUSTER => break,
+            SEEK_HEAD if size <= MAX_ELEMENT_SIZE => {
+                seeks.extend(seek_entries(&read_body(file, size)?));
+            }
+            INFO | TRACKS | TAGS if size <= MAX_ELEMENT_SIZE => {
+                found[slot(id)] = Some(read_body(file, size)?);
+            }
+            _ => {}
+        }
+        pos += header_len + size;
+    }
+    for (id, position) in seeks {
+        if !matches!(id, INFO | TRACKS | TAGS) || found[slot(id)].is_some() {
+            continue;
+        }
+        // A damaged SeekHead can point anywhere
+        let Some(at) = segment_start.checked_add(position).filter(|&at| at < len) else {
+            continue;
+        };
+        file.seek(SeekFrom::Start(at))?;
+        if let Some((found_id, Some(size), _)) = element_header(file)? {
+            if found_id == id && size <= MAX_ELEMENT_SIZE {
+                found[slot(id)] = Some(read_body(file, size)?);
+            }
+        }
+    }
+
+    let mut media = MediaMetadata {
+        container: Some(container.to_string()),
+        ..Default::default()
+    };
+    let [info, tracks, tags] = found;
+    if let Some(info) = info {
+        read_info(&info, &mut media);
+    }
+    if let Some(tracks) = tracks {
+        for (_, entry) in elements(&tracks).filter(|&(id, _)| id == TRACK_ENTRY) {
+            track(entry, &mut media);
+        }
+    }
+    if let Some(tags) = tags {
+        read_tags(&tags, &mut media);
+    }
+    Ok(Some(media))
+}
+
+fn slot(id: u32) -> usize {
+    match id {
+        INFO => 0,
+        TRACKS => 1,
+        _ => 2,
+    }
+}
+
+/// Read an element ID and size at the current position: the ID with its
+/// length marker, the size (None when unknown) and the header length
+fn element_header<R: Read>(file: &mut R) -> io::Result<Option<(u32, Option<u64>, u64)>> {
+    let mut header = [0u8; 12];
+    let read = file.by_ref().take(12).read(&mut header)?;
+    let header = &header[..read];
+    let Some((id, id_len)) = vint(header, 4) else {
+        return Ok(None);
+    };
+    let id = (id | (1 << (7 * id_len))) as u32;
+    let Some((size, size_len)) = vint(&header[id_len..], 8) else {
+        return Ok(None);
+    };
+    let unknown = size == (1 << (7 * size_len)) - 1;
+    Ok(Some((
+        id,
+        (!unknown).then_some(size),
+        (id_len + size_len) as u64,
+    )))
+}
+
+/// A variable-length integer without its length marker, and its length
+fn vint(data: &[u8], max_len: usize) -> Option<(u64, usize)> {
+    let first = *data.first()?;
+    let len = first.leading_zeros() as usize + 1;
+    if len > max_len {
+        return None;
+    }
+    let mut value = (first as u64) & (0xFF >> len);
+    for &byte in data.get(1..len)? {
+        value = (value << 8) | byte as u64;
+    }
+    Some((value, len))
+}
+
+fn read_body<R: Read>(file: &mut R, size: u64) -> io::Result<Vec<u8>> {
+    let mut body = Vec::with_capacity(size as usize);
+    file.by_ref().take(size).read_to_end(&mut body)?;
+    Ok(body)
+}
+
+/// The child elements in an element body
+fn elements(data: &[u8]) -> impl Iterator<Item = (u32, &[u8])> {
+    let mut pos = 0;
+    std::iter::from_fn(move || {
+        let rest = data.get(pos..)?;
+        let (id, id_len) = vint(rest, 4)?;
+        let (size, size_len) = vint(&rest[id_len..], 8)?;
+        let start = id_len + size_len;
+        let end = start.checked_add(usize::try_from(size).ok()?)?;
+        let body = rest.get(start..end)?;
+        pos += end;
+        Some(((id | (1 << (7 * id_len))) as u32, body))
+    })
+}
+
+fn uint(body: &[u8]) -> Option<u64> {
+    (!body.is_empty() && body.len() <= 8)
+        .then(|| body.iter().fold(0, |value, &b| (value << 8) | b as u64))
+}
+
+fn float(body: &[u8]) -> Option<f64> {
+    match body.len() {
+        4 => Some(f32::from_be_bytes(body.try_into().ok()?) as f64),
+        8 => Some(f64::from_be_bytes(body.try_into().ok()?)),
+        _ => None,
+    }
+}
+
+/// Strings may be padded with zeros
+fn text(body: &[u8]) -> String {
+    let end = body.iter().position(|&b| b == 0).unwrap_or(body.len());
+    String::from_utf8_lossy(&body[..end]).trim().to_string()
+}
+
+/// IDs and segment-relative positions from a SeekHead
+fn seek_entries(body: &[u8]) -> Vec<(u32, u64)> {
+    elements(body)
+        .filter(|&(id, _)| id == SEEK)
+        .filter_map(|(_, seek)| {
+            let id = elements(seek).find(|&(id, _)| id == SEEK_ID)?.1;
+            let position = elements(seek).find(|&(id, _)| id == SEEK_POSITION)?.1;
+            Some((uint(id)? as u32, uint(position)?))
+        })
+        .collect()
+}
+
+fn read_info(info: &[u8], media: &mut MediaMetadata) {
+    let mut scale = 1_000_000; // Nanoseconds per timestamp unit
+    let mut duration = None;
+    for (id, body) in elements(info) {
+        match id {
+            TIMESTAMP_SCALE => scale = uint(body).filter(|&s| s > 0).unwrap_or(scale),
+            DURATION => duration = float(body),
+            SEGMENT_TITLE => media.title = Some(text(body)).filter(|t| !t.is_empty()),
+            _ => {}
+        }
+    }
+    media.duration = duration
+        .filter(|&d| d > 0.0)
+        .map(|d| d * scale as f64 / 1e9);
+}
+
+/// Read a TrackEntry into the first video or audio stream not yet seen
+fn track(entry: &[u8], media: &mut MediaMetadata) {
+    let mut kind = None;
+    let mut codec = None;
+    let mut frame_duration = None;
+    let mut video = None;
+    let mut audio = None;
+    for (id, body) in elements(entry) {
+        match id {
+            TRACK_TYPE => kind = uint(body),
+            CODEC_ID => codec = Some(codec_name(&text(body))),
+            DEFAULT_DURATION => frame_duration = uint(body).filter(|&d| d > 0),
+            VIDEO => video = Some(body),
+            AUDIO => audio = Some(body),
+            _ => {}
+        }
+    }
+
+    match kind {
+        Some(1) if media.video.is_none() => {
+            let mut stream = VideoStream {
+                codec,
+                // Nanoseconds per frame
+                frame_rate: frame_duration.map(|d| 1e9 / d as f64),
+                ..Default::default()
+            };
+            for (id, body) in elements(video.unwrap_or(&[])) {
+                match id {
+                    PIXEL_WIDTH => stream.width = uint(body).map(|w| w as u32),
+                    PIXEL_HEIGHT => stream.height = uint(body).map(|h| h as u32),
+                    _ => {}
+                }
+            }
+            media.video = Some(stream);
+        }
+        Some(2) if media.audio.is_none() => {
+            // Defaults from the specification
+            let mut stream = AudioStream {
+                codec,
+                sample_rate: Some(8000),
+                channels: Some(1),
+                bits_per_sample: None,
+            };
+            for (id, body) in elements(audio.unwrap_or(&[])) {
+                match id {
+                    SAMPLING_FREQUENCY => {
+                        stream.sample_rate = float(body).map(|f| f.round() as u32)
+                    }
+                    CHANNELS => stream.channels = uint(body).map(|c| c as u32),
+                    BIT_DEPTH => stream.bits_per_sample = uint(body).map(|b| b as u32),
+                    _ => {}
+                }
+            }
+            media.audio = Some(stream);
+        }
+        _ => {}
+    }
+}
+
+fn codec_name(codec_id: &str) -> String {
+    let name = match codec_id {
+        "V_MPEG4/ISO/AVC" => "H.264",
+        "V_MPEGH/ISO/HEVC" => "H.265",
+        "V_AV1" => "AV1",
+        "V_VP8" => "VP8",
+        "V_VP9" => "VP9",
+        "V_MPEG2" => "MPEG-2",
+        "V_MJPEG" => "Motion JPEG",
+        "V_THEORA" => "Theora",
+        "V_PRORES" => "ProRes",
+        "A_MPEG/L3" => "MP3",
+        "A_MPEG/L2" => "MP2",
+        "A_OPUS" => "Opus",
+        "A_VORBIS" => "Vorbis",
+        "A_FLAC" => "FLAC",
+        "A_ALAC" => "ALAC",
+        "A_AC3" => "AC-3",
+        "A_EAC3" => "E-AC-3",
+        "A_DTS" => "DTS",
+        "A_TRUEHD" => "TrueHD",
+        id if id.starts_with("V_MPEG4/ISO/") => "MPEG-4 Visual",
+        id if id.starts_with("A_AAC") => "AAC",
+        id if id.starts_with("A_PCM/") => "PCM",
+        id => id,
+    };
+    name.to_string()
+}
+
+/// Tags on the whole file. Level 30 is a track or song, level 50 an album or
+/// movie; music files have both, films usually only the latter.
+fn read_tags(tags: &[u8], media: &mut MediaMetadata) {
+    let mut simple = Vec::new();
+    for (_, tag) in elements(tags).filter(|&(id, _)| id == TAG) {
+        let mut level = 50;
+        let mut whole_file = true;
+        for (id, body) in elements(tag) {
+            match id {
+                TARGETS => {
+                    for (id, body) in elements(body) {
+                        match id {
+                            TARGET_TYPE_VALUE => level = uint(body).unwrap_or(50),
+                            // UIDs narrow the tag to a track, chapter or attachment;
+                            // zero means all of them
+                            TAG_TRACK_UID | TAG_EDITION_UID | TAG_CHAPTER_UID
+                            | TAG_ATTACHMENT_UID => whole_file &= uint(body).unwrap_or(0) == 0,
+                            _ => {}
+                        }
+                    }
+                }
+                SIMPLE_TAG if whole_file => {
+                    let name = elements(body).find(|&(id, _)| id == TAG_NAME);
+                    let value = elements(body).find(|&(id, _)| id == TAG_STRING);
+                    if let (Some((_, name)), Some((_, value))) = (name, value) {
+                        let value = text(value);
+                        if !value.is_empty() {
+                            simple.push((level, text(name).to_uppercase(), value));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let has_songs = simple.iter().any(|(level, _, _)| *level < 50);
+    for (level, name, value) in simple {
+        let album_level = level >= 50 && has_songs;
+        match name.as_str() {
+            "TITLE" if album_level => media.album = Some(value),
+            "TITLE" => media.title = Some(value),
+            "ARTIST" if album_level => {
+                media.artist.get_or_insert_with(|| value.clone());
+                media.album_artist = Some(value);
+            }
+            "ARTIST" => media.artist = Some(value),
+            "GENRE" => media.genre = Some(value),
+            "DATE_RELEASED" | "DATE_RECORDED" => media.year = Some(value.chars().take(4).collect()),
+            "PART_NUMBER" if !album_level => media.track = value.parse().ok(),
+            "TOTAL_PARTS" if album_level => media.track_total = value.parse().ok(),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// An element with an 8-byte size
+    fn element(id: u32, body: &[u8]) -> Vec<u8> {
+        let id = id.to_be_bytes();
+        let id = &id[id.iter().position(|&b| b != 0).unwrap()..];
+        let size = (body.len() as u64 | 1 << 56).to_be_bytes();
+        [id, &size, body].concat()
+    }
+
+    fn seek_head(id: u32, position: u64) -> Vec<u8> {
+        let seek = [
+            element(SEEK_ID, &id.to_be_bytes()),
+            element(SEEK_POSITION, &position.to_be_bytes()),
+        ]
+        .concat();
+        element(SEEK_HEAD, &element(SEEK, &seek))
+    }
+
+    /// A WebM file whose segment, of unknown size, holds these elements
+    fn webm(segment: &[Vec<u8>]) -> Vec<u8> {
+        let mut file = element(EBML, &element(DOC_TYPE, b"webm"));
+        file.extend(SEGMENT.to_be_bytes());
+        file.extend([0x01, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+        file.extend(segment.concat());
+        file
+    }
+
+    /// Info for a 2.5 second file
+    fn info() -> Vec<u8> {
+        let info = [
+            element(TIMESTAMP_SCALE, &1_000_000u32.to_be_bytes()),
+            element(DURATION, &2500f64.to_be_bytes()),
+        ];
+        element(INFO, &info.concat())
+    }
+
+    fn read_bytes(data: Vec<u8>) -> Option<MediaMetadata> {
+        let len = data.len() as u64;
+        read(&mut Cursor::new(data), len).unwrap()
+    }
+
+    #[test]
+    fn seek_head_finds_info_after_cluster() {
+        let cluster = element(CLUSTER, &[0; 32]);
+        let position = (seek_head(INFO, 0).len() + cluster.len()) as u64;
+        let media = read_bytes(webm(&[seek_head(INFO, position), cluster, info()])).unwrap();
+        assert_eq!(media.container.as_deref(), Some("WebM"));
+        assert_eq!(media.duration, Some(2.5));
+    }
+
+    #[test]
+    fn seek_head_past_end_of_file() {
+        for position in [0x10000, u64::MAX] {
+            let cluster = element(CLUSTER, &[0; 32]);
+            let media = read_bytes(webm(&[seek_head(INFO, position), cluster])).unwrap();
+            assert_eq!(media.container.as_deref(), Some("WebM"));
+            assert!(media.duration.is_none());
+        }
+    }
+
+    #[test]
+    fn seek_head_pointing_at_another_element() {
+        let cluster = element(CLUSTER, &[0; 32]);
+        let position = seek_head(INFO, 0).len() as u64;
+        let media = read_bytes(webm(&[seek_head(INFO, position), cluster, info()])).unwrap();
+        assert!(media.duration.is_none());
+    }
+
+    #[test]
+    fn not_matroska() {
+        let file = element(EBML, &element(DOC_TYPE, b"other"));
+        assert!(read_bytes(file).is_none());
+        assert!(read_bytes(EBML.to_be_bytes().to_vec()).is_none());
+    }
+
+    #[test]
+    fn truncated_elements() {
+        // Says 16 bytes, has 2
+        let mut body = element(SEGMENT_TITLE, b"title");
+        body.extend(element(TRACKS, &[0; 16]).iter().take(10));
+        let ids: Vec<u32> = elements(&body).map(|(id, _)| id).collect();
+        assert_eq!(ids, [SEGMENT_TITLE]);
+        assert!(vint(&[0], 8).is_none());
+        assert!(vint(&[0x40], 8).is_none());
+    }
+}
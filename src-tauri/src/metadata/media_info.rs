@@ -0,0 +1,256 @@
+// Audio and video properties and tags.
+// MP4/QuickTime (mp4) and Matroska/WebM (matroska) are read by our own box
+// and element parsers, which know about video tracks. Everything else goes
+// through symphonia's format readers: ID3v2 on MP3, Vorbis comments on
+// FLAC/Ogg, and the stream parameters. Nothing is decoded.
+
+use super::{matroska, mp4};
+use crate::error::{AppError, PathContext};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use symphonia::core::codecs::*;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::{MetadataOptions, StandardTagKey, Tag};
+use symphonia::core::probe::Hint;
+
+/// Extensions worth opening for media metadata
+const MEDIA_EXTENSIONS: &[&str] = &[
+    "mp3", "flac", "ogg", "oga", "opus", "wav", "aiff", "aif", "m4a", "m4b", "mp4", "m4v", "mov",
+    "3gp", "mkv", "mka", "webm",
+];
+
+/// Linear PCM in every sample format and layout
+const PCM: &[CodecType] = &[
+    CODEC_TYPE_PCM_S32LE,
+    CODEC_TYPE_PCM_S32LE_PLANAR,
+    CODEC_TYPE_PCM_S32BE,
+    CODEC_TYPE_PCM_S32BE_PLANAR,
+    CODEC_TYPE_PCM_S24LE,
+    CODEC_TYPE_PCM_S24LE_PLANAR,
+    CODEC_TYPE_PCM_S24BE,
+    CODEC_TYPE_PCM_S24BE_PLANAR,
+    CODEC_TYPE_PCM_S16LE,
+    CODEC_TYPE_PCM_S16LE_PLANAR,
+    CODEC_TYPE_PCM_S16BE,
+    CODEC_TYPE_PCM_S16BE_PLANAR,
+    CODEC_TYPE_PCM_S8,
+    CODEC_TYPE_PCM_S8_PLANAR,
+    CODEC_TYPE_PCM_U32LE,
+    CODEC_TYPE_PCM_U32LE_PLANAR,
+    CODEC_TYPE_PCM_U32BE,
+    CODEC_TYPE_PCM_U32BE_PLANAR,
+    CODEC_TYPE_PCM_U24LE,
+    CODEC_TYPE_PCM_U24LE_PLANAR,
+    CODEC_TYPE_PCM_U24BE,
+    CODEC_TYPE_PCM_U24BE_PLANAR,
+    CODEC_TYPE_PCM_U16LE,
+    CODEC_TYPE_PCM_U16LE_PLANAR,
+    CODEC_TYPE_PCM_U16BE,
+    CODEC_TYPE_PCM_U16BE_PLANAR,
+    CODEC_TYPE_PCM_U8,
+    CODEC_TYPE_PCM_U8_PLANAR,
+    CODEC_TYPE_PCM_F32LE,
+    CODEC_TYPE_PCM_F32LE_PLANAR,
+    CODEC_TYPE_PCM_F32BE,
+    CODEC_TYPE_PCM_F32BE_PLANAR,
+    CODEC_TYPE_PCM_F64LE,
+    CODEC_TYPE_PCM_F64LE_PLANAR,
+    CODEC_TYPE_PCM_F64BE,
+    CODEC_TYPE_PCM_F64BE_PLANAR,
+];
+
+#[derive(Serialize, Clone, Default)]
+pub struct MediaMetadata {
+    pub container: Option<String>, // "MPEG-4", "Matroska", "FLAC", ...
+    pub duration: Option<f64>,     // Seconds
+    pub bitrate: Option<u32>,      // Overall, kbit/s
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub genre: Option<String>,
+    pub year: Option<String>,
+    pub track: Option<u32>,
+    pub track_total: Option<u32>,
+    pub audio: Option<AudioStream>, // The first audio stream
+    pub video: Option<VideoStream>, // The first video stream
+}
+
+#[derive(Serialize, Clone, Default)]
+pub struct AudioStream {
+    pub codec: Option<String>, // "AAC", "MP3", "FLAC", "Opus", ...
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u32>,
+    pub bits_per_sample: Option<u32>, // Lossless and PCM only
+}
+
+#[derive(Serialize, Clone, Default)]
+pub struct VideoStream {
+    pub codec: Option<String>, // "H.264", "VP9", "AV1", ...
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub frame_rate: Option<f64>, // Average frames per second
+}
+
+/// Whether a file might be audio or video, going by its extension
+pub fn is_media(path: &Path) -> bool {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .is_some_and(|e| MEDIA_EXTENSIONS.contains(&e.as_str()))
+}
+
+/// Get the tags, duration and stream properties of an audio or video file
+#[tauri::command]
+pub async fn get_media_metadata(path: String) -> Result<MediaMetadata, AppError> {
+    tauri::async_runtime::spawn_blocking(move || read(Path::new(&path)))
+        .await
+        .map_err(AppError::other)?
+}
+
+pub fn read(path: &Path) -> Result<MediaMetadata, AppError> {
+    if path.is_dir() {
+        return Err(AppError::is_a_directory(path));
+    }
+    let mut file = File::open(path).at(path)?;
+    let len = file.metadata().at(path)?.len();
+    let mut head = [0u8; 12];
+    let read = file.by_ref().take(12).read(&mut head).at(path)?;
+    let head = &head[..read];
+    file.seek(SeekFrom::Start(0)).at(path)?;
+
+    let media = if mp4::is_mp4(head) {
+        mp4::read(&mut file, len).at(path)?
+    } else if matroska::is_matroska(head) {
+        matroska::read(&mut file, len).at(path)?
+    } else {
+        probe(path, file)
+    };
+    let Some(mut media) = media.filter(|m| m.audio.is_some() || m.video.is_some()) else {
+        return Err(AppError::unsupported(path, "Not a readable media file"));
+    };
+
+    media.bitrate = media
+        .duration
+        .filter(|&d| d > 0.0)
+        .map(|d| (len as f64 * 8.0 / d / 1000.0).round() as u32);
+    Ok(media)
+}
+
+/// Read a file with symphonia; None if no format reader takes it
+fn probe(path: &Path, file: File) -> Option<MediaMetadata> {
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let mut hint = Hint::new();
+    hint.with_extension(&extension);
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .ok()?;
+
+    let mut media = MediaMetadata {
+        container: Some(container_name(&extension).to_string()),
+        ..Default::default()
+    };
+
+    // ID3v2 ahead of the stream is found by the probe; the rest by the reader
+    if let Some(mut metadata) = probed.metadata.get() {
+        if let Some(revision) = metadata.skip_to_latest() {
+            apply_tags(revision.tags(), &mut media);
+        }
+    }
+    if let Some(revision) = probed.format.metadata().skip_to_latest() {
+        apply_tags(revision.tags(), &mut media);
+    }
+
+    let params = &probed.format.default_track()?.codec_params;
+    media.duration = match (params.n_frames, params.time_base, params.sample_rate) {
+        (Some(frames), Some(base), _) => {
+            let time = base.calc_time(frames);
+            Some(time.seconds as f64 + time.frac)
+        }
+        (Some(frames), None, Some(rate)) if rate > 0 => Some(frames as f64 / rate as f64),
+        _ => None,
+    };
+    media.audio = Some(AudioStream {
+        codec: codec_name(params.codec).map(str::to_string),
+        sample_rate: params.sample_rate,
+        channels: params.channels.map(|c| c.count() as u32),
+        bits_per_sample: params.bits_per_sample,
+    });
+    Some(media)
+}
+
+/// Fill the fields of the standard tags a file has; later tags win
+fn apply_tags(tags: &[Tag], media: &mut MediaMetadata) {
+    for tag in tags {
+        let text = tag.value.to_string().trim().to_string();
+        if text.is_empty() {
+            continue;
+        }
+        match tag.std_key {
+            Some(StandardTagKey::TrackTitle) => media.title = Some(text),
+            Some(StandardTagKey::Artist) => media.artist = Some(text),
+            Some(StandardTagKey::Album) => media.album = Some(text),
+            Some(StandardTagKey::AlbumArtist) => media.album_artist = Some(text),
+            Some(StandardTagKey::Genre) => media.genre = Some(text),
+            Some(StandardTagKey::Date | StandardTagKey::ReleaseDate) => {
+                media.year = Some(text.chars().take(4).collect())
+            }
+            // Either "3" or "3/12"
+            Some(StandardTagKey::TrackNumber) => {
+                let mut parts = text.split('/');
+                media.track = parts.next().and_then(|t| t.trim().parse().ok());
+                if let Some(total) = parts.next().and_then(|t| t.trim().parse().ok()) {
+                    media.track_total = Some(total);
+                }
+            }
+            Some(StandardTagKey::TrackTotal) => media.track_total = text.parse().ok(),
+            _ => {}
+        }
+    }
+}
+
+fn container_name(extension: &str) -> &'static str {
+    match extension {
+        "mp3" => "MP3",
+        "flac" => "FLAC",
+        "ogg" | "oga" | "opus" => "Ogg",
+        "wav" => "WAVE",
+        "aiff" | "aif" => "AIFF",
+        _ => "Audio",
+    }
+}
+
+fn codec_name(codec: CodecType) -> Option<&'static str> {
+    Some(match codec {
+        CODEC_TYPE_MP1 => "MP1",
+        CODEC_TYPE_MP2 => "MP2",
+        CODEC_TYPE_MP3 => "MP3",
+        CODEC_TYPE_AAC => "AAC",
+        CODEC_TYPE_VORBIS => "Vorbis",
+        CODEC_TYPE_OPUS => "Opus",
+        CODEC_TYPE_FLAC => "FLAC",
+        CODEC_TYPE_ALAC => "ALAC",
+        CODEC_TYPE_EAC3 => "E-AC-3",
+        CODEC_TYPE_ADPCM_G722
+        | CODEC_TYPE_ADPCM_G726
+        | CODEC_TYPE_ADPCM_G726LE
+        | CODEC_TYPE_ADPCM_MS
+        | CODEC_TYPE_ADPCM_IMA_WAV
+        | CODEC_TYPE_ADPCM_IMA_QT => "ADPCM",
+        CODEC_TYPE_PCM_ALAW => "A-law",
+        CODEC_TYPE_PCM_MULAW => "µ-law",
+        _ if PCM.contains(&codec) => "PCM",
+        _ => return None,
+    })
+}
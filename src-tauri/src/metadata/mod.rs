@@ -0,0 +1,283 @@
+// Metadata read from inside files: image properties and EXIF (image_info),
+// and audio/video streams and tags (media_info, with the mp4 and matroska
+// container parsers).
+// Besides the commands that return all of it, listings can ask for some
+// fields as extra columns and searches can filter on them. Either way only
+// files whose extension suggests there's something to read are opened.
+
+pub mod image_info;
+mod matroska;
+pub mod media_info;
+mod mp4;
+
+use image_info::ImageMetadata;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Extra columns a listing can ask for
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Column {
+    Dimensions,
+    Camera,
+    Lens,
+    DateTaken,
+    ExposureTime,
+    FNumber,
+    Iso,
+    FocalLength,
+    Orientation,
+    Location,
+    Keywords,
+    Duration,
+    Bitrate,
+    Title,
+    Artist,
+    Album,
+    Track,
+    SampleRate,
+    FrameRate,
+    VideoCodec,
+    AudioCodec,
+}
+
+/// Values of the extra columns of one file. Only the columns asked for are
+/// filled, and only those the file has.
+#[derive(Serialize, Clone, Default)]
+pub struct Columns {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub camera: Option<String>, // Make and model, like "SONY ILCE-7M3"
+    pub lens: Option<String>,
+    pub date_taken: Option<String>, // "YYYY-MM-DD HH:MM:SS"
+    pub exposure_time: Option<String>,
+    pub f_number: Option<f64>,
+    pub iso: Option<u32>,
+    pub focal_length: Option<f64>,
+    pub orientation: Option<u16>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub keywords: Option<Vec<String>>,
+    pub duration: Option<f64>, // Seconds
+    pub bitrate: Option<u32>,  // kbit/s
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub track: Option<u32>,
+    pub sample_rate: Option<u32>,
+    pub frame_rate: Option<f64>,
+    pub video_codec: Option<String>,
+    pub audio_codec: Option<String>,
+}
+
+/// Search criteria on metadata. Files that don't have a field a criterion
+/// needs don't match.
+#[derive(Deserialize, Default)]
+pub struct MetadataFilter {
+    pub min_width: Option<u32>,
+    pub max_width: Option<u32>,
+    pub min_height: Option<u32>,
+    pub max_height: Option<u32>,
+    pub camera: Option<String>,      // Part of the make or model, any case
+    pub lens: Option<String>,        // Part of the lens model, any case
+    pub taken_after: Option<String>, // "YYYY-MM-DD", inclusive
+    pub taken_before: Option<String>, // "YYYY-MM-DD", inclusive
+    pub min_iso: Option<u32>,
+    pub max_iso: Option<u32>,
+    pub has_location: Option<bool>,
+    pub keyword: Option<String>, // One of the keywords, any case
+}
+
+/// Read the requested columns of a file; None if there's nothing to show
+pub fn columns(path: &Path, columns: &[Column]) -> Option<Columns> {
+    if image_info::is_image(path) && columns.iter().any(|c| c.for_images()) {
+        image_columns(path, columns)
+    } else if media_info::is_media(path) && columns.iter().any(|c| c.for_media()) {
+        media_columns(path, columns)
+    } else {
+        None
+    }
+}
+
+fn image_columns(path: &Path, columns: &[Column]) -> Option<Columns> {
+    let image = image_info::read(path, columns.contains(&Column::Keywords)).ok()?;
+    Some(image_values(&image, columns))
+}
+
+fn image_values(image: &ImageMetadata, columns: &[Column]) -> Columns {
+    let exif = image.exif.clone().unwrap_or_default();
+
+    let mut values = Columns::default();
+    for column in columns {
+        match column {
+            Column::Dimensions => {
+                values.width = image.width;
+                values.height = image.height;
+            }
+            Column::Camera => values.camera = camera(image),
+            Column::Lens => values.lens = exif.lens_model.clone(),
+            Column::DateTaken => values.date_taken = exif.date_taken.clone(),
+            Column::ExposureTime => values.exposure_time = exif.exposure_time.clone(),
+            Column::FNumber => values.f_number = exif.f_number,
+            Column::Iso => values.iso = exif.iso,
+            Column::FocalLength => values.focal_length = exif.focal_length,
+            Column::Orientation => values.orientation = exif.orientation,
+            Column::Location => {
+                values.latitude = exif.gps.map(|g| g.latitude);
+                values.longitude = exif.gps.map(|g| g.longitude);
+            }
+            Column::Keywords => values.keywords = Some(image.keywords.clone()),
+            _ => {}
+        }
+    }
+    values
+}
+
+fn media_columns(path: &Path, columns: &[Column]) -> Option<Columns> {
+    let media = media_info::read(path).ok()?;
+    let video = media.video.clone().unwrap_or_default();
+    let audio = media.audio.clone().unwrap_or_default();
+
+    let mut values = Columns::default();
+    for column in columns {
+        match column {
+            Column::Dimensions => {
+                values.width = video.width;
+                values.height = video.height;
+            }
+            Column::Duration => values.duration = media.duration,
+            Column::Bitrate => values.bitrate = media.bitrate,
+            Column::Title => values.title = media.title.clone(),
+            Column::Artist => values.artist = media.artist.clone(),
+            Column::Album => values.album = media.album.clone(),
+            Column::Track => values.track = media.track,
+            Column::SampleRate => values.sample_rate = audio.sample_rate,
+            Column::FrameRate => values.frame_rate = video.frame_rate,
+            Column::VideoCodec => values.video_codec = video.codec.clone(),
+            Column::AudioCodec => values.audio_codec = audio.codec.clone(),
+            _ => {}
+        }
+    }
+    Some(values)
+}
+
+impl Column {
+    /// Whether images can have this column
+    fn for_images(self) -> bool {
+        matches!(
+            self,
+            Column::Dimensions
+                | Column::Camera
+                | Column::Lens
+                | Column::DateTaken
+                | Column::ExposureTime
+                | Column::FNumber
+                | Column::Iso
+                | Column::FocalLength
+                | Column::Orientation
+                | Column::Location
+                | Column::Keywords
+        )
+    }
+
+    /// Whether audio or video files can have this column
+    fn for_media(self) -> bool {
+        matches!(self, Column::Dimensions) || !self.for_images()
+    }
+}
+
+/// The model, with the make in front unless the model already starts with it
+fn camera(image: &ImageMetadata) -> Option<String> {
+    let exif = image.exif.as_ref()?;
+    let model = exif.camera_model.as_deref()?;
+    let make = exif.camera_make.as_deref().unwrap_or("");
+    let brand = make.split_whitespace().next().unwrap_or("");
+    if brand.is_empty() || model.to_lowercase().starts_with(&brand.to_lowercase()) {
+        Some(model.to_string())
+    } else {
+        Some(format!("{} {}", brand, model))
+    }
+}
+
+impl MetadataFilter {
+    pub fn is_empty(&self) -> bool {
+        self.min_width.is_none()
+            && self.max_width.is_none()
+            && self.min_height.is_none()
+            && self.max_height.is_none()
+            && self.camera.is_none()
+            && self.lens.is_none()
+            && self.taken_after.is_none()
+            && self.taken_before.is_none()
+            && self.min_iso.is_none()
+            && self.max_iso.is_none()
+            && self.has_location.is_none()
+            && self.keyword.is_none()
+    }
+
+    /// Check a file against every criterion and read the columns asked for,
+    /// opening it once for both. None if it doesn't match; without criteria,
+    /// every file does.
+    pub fn matching_columns(&self, path: &Path, columns: &[Column]) -> Option<Option<Columns>> {
+        if self.is_empty() {
+            return Some(self::columns(path, columns));
+        }
+        if !image_info::is_image(path) || !path.is_file() {
+            return None;
+        }
+        let keywords = self.keyword.is_some() || columns.contains(&Column::Keywords);
+        let image = image_info::read(path, keywords).ok()?;
+        if !self.matches(&image) {
+            return None;
+        }
+        // Only images match, so only their columns can have values
+        Some(
+            columns
+                .iter()
+                .any(|c| c.for_images())
+                .then(|| image_values(&image, columns)),
+        )
+    }
+
+    fn matches(&self, image: &ImageMetadata) -> bool {
+        let exif = image.exif.clone().unwrap_or_default();
+
+        let contains = |value: &Option<String>, part: &Option<String>| match part {
+            Some(part) => value
+                .as_ref()
+                .is_some_and(|v| v.to_lowercase().contains(&part.to_lowercase())),
+            None => true,
+        };
+        let date = exif.date_taken.as_deref().map(|d| &d[..10]);
+
+        in_range(image.width, self.min_width, self.max_width)
+            && in_range(image.height, self.min_height, self.max_height)
+            && (contains(&exif.camera_make, &self.camera)
+                || contains(&exif.camera_model, &self.camera))
+            && contains(&exif.lens_model, &self.lens)
+            && in_range(
+                date,
+                self.taken_after.as_deref(),
+                self.taken_before.as_deref(),
+            )
+            && in_range(exif.iso, self.min_iso, self.max_iso)
+            && self
+                .has_location
+                .is_none_or(|wanted| exif.gps.is_some() == wanted)
+            && self.keyword.as_ref().is_none_or(|keyword| {
+                image
+                    .keywords
+                    .iter()
+                    .any(|k| k.to_lowercase() == keyword.to_lowercase())
+            })
+    }
+}
+
+/// Whether a value is within inclusive bounds; a missing value only passes
+/// when there are no bounds
+fn in_range<T: PartialOrd>(value: Option<T>, min: Option<T>, max: Option<T>) -> bool {
+    if min.is_none() && max.is_none() {
+        return true;
+    }
+    value.is_some_and(|v| min.is_none_or(|min| v >= min) && max.is_none_or(|max| v <= max))
+}
@@ -0,0 +1,395 @@
+// MP4 and QuickTime files (ISO base media): duration, the first video and
+// audio track, and iTunes-style tags. Only the moov box is read, wherever it
+// is in the file; mdat is skipped over.
+
+use super::media_info::{AudioStream, MediaMetadata, VideoStream};
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// Largest moov box read; real ones are a few MB at most
+const MAX_MOOV_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Whether the start of a file looks like an ISO base media file
+pub fn is_mp4(head: &[u8]) -> bool {
+    head.get(4..8)
+        .is_some_and(|t| matches!(t, b"ftyp" | b"moov" | b"mdat" | b"wide" | b"free" | b"skip"))
+}
+
+pub fn read<R: Read + Seek>(file: &mut R, len: u64) -> io::Result<Option<MediaMetadata>> {
+    // Find moov among the top-level boxes
+    let mut pos = 0;
+    let mut brand = None;
+    let moov = loop {
+        if pos + 8 > len {
+            return Ok(None);
+        }
+        file.seek(SeekFrom::Start(pos))?;
+        let mut header = [0u8; 16];
+        let read = file.by_ref().take(16).read(&mut header)?;
+        let Some((kind, header_len, size)) = box_header(&header[..read], len - pos) else {
+            return Ok(None);
+        };
+        if kind == *b"ftyp" && size >= header_len + 4 {
+            file.seek(SeekFrom::Start(pos + header_len))?;
+            let mut major = [0u8; 4];
+            file.read_exact(&mut major)?;
+            brand = Some(major);
+        }
+        if kind == *b"moov" {
+            if size > MAX_MOOV_SIZE {
+                return Ok(None);
+            }
+            file.seek(SeekFrom::Start(pos + header_len))?;
+            let mut moov = Vec::with_capacity((size - header_len) as usize);
+            file.by_ref()
+                .take(size - header_len)
+                .read_to_end(&mut moov)?;
+            break moov;
+        }
+        pos += size;
+    };
+
+    let mut media = MediaMetadata {
+        container: Some(match brand.as_ref() {
+            Some(b"qt  ") => "QuickTime".to_string(),
+            Some(b"M4A " | b"M4B " | b"M4P ") => "MPEG-4 Audio".to_string(),
+            Some(b) if b.starts_with(b"3g") => "3GPP".to_string(),
+            _ => "MPEG-4".to_string(),
+        }),
+        ..Default::default()
+    };
+    let mut movie_duration = None;
+    for (kind, body) in boxes(&moov) {
+        match &kind {
+            b"mvhd" => movie_duration = mvhd_duration(body),
+            b"trak" => track(body, &mut media),
+            b"udta" => {
+                if let Some((_, meta)) = boxes(body).find(|(k, _)| k == b"meta") {
+                    tags(meta, &mut media);
+                }
+            }
+            b"meta" => tags(body, &mut media),
+            _ => {}
+        }
+    }
+    media.duration = movie_duration.or(media.duration);
+    Ok(Some(media))
+}
+
+/// Type, header length and total size of the box at the start of data
+fn box_header(data: &[u8], remaining: u64) -> Option<([u8; 4], u64, u64)> {
+    let size = u32::from_be_bytes(data.get(0..4)?.try_into().ok()?) as u64;
+    let kind: [u8; 4] = data.get(4..8)?.try_into().ok()?;
+    let (header_len, size) = match size {
+        0 => (8, remaining), // Runs to the end of the file
+        1 => (16, u64::from_be_bytes(data.get(8..16)?.try_into().ok()?)),
+        _ => (8, size),
+    };
+    (size >= header_len && size <= remaining).then_some((kind, header_len, size))
+}
+
+/// The child boxes in a box body
+fn boxes(data: &[u8]) -> impl Iterator<Item = ([u8; 4], &[u8])> {
+    let mut pos = 0;
+    std::iter::from_fn(move || {
+        let rest = data.get(pos..)?;
+        let (kind, header_len, size) = box_header(rest, rest.len() as u64)?;
+        pos += size as usize;
+        Some((kind, &rest[header_len as usize..size as usize]))
+    })
+}
+
+fn u16_at(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn u64_at(data: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(data.get(at..at + 8)?.try_into().ok()?))
+}
+
+/// Timescale and duration of an mvhd or mdhd box, which share their layout
+fn timescale_and_duration(body: &[u8]) -> Option<(u32, u64)> {
+    match body.first()? {
+        1 => Some((u32_at(body, 20)?, u64_at(body, 24)?)),
+        _ => Some((u32_at(body, 12)?, u32_at(body, 16)? as u64)),
+    }
+}
+
+fn mvhd_duration(body: &[u8]) -> Option<f64> {
+    let (timescale, duration) = timescale_and_duration(body)?;
+    (timescale > 0 && duration > 0 && duration != u32::MAX as u64)
+        .then(|| duration as f64 / timescale as f64)
+}
+
+/// Read a trak box into the first video or audio stream not yet seen
+fn track(trak: &[u8], media: &mut MediaMetadata) {
+    let mut handler = None;
+    let mut timescale_duration = None;
+    let mut sample_entry = None;
+    let mut sample_count = 0u64;
+
+    let Some((_, mdia)) = boxes(trak).find(|(k, _)| k == b"mdia") else {
+        return;
+    };
+    for (kind, body) in boxes(mdia) {
+        match &kind {
+            b"mdhd" => timescale_duration = timescale_and_duration(body),
+            b"hdlr" => handler = body.get(8..12).map(|h| [h[0], h[1], h[2], h[3]]),
+            b"minf" => {
+                let Some((_, stbl)) = boxes(body).find(|(k, _)| k == b"stbl") else {
+                    continue;
+                };
+                for (kind, body) in boxes(stbl) {
+                    match &kind {
+                        // First sample entry, after the version and entry count
+                        b"stsd" => sample_entry = body.get(8..).and_then(|e| boxes(e).next()),
+                        b"stts" => sample_count = stts_samples(body),
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let duration = timescale_duration
+        .filter(|&(timescale, duration)| timescale > 0 && duration > 0)
+        .map(|(timescale, duration)| duration as f64 / timescale as f64);
+    if media.duration.is_none() {
+        media.duration = duration;
+    }
+    let Some((format, entry)) = sample_entry else {
+        return;
+    };
+
+    match handler.as_ref() {
+        Some(b"vide") if media.video.is_none() => {
+            // Visual sample entry: 6 reserved, data reference, 16 predefined, then size
+            media.video = Some(VideoStream {
+                codec: Some(codec_name(&format, entry)),
+                width: u16_at(entry, 24).map(u32::from),
+                height: u16_at(entry, 26).map(u32::from),
+                frame_rate: duration
+                    .filter(|_| sample_count > 1)
+                    .map(|d| sample_count as f64 / d),
+            });
+        }
+        Some(b"soun") if media.audio.is_none() => {
+            // Audio sample entry: 6 reserved, data reference, 8 reserved or
+            // QuickTime version fields, channels, sample size, 4 predefined,
+            // then the rate as 16.16 fixed point
+            let channels = u16_at(entry, 16).filter(|&c| c > 0);
+            let rate = u32_at(entry, 24).map(|r| r >> 16).filter(|&r| r > 0);
+            let timescale = timescale_duration.map(|(t, _)| t);
+            media.audio = Some(AudioStream {
+                codec: Some(codec_name(&format, entry)),
+                // Rates over 65535 Hz don't fit; the track timescale is usually the rate
+                sample_rate: rate.filter(|&r| r > 1).or(timescale),
+                channels: channels.map(u32::from),
+                bits_per_sample: u16_at(entry, 18)
+                    .filter(|_| {
+                        matches!(
+                            &format,
+                            b"lpcm" | b"sowt" | b"twos" | b"ipcm" | b"fLaC" | b"alac"
+                        )
+                    })
+                    .map(u32::from),
+            });
+        }
+        _ => {}
+    }
+}
+
+fn stts_samples(body: &[u8]) -> u64 {
+    let count = u32_at(body, 4).unwrap_or(0) as usize;
+    (0..count)
+        .map_while(|i| u32_at(body, 8 + i * 8))
+        .map(u64::from)
+        .sum()
+}
+
+fn codec_name(format: &[u8; 4], entry: &[u8]) -> String {
+    match format {
+        b"avc1" | b"avc3" => "H.264",
+        b"hvc1" | b"hev1" => "H.265",
+        b"av01" => "AV1",
+        b"vp08" => "VP8",
+        b"vp09" => "VP9",
+        b"mp4v" => "MPEG-4 Visual",
+        b"apch" | b"apcn" | b"apcs" | b"apco" | b"ap4h" | b"ap4x" => "ProRes",
+        b"jpeg" | b"mjpa" | b"mjpb" => "Motion JPEG",
+        b"mp4a" => return mp4a_codec(entry).to_string(),
+        b"ac-3" => "AC-3",
+        b"ec-3" => "E-AC-3",
+        b"Opus" => "Opus",
+        b"fLaC" => "FLAC",
+        b"alac" => "ALAC",
+        b".mp3" => "MP3",
+        b"lpcm" | b"sowt" | b"twos" | b"ipcm" => "PCM",
+        _ => return String::from_utf8_lossy(format).trim().to_string(),
+    }
+    .to_string()
+}
+
+/// mp4a is usually AAC, but the object type in the esds box can say MP3
+fn mp4a_codec(entry: &[u8]) -> &'static str {
+    // Children follow the sample entry, which QuickTime versions 1 and 2 extend
+    let children_at = match u16_at(entry, 8) {
+        Some(1) => 44,
+        Some(2) => 64,
+        _ => 28,
+    };
+    let object_type = entry
+        .get(children_at..)
+        .and_then(|children| boxes(children).find(|(k, _)| k == b"esds"))
+        .and_then(|(_, esds)| esds_object_type(esds.get(4..)?));
+    match object_type {
+        Some(0x69 | 0x6b) => "MP3",
+        _ => "AAC",
+    }
+}
+
+/// The object type in the decoder config of an ES descriptor
+fn esds_object_type(data: &[u8]) -> Option<u8> {
+    // A tag, then a length of up to four bytes with 7 bits each
+    let descriptor = |data: &[u8], tag: u8| -> Option<usize> {
+        if *data.first()? != tag {
+            return None;
+        }
+        let len_bytes = data[1..]
+            .iter()
+            .take(4)
+            .take_while(|&&b| b & 0x80 != 0)
+            .count()
+            + 1;
+        Some(1 + len_bytes)
+    };
+    let mut at = descriptor(data, 0x03)?;
+    let flags = *data.get(at + 2)?;
+    at += 3;
+    if flags & 0x80 != 0 {
+        at += 2; // Depends on ES_ID
+    }
+    if flags & 0x40 != 0 {
+        at += 1 + *data.get(at)? as usize; // URL
+    }
+    if flags & 0x20 != 0 {
+        at += 2; // OCR ES_ID
+    }
+    at += descriptor(data.get(at..)?, 0x04)?;
+    data.get(at).copied()
+}
+
+/// iTunes-style tags in meta/ilst
+fn tags(meta: &[u8], media: &mut MediaMetadata) {
+    // ISO meta is a full box; QuickTime's isn't
+    let children = if meta.get(4..8) == Some(b"hdlr") {
+        meta
+    } else {
+        meta.get(4..).unwrap_or(&[])
+    };
+    let Some((_, ilst)) = boxes(children).find(|(k, _)| k == b"ilst") else {
+        return;
+    };
+
+    for (kind, item) in boxes(ilst) {
+        // The value is in a data box: type, locale, then the value
+        let Some((_, data)) = boxes(item).find(|(k, _)| k == b"data") else {
+            continue;
+        };
+        let value = data.get(8..).unwrap_or(&[]);
+        let text = || {
+            let text = String::from_utf8_lossy(value).trim().to_string();
+            (!text.is_empty()).then_some(text)
+        };
+        match &kind {
+            b"\xa9nam" => media.title = text(),
+            b"\xa9ART" => media.artist = text(),
+            b"\xa9alb" => media.album = text(),
+            b"aART" => media.album_artist = text(),
+            b"\xa9gen" => media.genre = text(),
+            b"\xa9day" => media.year = text().map(|d| d.chars().take(4).collect()),
+            b"trkn" => {
+                media.track = u16_at(value, 2).filter(|&t| t > 0).map(u32::from);
+                media.track_total = u16_at(value, 4).filter(|&t| t > 0).map(u32::from);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn mp4_box(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        [&(8 + body.len() as u32).to_be_bytes()[..], kind, body].concat()
+    }
+
+    /// A version 0 mvhd: 1000 units a second, 5 seconds long
+    fn mvhd() -> Vec<u8> {
+        let mut body = vec![0; 12];
+        body.extend(1000u32.to_be_bytes());
+        body.extend(5000u32.to_be_bytes());
+        body.resize(100, 0);
+        mp4_box(b"mvhd", &body)
+    }
+
+    fn read_bytes(data: Vec<u8>) -> Option<MediaMetadata> {
+        let len = data.len() as u64;
+        read(&mut Cursor::new(data), len).unwrap()
+    }
+
+    #[test]
+    fn moov_after_other_boxes() {
+        let mut file = mp4_box(b"ftyp", b"qt  \0\0\0\0qt  ");
+        file.extend(mp4_box(b"mdat", &[0; 64]));
+        file.extend(mp4_box(b"moov", &mvhd()));
+        let media = read_bytes(file).unwrap();
+        assert_eq!(media.container.as_deref(), Some("QuickTime"));
+        assert_eq!(media.duration, Some(5.0));
+    }
+
+    #[test]
+    fn truncated_box() {
+        // moov says it's longer than the file
+        let mut file = mp4_box(b"ftyp", b"isom");
+        let mut moov = mp4_box(b"moov", &mvhd());
+        moov[..4].copy_from_slice(&1000u32.to_be_bytes());
+        file.extend(moov);
+        assert!(read_bytes(file).is_none());
+
+        // Only a header, with no room for the brand
+        assert!(read_bytes(mp4_box(b"ftyp", b"")).is_none());
+        assert!(read_bytes(b"\0\0\0\x10moov".to_vec()).is_none());
+    }
+
+    #[test]
+    fn truncated_child_box() {
+        // A trak running past the end of moov is dropped, along with what follows
+        let mut trak = mp4_box(b"trak", &[0; 32]);
+        trak[..4].copy_from_slice(&500u32.to_be_bytes());
+        let mut moov = mvhd();
+        moov.extend(trak);
+        let media = read_bytes(mp4_box(b"moov", &moov)).unwrap();
+        assert_eq!(media.container.as_deref(), Some("MPEG-4"));
+        assert_eq!(media.duration, Some(5.0));
+        assert!(media.video.is_none() && media.audio.is_none());
+    }
+
+    #[test]
+    fn box_sizes() {
+        // 64-bit size smaller than its own header
+        let mut data = 1u32.to_be_bytes().to_vec();
+        data.extend(b"free");
+        data.extend(8u64.to_be_bytes());
+        assert!(box_header(&data, 100).is_none());
+        // Size 0 runs to the end
+        assert_eq!(box_header(b"\0\0\0\0mdat", 40), Some((*b"mdat", 8, 40)));
+        assert!(boxes(b"\0\0\0\x04free").next().is_none());
+    }
+}
@@ -0,0 +1,149 @@
+// Guessing how a text file is encoded from its first few KB: byte order marks,
+// UTF-16 without a BOM, UTF-8, then the common legacy code pages. The same
+// sample decides whether the file is text at all.
+
+use encoding_rs::{
+    DecoderResult, Encoding, EUC_KR, GBK, SHIFT_JIS, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1251,
+    WINDOWS_1252,
+};
+
+/// Bytes looked at to detect the encoding and tell text from binary
+pub const SAMPLE_SIZE: usize = 8 * 1024;
+
+/// Encoding of a text file
+pub struct Detected {
+    pub encoding: &'static Encoding,
+    pub bom_len: usize, // Bytes to skip at the start of the file
+}
+
+/// Detect the encoding of a file from its first bytes.
+/// Returns None if the sample looks like binary data.
+pub fn detect(sample: &[u8]) -> Option<Detected> {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(sample) {
+        return Some(Detected { encoding, bom_len });
+    }
+
+    if let Some(encoding) = utf16_without_bom(sample) {
+        return Some(Detected {
+            encoding,
+            bom_len: 0,
+        });
+    }
+
+    if looks_binary(sample) {
+        return None;
+    }
+
+    Some(Detected {
+        encoding: guess_encoding(sample),
+        bom_len: 0,
+    })
+}
+
+/// Text in UTF-16 is mostly ASCII with a zero high byte, so one of the two
+/// byte positions is zero far more often than the other
+fn utf16_without_bom(sample: &[u8]) -> Option<&'static Encoding> {
+    let units = sample.len() / 2;
+    if units < 2 {
+        return None;
+    }
+
+    let zeros_at = |offset: usize| {
+        sample
+            .chunks_exact(2)
+            .filter(|unit| unit[offset] == 0)
+            .count()
+    };
+    let (even, odd) = (zeros_at(0), zeros_at(1));
+
+    if odd * 10 >= units * 3 && even * 20 <= units {
+        Some(UTF_16LE)
+    } else if even * 10 >= units * 3 && odd * 20 <= units {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+/// Text never contains NUL and only a few control characters
+fn looks_binary(sample: &[u8]) -> bool {
+    if sample.contains(&0) {
+        return true;
+    }
+
+    let controls = sample
+        .iter()
+        .filter(|&&b| {
+            (b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0c | 0x1b | 0x08 | 0x1a))
+                || b == 0x7f
+        })
+        .count();
+    controls * 20 > sample.len()
+}
+
+fn guess_encoding(sample: &[u8]) -> &'static Encoding {
+    // A multi-byte character may be cut off at the end of the sample
+    match std::str::from_utf8(sample) {
+        Ok(_) => return UTF_8,
+        Err(e) if e.error_len().is_none() => return UTF_8,
+        Err(_) => {}
+    }
+
+    // East Asian multi-byte encodings, if the sample decodes cleanly into
+    // mostly their own script
+    if let Some(text) = decode_cleanly(SHIFT_JIS, sample) {
+        if script_share(&text, |c| ('\u{3040}'..='\u{30ff}').contains(&c)) >= 0.2 {
+            return SHIFT_JIS;
+        }
+    }
+    if let Some(text) = decode_cleanly(EUC_KR, sample) {
+        // Korean separates words with spaces, Chinese doesn't; both decode
+        // as the other more often than not
+        let spaces = text.chars().filter(|&c| c == ' ').count();
+        if script_share(&text, |c| ('\u{ac00}'..='\u{d7a3}').contains(&c)) >= 0.5
+            && spaces * 20 >= text.chars().count()
+        {
+            return EUC_KR;
+        }
+    }
+    if let Some(text) = decode_cleanly(GBK, sample) {
+        if script_share(&text, |c| ('\u{4e00}'..='\u{9fff}').contains(&c)) >= 0.5 {
+            return GBK;
+        }
+    }
+
+    // Single-byte code pages put accented and non-Latin letters at 0xC0-0xFF.
+    // Western text uses them now and then, Cyrillic text for most letters.
+    let high = sample.iter().filter(|&&b| b >= 0xc0).count();
+    let ascii = sample.iter().filter(|b| b.is_ascii_alphabetic()).count();
+    if high > ascii {
+        WINDOWS_1251
+    } else {
+        WINDOWS_1252
+    }
+}
+
+/// Decode without replacement characters, allowing a sequence cut off at the end
+fn decode_cleanly(encoding: &'static Encoding, sample: &[u8]) -> Option<String> {
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    let mut text =
+        String::with_capacity(decoder.max_utf8_buffer_length_without_replacement(sample.len())?);
+    let (result, _) = decoder.decode_to_string_without_replacement(sample, &mut text, false);
+    (result == DecoderResult::InputEmpty).then_some(text)
+}
+
+/// Share of the non-ASCII characters that belong to a script
+fn script_share(text: &str, in_script: impl Fn(char) -> bool) -> f64 {
+    let (mut total, mut matching) = (0, 0);
+    for c in text.chars().filter(|c| !c.is_ascii()) {
+        total += 1;
+        if in_script(c) {
+            matching += 1;
+        }
+    }
+    if total == 0 {
+        0.0
+    } else {
+        matching as f64 / total as f64
+    }
+}
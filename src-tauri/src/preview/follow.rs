@@ -0,0 +1,116 @@
+// Following a growing file like `tail -f`.
+// Runs as a job until cancel_job: the file is polled for new whole lines,
+// which are sent as "file-follow" events. A truncated file is read again from
+// the start, and so is a new file put in its place by log rotation.
+
+use super::text_file::{TextFile, MAX_PAGE_BYTES};
+use crate::error::AppError;
+use crate::identity;
+use crate::jobs::{job_error, Job};
+use serde::Serialize;
+use std::path::Path;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// Time between two checks of the file
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Payload of "file-follow" events
+#[derive(Serialize, Clone)]
+pub struct FollowEvent {
+    pub job_id: String,
+    pub content: String, // New lines, joined with "\n"
+    pub line_count: usize,
+    pub start_offset: u64,
+    pub end_offset: u64,
+    pub file_len: u64,
+    pub truncated: bool, // The file was truncated or replaced; content starts it over
+}
+
+/// Follow a file from a byte offset (by default its end) until the job is
+/// cancelled, which resolves the command
+#[tauri::command]
+pub async fn follow_file(
+    app: AppHandle,
+    job_id: String,
+    path: String,
+    offset: Option<u64>,
+) -> Result<(), AppError> {
+    let job = Job::start(&app, job_id.clone())?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        follow(&app, &job, &job_id, Path::new(&path), offset).map_err(|e| job_error(&job, e))
+    })
+    .await
+    .map_err(AppError::other)?
+}
+
+fn follow(
+    app: &AppHandle,
+    job: &Job,
+    job_id: &str,
+    path: &Path,
+    offset: Option<u64>,
+) -> Result<(), AppError> {
+    let mut file = TextFile::open(path)?;
+    let mut id = identity::file_id(path).ok();
+    let mut pos = file.align(offset.unwrap_or(file.len));
+    let mut truncated = false;
+
+    while !job.is_cancelled() {
+        std::thread::sleep(POLL_INTERVAL);
+
+        // Rotated: another file now has the name. While the name is missing,
+        // or the new file can't be opened yet, keep reading the old one.
+        let current = identity::file_id(path).ok();
+        if current.is_some() && current != id {
+            if let Ok(rotated) = TextFile::open(path) {
+                file = rotated;
+                id = current;
+                pos = file.data_start;
+                truncated = true;
+            }
+        }
+
+        let len = file.refresh_len()?;
+        if len < pos {
+            pos = file.data_start;
+            truncated = true;
+        }
+
+        // Only whole lines, unless a single line fills a whole page
+        let mut end = pos;
+        if len > pos {
+            let bytes = file.read_range(pos, pos + MAX_PAGE_BYTES)?;
+            end = match file.newline.ends_in(&bytes, pos).last() {
+                Some(&line_end) => line_end,
+                None if bytes.len() as u64 == MAX_PAGE_BYTES => {
+                    let cut = file.align(pos + MAX_PAGE_BYTES);
+                    file.char_boundary(pos, cut)?
+                }
+                None => pos,
+            };
+        }
+
+        if end > pos || truncated {
+            let bytes = file.read_range(pos, end)?;
+            let (content, line_count) = file.decode(&bytes);
+            let _ = app.emit(
+                "file-follow",
+                FollowEvent {
+                    job_id: job_id.to_string(),
+                    content,
+                    line_count,
+                    start_offset: pos,
+                    end_offset: end,
+                    file_len: len,
+                    truncated,
+                },
+            );
+            pos = end;
+            truncated = false;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,350 @@
+// Decoding the headers of a few common binary formats for the hex view:
+// ELF and PE executables, PNG images and ZIP archives. Only the fields worth
+// a glance are shown; anything malformed just isn't decoded.
+
+use serde::Serialize;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+
+/// Bytes read from the start of the file
+const HEAD_SIZE: u64 = 4096;
+
+/// Bytes read from the end of a ZIP file to find its central directory
+const ZIP_TAIL_SIZE: u64 = 64 * 1024 + 22;
+
+#[derive(Serialize)]
+pub struct FileHeader {
+    pub format: String, // "ELF", "PE", "PNG" or "ZIP"
+    pub fields: Vec<HeaderField>,
+}
+
+#[derive(Serialize)]
+pub struct HeaderField {
+    pub name: String,
+    pub value: String,
+}
+
+/// Decode the header of a file, if it's a known format
+pub fn decode(file: &mut File, len: u64) -> Option<FileHeader> {
+    let head = read_at(file, 0, HEAD_SIZE.min(len))?;
+
+    if head.starts_with(b"\x7fELF") {
+        elf(&head)
+    } else if head.starts_with(b"MZ") {
+        pe(file, &head, len)
+    } else if head.starts_with(b"\x89PNG\r\n\x1a\n") {
+        png(&head)
+    } else if head.starts_with(b"PK\x03\x04") || head.starts_with(b"PK\x05\x06") {
+        let tail_start = len.saturating_sub(ZIP_TAIL_SIZE);
+        let tail = read_at(file, tail_start, len - tail_start)?;
+        zip(&head, &tail)
+    } else {
+        None
+    }
+}
+
+fn read_at(file: &mut File, offset: u64, len: u64) -> Option<Vec<u8>> {
+    let mut buf = Vec::with_capacity(len as usize);
+    file.seek(SeekFrom::Start(offset)).ok()?;
+    file.by_ref().take(len).read_to_end(&mut buf).ok()?;
+    Some(buf)
+}
+
+/// Little or big endian reads that fail past the end of the buffer
+struct Bytes<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl Bytes<'_> {
+    fn u8(&self, at: usize) -> Option<u8> {
+        self.data.get(at).copied()
+    }
+
+    fn u16(&self, at: usize) -> Option<u16> {
+        let b: [u8; 2] = self.data.get(at..at + 2)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u16::from_be_bytes(b)
+        } else {
+            u16::from_le_bytes(b)
+        })
+    }
+
+    fn u32(&self, at: usize) -> Option<u32> {
+        let b: [u8; 4] = self.data.get(at..at + 4)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        })
+    }
+
+    fn u64(&self, at: usize) -> Option<u64> {
+        let b: [u8; 8] = self.data.get(at..at + 8)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u64::from_be_bytes(b)
+        } else {
+            u64::from_le_bytes(b)
+        })
+    }
+}
+
+fn field(name: &str, value: impl ToString) -> HeaderField {
+    HeaderField {
+        name: name.to_string(),
+        value: value.to_string(),
+    }
+}
+
+fn elf(head: &[u8]) -> Option<FileHeader> {
+    let is_64 = match head.get(4)? {
+        1 => false,
+        2 => true,
+        _ => return None,
+    };
+    let b = Bytes {
+        data: head,
+        big_endian: *head.get(5)? == 2,
+    };
+
+    let os_abi = match b.u8(7)? {
+        0 => "System V".to_string(),
+        3 => "Linux".to_string(),
+        6 => "Solaris".to_string(),
+        9 => "FreeBSD".to_string(),
+        12 => "OpenBSD".to_string(),
+        n => format!("0x{:02x}", n),
+    };
+    let kind = match b.u16(16)? {
+        1 => "Relocatable".to_string(),
+        2 => "Executable".to_string(),
+        3 => "Shared object".to_string(),
+        4 => "Core dump".to_string(),
+        n => format!("0x{:04x}", n),
+    };
+    let machine = match b.u16(18)? {
+        0x03 => "x86".to_string(),
+        0x08 => "MIPS".to_string(),
+        0x14 => "PowerPC".to_string(),
+        0x15 => "PowerPC 64".to_string(),
+        0x28 => "ARM".to_string(),
+        0x3e => "x86-64".to_string(),
+        0xb7 => "AArch64".to_string(),
+        0xf3 => "RISC-V".to_string(),
+        0xf7 => "BPF".to_string(),
+        0x102 => "LoongArch".to_string(),
+        n => format!("0x{:04x}", n),
+    };
+    let (entry, ph_count, sh_count) = if is_64 {
+        (b.u64(24)?, b.u16(56)?, b.u16(60)?)
+    } else {
+        (b.u32(24)? as u64, b.u16(44)?, b.u16(48)?)
+    };
+
+    Some(FileHeader {
+        format: "ELF".to_string(),
+        fields: vec![
+            field("Class", if is_64 { "64-bit" } else { "32-bit" }),
+            field(
+                "Byte order",
+                if b.big_endian {
+                    "Big endian"
+                } else {
+                    "Little endian"
+                },
+            ),
+            field("OS/ABI", os_abi),
+            field("Type", kind),
+            field("Machine", machine),
+            field("Entry point", format!("0x{:x}", entry)),
+            field("Program headers", ph_count),
+            field("Section headers", sh_count),
+        ],
+    })
+}
+
+fn pe(file: &mut File, head: &[u8], len: u64) -> Option<FileHeader> {
+    let dos = Bytes {
+        data: head,
+        big_endian: false,
+    };
+    let pe_offset = dos.u32(0x3c)? as u64;
+    if pe_offset + 24 > len {
+        return None;
+    }
+    // The PE header is usually within the first few hundred bytes
+    let data = if pe_offset + 264 <= head.len() as u64 {
+        head[pe_offset as usize..].to_vec()
+    } else {
+        read_at(file, pe_offset, 264)?
+    };
+    if !data.starts_with(b"PE\0\0") {
+        return None;
+    }
+    let b = Bytes {
+        data: &data,
+        big_endian: false,
+    };
+
+    let machine = match b.u16(4)? {
+        0x014c => "x86".to_string(),
+        0x8664 => "x86-64".to_string(),
+        0x01c0 | 0x01c4 => "ARM".to_string(),
+        0xaa64 => "ARM64".to_string(),
+        0x0200 => "Itanium".to_string(),
+        n => format!("0x{:04x}", n),
+    };
+    let sections = b.u16(6)?;
+    let timestamp = b.u32(8)?;
+    let characteristics = b.u16(22)?;
+    let kind = if characteristics & 0x2000 != 0 {
+        "DLL"
+    } else if characteristics & 0x0002 != 0 {
+        "Executable"
+    } else {
+        "Object"
+    };
+
+    let mut fields = vec![
+        field("Type", kind),
+        field("Machine", machine),
+        field("Sections", sections),
+        field("Timestamp", format!("0x{:08x}", timestamp)),
+    ];
+
+    // Optional header, at 24
+    let optional_size = b.u16(20)?;
+    if optional_size > 0 {
+        let is_plus = match b.u16(24)? {
+            0x10b => false,
+            0x20b => true,
+            _ => return Some(pe_header(fields)),
+        };
+        fields.push(field("Format", if is_plus { "PE32+" } else { "PE32" }));
+        fields.push(field("Entry point", format!("0x{:x}", b.u32(24 + 16)?)));
+        let image_base = if is_plus {
+            b.u64(24 + 24)?
+        } else {
+            b.u32(24 + 28)? as u64
+        };
+        fields.push(field("Image base", format!("0x{:x}", image_base)));
+        let subsystem = match b.u16(24 + 68)? {
+            1 => "Native".to_string(),
+            2 => "Windows GUI".to_string(),
+            3 => "Windows console".to_string(),
+            10 => "EFI application".to_string(),
+            n => n.to_string(),
+        };
+        fields.push(field("Subsystem", subsystem));
+    }
+
+    Some(pe_header(fields))
+}
+
+fn pe_header(fields: Vec<HeaderField>) -> FileHeader {
+    FileHeader {
+        format: "PE".to_string(),
+        fields,
+    }
+}
+
+fn png(head: &[u8]) -> Option<FileHeader> {
+    let b = Bytes {
+        data: head,
+        big_endian: true,
+    };
+    if head.get(12..16)? != b"IHDR" {
+        return None;
+    }
+
+    let color = match b.u8(25)? {
+        0 => "Grayscale",
+        2 => "RGB",
+        3 => "Indexed",
+        4 => "Grayscale + alpha",
+        6 => "RGBA",
+        _ => "Unknown",
+    };
+    let mut fields = vec![
+        field("Width", b.u32(16)?),
+        field("Height", b.u32(20)?),
+        field("Bit depth", b.u8(24)?),
+        field("Color type", color),
+        field("Interlaced", if b.u8(28)? == 1 { "Yes" } else { "No" }),
+    ];
+
+    // Chunks that fit in what was read
+    let mut chunks = Vec::new();
+    let mut pos = 8;
+    while let (Some(len), Some(kind)) = (b.u32(pos), head.get(pos + 4..pos + 8)) {
+        chunks.push(String::from_utf8_lossy(kind).to_string());
+        pos = pos.saturating_add(12 + len as usize);
+    }
+    fields.push(field("Chunks", chunks.join(", ")));
+
+    Some(FileHeader {
+        format: "PNG".to_string(),
+        fields,
+    })
+}
+
+fn zip(head: &[u8], tail: &[u8]) -> Option<FileHeader> {
+    let mut fields = Vec::new();
+
+    // First local file header
+    let local = Bytes {
+        data: head,
+        big_endian: false,
+    };
+    if head.starts_with(b"PK\x03\x04") {
+        let flags = local.u16(6)?;
+        let method = match local.u16(8)? {
+            0 => "Stored".to_string(),
+            8 => "Deflate".to_string(),
+            9 => "Deflate64".to_string(),
+            12 => "BZIP2".to_string(),
+            14 => "LZMA".to_string(),
+            93 => "Zstandard".to_string(),
+            99 => "AES".to_string(),
+            n => n.to_string(),
+        };
+        let name_len = local.u16(26)? as usize;
+        let name = head.get(30..30 + name_len).unwrap_or_default();
+        fields.push(field("First entry", String::from_utf8_lossy(name)));
+        fields.push(field("Compression", method));
+        fields.push(field(
+            "Encrypted",
+            if flags & 1 != 0 { "Yes" } else { "No" },
+        ));
+    }
+
+    // End of central directory record, the last "PK\x05\x06" in the file
+    let end = tail.windows(4).rposition(|w| w == b"PK\x05\x06");
+    if let Some(end) = end {
+        let eocd = Bytes {
+            data: &tail[end..],
+            big_endian: false,
+        };
+        let entries = eocd.u16(10)?;
+        fields.push(field(
+            "Entries",
+            if entries == 0xffff {
+                "See ZIP64 record".to_string()
+            } else {
+                entries.to_string()
+            },
+        ));
+        fields.push(field("Central directory size", eocd.u32(12)?));
+        let comment_len = eocd.u16(20)? as usize;
+        if let Some(comment) = tail.get(end + 22..end + 22 + comment_len) {
+            if !comment.is_empty() {
+                fields.push(field("Comment", String::from_utf8_lossy(comment)));
+            }
+        }
+    }
+
+    Some(FileHeader {
+        format: "ZIP".to_string(),
+        fields,
+    })
+}
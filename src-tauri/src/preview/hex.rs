@@ -0,0 +1,253 @@
+// Hex view of any file: pages of hex + ASCII rows at any offset, searching
+// for byte sequences or text, and a decoded header for known formats.
+
+use super::header::{self, FileHeader};
+use crate::error::{AppError, PathContext};
+use crate::jobs::{job_error, Job};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Most rows returned by one page
+const MAX_ROWS: usize = 4096;
+
+/// Bytes read at a time while searching
+const SEARCH_BLOCK_SIZE: usize = 1024 * 1024;
+
+/// One row of the hex view
+#[derive(Serialize)]
+pub struct HexRow {
+    pub offset: u64,
+    pub hex: String,   // "7f 45 4c 46 ..."
+    pub ascii: String, // Printable ASCII, "." for anything else
+}
+
+/// A page of rows
+#[derive(Serialize)]
+pub struct HexPage {
+    pub offset: u64, // Offset of the first row
+    pub rows: Vec<HexRow>,
+    pub bytes_per_row: usize,
+    pub file_len: u64,
+    pub header: Option<FileHeader>, // Only on the page at offset 0
+}
+
+/// What to look for
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase", tag = "kind", content = "pattern")]
+pub enum HexQuery {
+    Hex(String),  // "4d 5a 90 00" or "4d5a9000"
+    Text(String), // Matched as UTF-8 and as UTF-16LE
+}
+
+#[derive(Deserialize, Default)]
+pub struct HexSearchOptions {
+    #[serde(default)]
+    pub ignore_case: bool, // ASCII letters only, for text queries
+}
+
+#[derive(Serialize)]
+pub struct HexMatch {
+    pub offset: u64,
+    pub len: u64,
+}
+
+/// Read rows of bytes_per_row bytes (16 by default) starting at the row
+/// containing offset
+#[tauri::command]
+pub fn read_file_hex(
+    path: String,
+    offset: Option<u64>,
+    rows: Option<usize>,
+    bytes_per_row: Option<usize>,
+) -> Result<HexPage, AppError> {
+    let path = Path::new(&path);
+    let mut file = open(path)?;
+    let file_len = file.metadata().at(path)?.len();
+    let width = bytes_per_row.unwrap_or(16).clamp(1, 64);
+    let rows = rows.unwrap_or(64).clamp(1, MAX_ROWS);
+
+    let offset = offset.unwrap_or(0).min(file_len);
+    let offset = offset - offset % width as u64;
+
+    let mut data = Vec::with_capacity(rows * width);
+    file.seek(SeekFrom::Start(offset)).at(path)?;
+    file.by_ref()
+        .take((rows * width) as u64)
+        .read_to_end(&mut data)
+        .at(path)?;
+
+    let rows = data
+        .chunks(width)
+        .enumerate()
+        .map(|(i, chunk)| HexRow {
+            offset: offset + (i * width) as u64,
+            hex: chunk
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<Vec<_>>()
+                .join(" "),
+            ascii: chunk
+                .iter()
+                .map(|&b| {
+                    if (0x20..0x7f).contains(&b) {
+                        b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect(),
+        })
+        .collect();
+
+    let header = if offset == 0 {
+        header::decode(&mut file, file_len)
+    } else {
+        None
+    };
+
+    Ok(HexPage {
+        offset,
+        rows,
+        bytes_per_row: width,
+        file_len,
+        header,
+    })
+}
+
+/// Find the first match at or after offset. Runs as a job reporting the bytes
+/// searched; resolves to null if there is no match.
+#[tauri::command]
+pub async fn find_in_file(
+    app: tauri::AppHandle,
+    job_id: String,
+    path: String,
+    query: HexQuery,
+    offset: Option<u64>,
+    options: Option<HexSearchOptions>,
+) -> Result<Option<HexMatch>, AppError> {
+    let job = Job::start(&app, job_id)?;
+    let options = options.unwrap_or_default();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        find(
+            &job,
+            Path::new(&path),
+            &query,
+            offset.unwrap_or(0),
+            &options,
+        )
+        .map_err(|e| job_error(&job, e))
+    })
+    .await
+    .map_err(AppError::other)?
+}
+
+fn open(path: &Path) -> Result<File, AppError> {
+    if crate::archive::member_path(path).is_some() {
+        return Err(AppError::unsupported(
+            path,
+            "Not available for files inside archives",
+        ));
+    }
+    if path.is_dir() {
+        return Err(AppError::is_a_directory(path));
+    }
+    File::open(path).at(path)
+}
+
+fn find(
+    job: &Job,
+    path: &Path,
+    query: &HexQuery,
+    offset: u64,
+    options: &HexSearchOptions,
+) -> Result<Option<HexMatch>, AppError> {
+    let patterns = patterns(query)?;
+    let ignore_case = options.ignore_case && matches!(query, HexQuery::Text(_));
+    let longest = patterns.iter().map(Vec::len).max().unwrap_or(1);
+
+    let mut file = open(path)?;
+    let len = file.metadata().at(path)?.len();
+    if offset >= len {
+        return Ok(None);
+    }
+    job.set_total(len - offset, 1);
+    job.begin_item(&path.to_string_lossy());
+    file.seek(SeekFrom::Start(offset)).at(path)?;
+
+    // Each block keeps the last longest - 1 bytes of the previous one, so
+    // matches across block boundaries are found
+    let mut reader = job.track(file);
+    let mut buf: Vec<u8> = Vec::with_capacity(SEARCH_BLOCK_SIZE + longest);
+    let mut buf_start = offset;
+    loop {
+        let kept = buf.len();
+        buf.resize(kept + SEARCH_BLOCK_SIZE, 0);
+        let read = reader.read(&mut buf[kept..]).at(path)?;
+        buf.truncate(kept + read);
+
+        let found = patterns
+            .iter()
+            .filter_map(|p| position(&buf, p, ignore_case).map(|i| (i, p.len())))
+            .min();
+        if let Some((i, len)) = found {
+            job.finish();
+            return Ok(Some(HexMatch {
+                offset: buf_start + i as u64,
+                len: len as u64,
+            }));
+        }
+        if read == 0 {
+            job.finish();
+            return Ok(None);
+        }
+
+        let keep = buf.len().min(longest - 1);
+        buf_start += (buf.len() - keep) as u64;
+        buf.drain(..buf.len() - keep);
+    }
+}
+
+/// Byte sequences a query stands for
+fn patterns(query: &HexQuery) -> Result<Vec<Vec<u8>>, AppError> {
+    match query {
+        HexQuery::Hex(hex) => {
+            let digits: Vec<char> = hex.chars().filter(|c| !c.is_whitespace()).collect();
+            if digits.is_empty() || !digits.len().is_multiple_of(2) {
+                return Err(AppError::invalid_input(
+                    "Enter whole bytes in hex, like 4d 5a",
+                ));
+            }
+            let bytes = digits
+                .chunks(2)
+                .map(|pair| {
+                    let pair: String = pair.iter().collect();
+                    u8::from_str_radix(&pair, 16)
+                })
+                .collect::<Result<Vec<u8>, _>>()
+                .map_err(|_| {
+                    AppError::invalid_input(format!("Not a hex byte sequence: {}", hex))
+                })?;
+            Ok(vec![bytes])
+        }
+        HexQuery::Text(text) => {
+            if text.is_empty() {
+                return Err(AppError::invalid_input("Search text is empty"));
+            }
+            let utf16 = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+            Ok(vec![text.as_bytes().to_vec(), utf16])
+        }
+    }
+}
+
+fn position(haystack: &[u8], needle: &[u8], ignore_case: bool) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| {
+        if ignore_case {
+            window.eq_ignore_ascii_case(needle)
+        } else {
+            window == needle
+        }
+    })
+}
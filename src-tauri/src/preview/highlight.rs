@@ -0,0 +1,288 @@
+// Syntax highlighting of text previews.
+// The language is picked by file extension, then by the first line (shebangs,
+// modelines). Lines are highlighted on a job and sent in batches as
+// "highlight-chunk" events, so the plain preview shows right away and a large
+// file never blocks anything.
+
+use super::text_file::TextFile;
+use crate::error::AppError;
+use crate::jobs::{job_error, Job};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Color, FontStyle, Style, Theme, ThemeSet};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+use tauri::{Emitter, Manager, WebviewWindow};
+
+/// Most lines highlighted by one job
+const MAX_HIGHLIGHT_LINES: usize = 100_000;
+
+/// Longer lines (minified code, data) are sent unhighlighted
+const MAX_LINE_LENGTH: usize = 10_000;
+
+/// Lines per "highlight-chunk" event, unless CHUNK_INTERVAL passes first
+const CHUNK_LINES: usize = 500;
+const CHUNK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Syntect's grammars plus TypeScript, TOML and others it lacks
+static SYNTAXES: LazyLock<SyntaxSet> = LazyLock::new(two_face::syntax::extra_newlines);
+static THEMES: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeMode {
+    Light,
+    Dark,
+}
+
+impl ThemeMode {
+    fn theme(self) -> &'static Theme {
+        let name = match self {
+            ThemeMode::Light => "InspiredGitHub",
+            ThemeMode::Dark => "base16-ocean.dark",
+        };
+        &THEMES.themes[name]
+    }
+}
+
+/// A run of text in one style
+#[derive(Serialize, Clone)]
+pub struct Span {
+    pub text: String,
+    pub color: String, // "#rrggbb"
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+}
+
+/// Payload of "highlight-chunk" events
+#[derive(Serialize, Clone)]
+pub struct HighlightChunk {
+    pub job_id: String,
+    pub first_line: usize, // 0-based index of the first line in lines
+    pub lines: Vec<Vec<Span>>,
+}
+
+/// What the job found, once it's done
+#[derive(Serialize)]
+pub struct HighlightResult {
+    pub language: Option<String>, // None: no grammar, nothing was sent
+    pub background: String,
+    pub foreground: String,
+    pub line_count: usize,
+}
+
+/// Name of the language a file would be highlighted as
+pub fn language_for(path: &Path, first_line: &str) -> Option<&'static SyntaxReference> {
+    let by_extension = path
+        .extension()
+        .and_then(|e| SYNTAXES.find_syntax_by_extension(&e.to_string_lossy()));
+    // Files like "Makefile" or ".bashrc" are matched by their whole name
+    let by_name = || {
+        path.file_name()
+            .and_then(|n| SYNTAXES.find_syntax_by_extension(&n.to_string_lossy()))
+    };
+    by_extension
+        .or_else(by_name)
+        .or_else(|| SYNTAXES.find_syntax_by_first_line(first_line))
+        .filter(|s| s.name != "Plain Text")
+}
+
+/// Highlight a few lines, each with its line break, in the light theme; for
+/// text thumbnails. Files without a grammar come back plain.
+pub fn highlight_lines(path: &Path, lines: &[String]) -> Vec<Vec<Span>> {
+    let theme = ThemeMode::Light.theme();
+    let first = lines.first().map_or("", String::as_str);
+    let Some(syntax) = language_for(path, first) else {
+        return lines.iter().map(|line| vec![plain(line, theme)]).collect();
+    };
+    let mut highlighter = HighlightLines::new(syntax, theme);
+    lines
+        .iter()
+        .map(|line| match highlighter.highlight_line(line, &SYNTAXES) {
+            Ok(regions) => spans(&regions),
+            Err(_) => vec![plain(line, theme)],
+        })
+        .collect()
+}
+
+/// Highlight the first max_lines lines of a file (all of them by default, up
+/// to 100,000). Theme defaults to the window's light or dark mode.
+#[tauri::command]
+pub async fn highlight_file(
+    window: WebviewWindow,
+    job_id: String,
+    path: String,
+    theme: Option<ThemeMode>,
+    max_lines: Option<usize>,
+) -> Result<HighlightResult, AppError> {
+    let job = Job::start(window.app_handle(), job_id.clone())?;
+    let mode = theme.unwrap_or(match window.theme() {
+        Ok(tauri::Theme::Light) => ThemeMode::Light,
+        _ => ThemeMode::Dark,
+    });
+    let max = max_lines
+        .unwrap_or(MAX_HIGHLIGHT_LINES)
+        .min(MAX_HIGHLIGHT_LINES);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        highlight(&window, &job, &job_id, Path::new(&path), mode, max)
+            .map_err(|e| job_error(&job, e))
+    })
+    .await
+    .map_err(AppError::other)?
+}
+
+fn highlight(
+    window: &WebviewWindow,
+    job: &Job,
+    job_id: &str,
+    path: &Path,
+    mode: ThemeMode,
+    max: usize,
+) -> Result<HighlightResult, AppError> {
+    let theme = mode.theme();
+    let mut result = HighlightResult {
+        language: None,
+        background: hex(theme.settings.background.unwrap_or(Color::WHITE)),
+        foreground: hex(theme.settings.foreground.unwrap_or(Color::BLACK)),
+        line_count: 0,
+    };
+
+    let mut file = TextFile::open(path)?;
+    let mut pos = file.data_start;
+    let mut page = next_page(&mut file, &mut pos)?;
+    let first = page.first().map(|(line, _)| line.as_str()).unwrap_or("");
+    let Some(syntax) = language_for(path, first) else {
+        return Ok(result);
+    };
+    result.language = Some(syntax.name.clone());
+    job.set_total(file.len, 0);
+
+    let mut highlighter = HighlightLines::new(syntax, theme);
+    let mut batch = Vec::new();
+    let mut first_line = 0;
+    let mut last_emit = Instant::now();
+    let send = |batch: &mut Vec<Vec<Span>>, first_line: &mut usize| {
+        let lines = std::mem::take(batch);
+        let count = lines.len();
+        let _ = window.emit(
+            "highlight-chunk",
+            HighlightChunk {
+                job_id: job_id.to_string(),
+                first_line: *first_line,
+                lines,
+            },
+        );
+        *first_line += count;
+    };
+
+    let mut counted = file.data_start;
+    while !page.is_empty() && result.line_count < max {
+        for (line, whole) in &page {
+            if result.line_count == max {
+                break;
+            }
+            job.check_cancelled().map_err(AppError::other)?;
+
+            let spans = if *whole {
+                let regions = highlighter
+                    .highlight_line(line, &SYNTAXES)
+                    .map_err(AppError::other)?;
+                spans(&regions)
+            } else {
+                vec![plain(line, theme)]
+            };
+            batch.push(spans);
+            result.line_count += 1;
+
+            if batch.len() >= CHUNK_LINES || last_emit.elapsed() >= CHUNK_INTERVAL {
+                send(&mut batch, &mut first_line);
+                last_emit = Instant::now();
+            }
+        }
+        job.advance(pos - counted);
+        counted = pos;
+        page = next_page(&mut file, &mut pos)?;
+    }
+
+    if !batch.is_empty() {
+        send(&mut batch, &mut first_line);
+    }
+    job.finish();
+    Ok(result)
+}
+
+/// Decode the next CHUNK_LINES lines, each with its line break. Lines too long
+/// to highlight are cut and flagged false.
+fn next_page(file: &mut TextFile, pos: &mut u64) -> Result<Vec<(String, bool)>, AppError> {
+    let (end, _) = file.forward(*pos, CHUNK_LINES)?;
+    let bytes = file.read_range(*pos, end)?;
+    let (text, _) = file.encoding.decode_without_bom_handling(&bytes);
+    let mut lines: Vec<(String, bool)> = text
+        .split_inclusive('\n')
+        .map(|line| (line.to_string(), line.len() <= MAX_LINE_LENGTH))
+        .collect();
+
+    // A line longer than a whole page was cut: skip the rest of it
+    let newline = file.newline.ends_in(&bytes, *pos).last() == Some(&end);
+    *pos = if end < file.len && !newline {
+        if let Some(last) = lines.last_mut() {
+            last.1 = false;
+        }
+        file.skip_lines(end, 1)?.0
+    } else {
+        end
+    };
+    Ok(lines)
+}
+
+/// Merge regions of the same style and drop the line break
+fn spans(regions: &[(Style, &str)]) -> Vec<Span> {
+    let mut spans: Vec<(Style, String)> = Vec::new();
+    for (style, text) in regions {
+        let text = text.trim_end_matches(['\n', '\r']);
+        if text.is_empty() {
+            continue;
+        }
+        match spans.last_mut() {
+            Some((last, joined))
+                if last.foreground == style.foreground && last.font_style == style.font_style =>
+            {
+                joined.push_str(text)
+            }
+            _ => spans.push((*style, text.to_string())),
+        }
+    }
+    spans
+        .into_iter()
+        .map(|(style, text)| Span {
+            text,
+            color: hex(style.foreground),
+            bold: style.font_style.contains(FontStyle::BOLD),
+            italic: style.font_style.contains(FontStyle::ITALIC),
+            underline: style.font_style.contains(FontStyle::UNDERLINE),
+        })
+        .collect()
+}
+
+fn plain(line: &str, theme: &Theme) -> Span {
+    let line = line.trim_end_matches(['\n', '\r']);
+    let mut end = line.len().min(MAX_LINE_LENGTH);
+    while !line.is_char_boundary(end) {
+        end -= 1;
+    }
+    Span {
+        text: line[..end].to_string(),
+        color: hex(theme.settings.foreground.unwrap_or(Color::BLACK)),
+        bold: false,
+        italic: false,
+        underline: false,
+    }
+}
+
+fn hex(color: Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
@@ -0,0 +1,250 @@
+// Sparse line index for jumping to a line number in large text files.
+// Built on a background thread the first time it's needed; it records the
+// byte offset of every LINE_STRIDE-th line and reports its progress as
+// "line-index-progress" events. A file that only grew is indexed further
+// instead of from scratch, so following a log keeps its index.
+
+use super::text_file::TextFile;
+use crate::error::{AppError, PathContext};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Emitter};
+
+/// Lines between two recorded offsets
+const LINE_STRIDE: u64 = 1000;
+
+/// Number of indexes kept in memory
+const INDEX_CACHE_SIZE: usize = 4;
+
+/// Bytes read at a time while indexing
+const INDEX_BLOCK_SIZE: u64 = 1024 * 1024;
+
+/// Minimum time between two progress events of the same index
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Progress of a line index, also the payload of "line-index-progress" events
+#[derive(Serialize, Clone)]
+pub struct LineIndexStatus {
+    pub path: String,
+    pub lines: u64, // Lines found so far; the total once complete
+    pub indexed_bytes: u64,
+    pub file_len: u64,
+    pub complete: bool,
+}
+
+pub struct LineIndex {
+    path: PathBuf,
+    stopped: AtomicBool, // Set when evicted from the cache
+    state: Mutex<IndexState>,
+    progress: Condvar, // Notified as the index grows, and when building stops
+}
+
+struct IndexState {
+    checkpoints: Vec<u64>, // checkpoints[k] is the offset of line k * LINE_STRIDE
+    breaks: u64,           // Line breaks seen
+    indexed_bytes: u64,
+    file_len: u64, // Length being indexed up to
+    modified: Option<SystemTime>,
+    data_start: u64,
+    ends_with_break: bool,
+    building: bool,
+    complete: bool,
+}
+
+/// Recently used indexes, most recently used last
+static INDEXES: Mutex<Vec<Arc<LineIndex>>> = Mutex::new(Vec::new());
+
+impl LineIndex {
+    pub fn status(&self) -> LineIndexStatus {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        // A last line without a line break is only counted once it's certain
+        let trailing =
+            state.complete && !state.ends_with_break && state.indexed_bytes > state.data_start;
+        LineIndexStatus {
+            path: self.path.to_string_lossy().to_string(),
+            lines: state.breaks + u64::from(trailing),
+            indexed_bytes: state.indexed_bytes,
+            file_len: state.file_len,
+            complete: state.complete,
+        }
+    }
+
+    /// The closest known line start at or before a line: (offset, line)
+    pub fn checkpoint_for_line(&self, line: u64) -> (u64, u64) {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let k = ((line / LINE_STRIDE) as usize).min(state.checkpoints.len() - 1);
+        (state.checkpoints[k], k as u64 * LINE_STRIDE)
+    }
+
+    /// Wait until the index has reached a line (0-based), or has gone as far
+    /// as it will
+    pub fn wait_for_line(&self, line: u64) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        while state.building && state.breaks < line {
+            state = self.progress.wait(state).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    /// The closest known line start at or before an offset: (offset, line).
+    /// None if the index hasn't got that far yet.
+    pub fn checkpoint_for_offset(&self, offset: u64) -> Option<(u64, u64)> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if offset > state.indexed_bytes {
+            return None;
+        }
+        let k = state.checkpoints.partition_point(|&c| c <= offset).max(1) - 1;
+        Some((state.checkpoints[k], k as u64 * LINE_STRIDE))
+    }
+}
+
+/// Get the line index of a file, starting or continuing to build it as needed
+pub fn index_for(app: &AppHandle, path: &Path) -> Result<Arc<LineIndex>, AppError> {
+    let metadata = fs::metadata(path).at(path)?;
+    let modified = metadata.modified().ok();
+    let len = metadata.len();
+
+    let mut cache = INDEXES.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(pos) = cache.iter().position(|i| i.path == path) {
+        let index = cache.remove(pos);
+        let mut state = index.state.lock().unwrap_or_else(|e| e.into_inner());
+        let unchanged = state.modified == modified && state.file_len == len;
+        let grew = len > state.file_len;
+
+        if unchanged || grew {
+            // Index the appended part once the running pass (if any) is done
+            let resume = grew && !state.building;
+            if resume {
+                state.file_len = len;
+                state.modified = modified;
+                state.building = true;
+                state.complete = false;
+            }
+            drop(state);
+            cache.push(index.clone());
+            if resume {
+                spawn_builder(app, index.clone());
+            }
+            return Ok(index);
+        }
+
+        drop(state);
+        index.stopped.store(true, Ordering::Relaxed);
+    }
+
+    let file = TextFile::open(path)?;
+    let index = Arc::new(LineIndex {
+        path: path.to_path_buf(),
+        stopped: AtomicBool::new(false),
+        state: Mutex::new(IndexState {
+            checkpoints: vec![file.data_start],
+            breaks: 0,
+            indexed_bytes: file.data_start,
+            file_len: len,
+            modified,
+            data_start: file.data_start,
+            ends_with_break: false,
+            building: true,
+            complete: false,
+        }),
+        progress: Condvar::new(),
+    });
+    cache.push(index.clone());
+    if cache.len() > INDEX_CACHE_SIZE {
+        cache.remove(0).stopped.store(true, Ordering::Relaxed);
+    }
+    drop(cache);
+
+    spawn_builder(app, index.clone());
+    Ok(index)
+}
+
+/// Get an index only if one was already started and still fits the file,
+/// without building anything
+pub fn existing_index(path: &Path) -> Option<Arc<LineIndex>> {
+    let metadata = fs::metadata(path).ok()?;
+    let index = INDEXES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .find(|i| i.path == path)
+        .cloned()?;
+
+    let state = index.state.lock().unwrap_or_else(|e| e.into_inner());
+    let fits = metadata.len() > state.file_len
+        || (metadata.len() == state.file_len && metadata.modified().ok() == state.modified);
+    drop(state);
+    fits.then_some(index)
+}
+
+fn spawn_builder(app: &AppHandle, index: Arc<LineIndex>) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let result = build(&app, &index);
+        let mut state = index.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.building = false;
+        state.complete = result.is_ok();
+        drop(state);
+        index.progress.notify_all();
+        let _ = app.emit("line-index-progress", index.status());
+    });
+}
+
+/// Scan from where the index stopped up to the length it's meant to reach
+fn build(app: &AppHandle, index: &LineIndex) -> Result<(), AppError> {
+    let mut file = TextFile::open(&index.path)?;
+    let (mut pos, len) = {
+        let state = index.state.lock().unwrap_or_else(|e| e.into_inner());
+        (state.indexed_bytes, state.file_len.min(file.len))
+    };
+    let mut last_emit = Instant::now();
+
+    while pos < len {
+        if index.stopped.load(Ordering::Relaxed) {
+            return Err(AppError::Cancelled);
+        }
+
+        let block = file.read_range(pos, (pos + INDEX_BLOCK_SIZE).min(len))?;
+        if block.is_empty() {
+            break;
+        }
+        // Keep offsets on code unit boundaries for UTF-16
+        let usable = block.len() as u64 - block.len() as u64 % file.newline.unit();
+        let block = &block[..usable as usize];
+        if block.is_empty() {
+            break;
+        }
+
+        let ends = file.newline.ends_in(block, pos);
+        pos += usable;
+        {
+            let mut state = index.state.lock().unwrap_or_else(|e| e.into_inner());
+            for end in &ends {
+                state.breaks += 1;
+                if state.breaks.is_multiple_of(LINE_STRIDE) {
+                    state.checkpoints.push(*end);
+                }
+            }
+            state.ends_with_break = ends.last() == Some(&pos);
+            state.indexed_bytes = pos;
+        }
+        index.progress.notify_all();
+
+        if last_emit.elapsed() >= PROGRESS_INTERVAL {
+            let _ = app.emit("line-index-progress", index.status());
+            last_emit = Instant::now();
+        }
+    }
+
+    Ok(())
+}
+
+/// Get the progress of a file's line index, starting to build it if needed.
+/// Progress is also sent as "line-index-progress" events.
+#[tauri::command]
+pub fn get_line_index(app: AppHandle, path: String) -> Result<LineIndexStatus, AppError> {
+    Ok(index_for(&app, Path::new(&path))?.status())
+}
@@ -0,0 +1,38 @@
+// Markdown rendered to HTML for the preview panel.
+// The HTML is sanitized here, so raw HTML in a document can't run scripts or
+// load anything the frontend wouldn't show from a plain image or link.
+
+use pulldown_cmark::{html, Options, Parser};
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct Markdown {
+    pub html: String,
+}
+
+/// Render Markdown (CommonMark with tables, task lists, footnotes and
+/// strikethrough) to sanitized HTML
+pub fn render(text: &str) -> Markdown {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS;
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, Parser::new_ext(text, options));
+
+    // Task list items are disabled checkboxes; no other inputs
+    let html = ammonia::Builder::default()
+        .add_tags(["input"])
+        .add_tag_attributes("input", ["type", "checked", "disabled"])
+        .attribute_filter(|element, attribute, value| {
+            if element == "input" && attribute == "type" && value != "checkbox" {
+                None
+            } else {
+                Some(value.into())
+            }
+        })
+        .clean(&unsafe_html)
+        .to_string();
+
+    Markdown { html }
+}
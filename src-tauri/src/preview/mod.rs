@@ -0,0 +1,239 @@
+// Text previews of files and archive members.
+// The encoding is detected from the first few KB and the text transcoded to
+// UTF-8; lines are counted on the raw bytes, and only estimated for files too
+// large to scan in full. Large files are paged by byte offset (range), jumped
+// into by line number (line_index) and followed as they grow (follow).
+// Code can be syntax highlighted (highlight), data files parsed into tables
+// and trees (structured); binary files get a hex view instead (hex).
+
+pub mod encoding;
+pub mod follow;
+mod header;
+pub mod hex;
+pub mod highlight;
+pub mod line_index;
+mod markdown;
+pub mod range;
+pub mod structured;
+mod table;
+mod text_file;
+mod tree;
+
+use crate::error::{AppError, PathContext};
+use encoding_rs::{CoderResult, Decoder, Encoding, UTF_16BE, UTF_16LE};
+use serde::Serialize;
+use std::fs;
+use std::io::{BufReader, Cursor, Read};
+use std::path::Path;
+
+/// Largest amount of text returned by one preview, in bytes of UTF-8
+const MAX_PREVIEW_TEXT: usize = 1024 * 1024;
+
+/// Bytes scanned for line breaks before the line count is estimated instead
+const LINE_COUNT_SCAN_LIMIT: u64 = 16 * 1024 * 1024;
+
+/// Size of the reads after the sample
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Result of reading a file preview
+#[derive(Serialize)]
+pub struct FilePreviewResult {
+    pub content: String,
+    pub line_count: usize,
+    pub line_count_is_estimate: bool, // Extrapolated from the first LINE_COUNT_SCAN_LIMIT bytes
+    pub is_truncated: bool,
+    pub encoding: String, // WHATWG name: "UTF-8", "UTF-16LE", "windows-1252", ...
+    pub has_bom: bool,
+}
+
+/// Read the first N lines of a text file for preview
+#[tauri::command]
+pub fn read_file_preview(
+    path: String,
+    max_lines: Option<usize>,
+) -> Result<FilePreviewResult, AppError> {
+    let file_path = Path::new(&path);
+    let max = max_lines.unwrap_or(100);
+
+    // Members of archives are read into memory, never extracted to disk
+    if let Some((archive_path, inner)) = crate::archive::member_path(file_path) {
+        let data =
+            crate::archive::read_member(&archive_path, &inner, crate::archive::MEMBER_READ_LIMIT)?;
+        let len = data.len() as u64;
+        return preview_text(Cursor::new(data), len, max, file_path);
+    }
+
+    if !file_path.exists() {
+        return Err(AppError::not_found(file_path));
+    }
+
+    if file_path.is_dir() {
+        return Err(AppError::is_a_directory(file_path));
+    }
+
+    let file = fs::File::open(&path).at(file_path)?;
+    let len = file.metadata().at(file_path)?.len();
+    preview_text(BufReader::new(file), len, max, file_path)
+}
+
+/// Decode the first `max` lines of a text stream of `len` bytes and count the rest
+fn preview_text<R: Read>(
+    mut reader: R,
+    len: u64,
+    max: usize,
+    path: &Path,
+) -> Result<FilePreviewResult, AppError> {
+    let mut sample = Vec::with_capacity(encoding::SAMPLE_SIZE);
+    reader
+        .by_ref()
+        .take(encoding::SAMPLE_SIZE as u64)
+        .read_to_end(&mut sample)
+        .at(path)?;
+
+    let detected = encoding::detect(&sample)
+        .ok_or_else(|| AppError::unsupported(path, "Cannot preview binary files"))?;
+
+    let mut decoder = detected.encoding.new_decoder_without_bom_handling();
+    let mut counter = LineCounter::new(detected.encoding);
+    let mut text = String::new();
+    let mut collecting = max > 0;
+    let mut text_cut = false;
+    let mut scanned = detected.bom_len as u64;
+    let mut at_end = false;
+
+    let mut chunk = sample[detected.bom_len..].to_vec();
+    loop {
+        counter.feed(&chunk);
+        scanned += chunk.len() as u64;
+
+        if collecting {
+            decode_into(&mut decoder, &chunk, &mut text, at_end);
+            if text.matches('\n').count() >= max {
+                collecting = false;
+            } else if text.len() >= MAX_PREVIEW_TEXT {
+                collecting = false;
+                text_cut = true;
+            }
+        }
+
+        if at_end || (!collecting && scanned >= LINE_COUNT_SCAN_LIMIT) {
+            break;
+        }
+
+        chunk.resize(CHUNK_SIZE, 0);
+        let read = reader.read(&mut chunk).at(path)?;
+        chunk.truncate(read);
+        at_end = read == 0;
+    }
+
+    // Lines are counted like BufRead::lines: a final line needs no line break
+    let (line_count, line_count_is_estimate) = if at_end {
+        (
+            counter.lines
+                + usize::from(!counter.ends_with_newline && scanned > detected.bom_len as u64),
+            false,
+        )
+    } else {
+        let estimate = counter.lines as f64 * len as f64 / scanned.max(1) as f64;
+        ((estimate.round() as usize).max(counter.lines), true)
+    };
+
+    if text_cut {
+        let mut end = MAX_PREVIEW_TEXT.min(text.len());
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+    }
+    let lines: Vec<&str> = text.lines().take(max).collect();
+
+    Ok(FilePreviewResult {
+        is_truncated: text_cut || line_count > lines.len(),
+        content: lines.join("\n"),
+        line_count,
+        line_count_is_estimate,
+        encoding: detected.encoding.name().to_string(),
+        has_bom: detected.bom_len > 0,
+    })
+}
+
+/// Decode a chunk with replacement characters, growing text as needed
+fn decode_into(decoder: &mut Decoder, mut src: &[u8], text: &mut String, last: bool) {
+    loop {
+        text.reserve(
+            decoder
+                .max_utf8_buffer_length(src.len())
+                .unwrap_or(src.len() * 3 + 16),
+        );
+        let (result, read, _) = decoder.decode_to_string(src, text, last);
+        src = &src[read..];
+        if result == CoderResult::InputEmpty {
+            break;
+        }
+    }
+}
+
+/// Counts line feeds in raw bytes. ASCII-compatible encodings never use 0x0A
+/// inside a multi-byte character; UTF-16 needs the whole code unit.
+struct LineCounter {
+    utf16: Option<bool>, // Some(big endian) for UTF-16
+    pending: Option<u8>, // First byte of a code unit split across chunks
+    lines: usize,
+    ends_with_newline: bool,
+}
+
+impl LineCounter {
+    fn new(encoding: &'static Encoding) -> Self {
+        let utf16 = if encoding == UTF_16LE {
+            Some(false)
+        } else if encoding == UTF_16BE {
+            Some(true)
+        } else {
+            None
+        };
+        LineCounter {
+            utf16,
+            pending: None,
+            lines: 0,
+            ends_with_newline: false,
+        }
+    }
+
+    fn feed(&mut self, bytes: &[u8]) {
+        let Some(big_endian) = self.utf16 else {
+            self.lines += bytes.iter().filter(|&&b| b == b'\n').count();
+            if let Some(&last) = bytes.last() {
+                self.ends_with_newline = last == b'\n';
+            }
+            return;
+        };
+
+        let mut bytes = bytes;
+        if let Some(first) = self.pending.take() {
+            let Some((&second, rest)) = bytes.split_first() else {
+                self.pending = Some(first);
+                return;
+            };
+            self.count_unit([first, second], big_endian);
+            bytes = rest;
+        }
+
+        let mut units = bytes.chunks_exact(2);
+        for unit in &mut units {
+            self.count_unit([unit[0], unit[1]], big_endian);
+        }
+        self.pending = units.remainder().first().copied();
+    }
+
+    fn count_unit(&mut self, unit: [u8; 2], big_endian: bool) {
+        let value = if big_endian {
+            u16::from_be_bytes(unit)
+        } else {
+            u16::from_le_bytes(unit)
+        };
+        self.ends_with_newline = value == 0x0a;
+        if self.ends_with_newline {
+            self.lines += 1;
+        }
+    }
+}
@@ -0,0 +1,137 @@
+// Paging through text files of any size by byte offset.
+// Each page reports the offsets it covers so the next one can continue from
+// there, forward or backward; reading backward from no offset gives the tail
+// of the file. Line numbers come from the line index when it's available.
+
+use super::line_index;
+use super::text_file::TextFile;
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::AppHandle;
+
+/// Which way to page from the given offset
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    #[default]
+    Forward,
+    Backward,
+}
+
+/// A page of whole lines
+#[derive(Serialize)]
+pub struct TextRange {
+    pub content: String,
+    pub line_count: usize,
+    pub start_offset: u64,       // Byte offset of the first line
+    pub end_offset: u64,         // Byte offset just past the last line
+    pub first_line: Option<u64>, // 1-based number of the first line, if known
+    pub file_len: u64,
+    pub at_start: bool,
+    pub at_end: bool,
+    pub encoding: String,
+}
+
+/// Read up to max_lines lines starting at a byte offset (forward) or ending at
+/// it (backward). Without an offset, forward starts at the beginning of the
+/// file and backward ends at its end.
+#[tauri::command]
+pub fn read_text_range(
+    path: String,
+    offset: Option<u64>,
+    direction: Option<Direction>,
+    max_lines: Option<usize>,
+) -> Result<TextRange, AppError> {
+    let path = Path::new(&path);
+    let mut file = TextFile::open(path)?;
+    let max = max_lines.unwrap_or(100).max(1);
+
+    let (start, end) = match direction.unwrap_or_default() {
+        Direction::Forward => {
+            let start = file.line_start_at_or_after(offset.unwrap_or(0))?;
+            (start, file.forward(start, max)?.0)
+        }
+        Direction::Backward => {
+            let end = file.align(offset.unwrap_or(file.len));
+            (file.backward(end, max)?, end)
+        }
+    };
+
+    let first_line = line_number_at(&mut file, start)?;
+    page(&mut file, start, end, first_line)
+}
+
+/// Read up to max_lines lines starting at a 1-based line number; lines past
+/// the end give the tail. Waits for the line index to get to the line,
+/// building it in the background if needed, while its progress is sent as
+/// "line-index-progress" events.
+#[tauri::command]
+pub async fn read_text_at_line(
+    app: AppHandle,
+    path: String,
+    line: u64,
+    max_lines: Option<usize>,
+) -> Result<TextRange, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = Path::new(&path);
+        let index = line_index::index_for(&app, path)?;
+        let max = max_lines.unwrap_or(100).max(1);
+
+        // Lines are only skipped one by one from the closest checkpoint
+        let target = line.max(1) - 1;
+        index.wait_for_line(target);
+        let mut file = TextFile::open(path)?;
+        let (checkpoint, checkpoint_line) = index.checkpoint_for_line(target);
+        let (start, skipped) = file.skip_lines(checkpoint, target - checkpoint_line)?;
+
+        if start >= file.len && file.len > file.data_start {
+            // Past the last line
+            let end = file.len;
+            let start = file.backward(end, max)?;
+            let first_line = line_number_at(&mut file, start)?;
+            return page(&mut file, start, end, first_line);
+        }
+
+        let end = file.forward(start, max)?.0;
+        page(&mut file, start, end, Some(checkpoint_line + skipped + 1))
+    })
+    .await
+    .map_err(AppError::other)?
+}
+
+/// 1-based number of the line starting at offset, if the line index has got there
+fn line_number_at(file: &mut TextFile, offset: u64) -> Result<Option<u64>, AppError> {
+    if offset <= file.data_start {
+        return Ok(Some(1));
+    }
+    let Some(index) = line_index::existing_index(&file.path) else {
+        return Ok(None);
+    };
+    let Some((checkpoint, line)) = index.checkpoint_for_offset(offset) else {
+        return Ok(None);
+    };
+    Ok(Some(line + file.count_lines(checkpoint, offset)? + 1))
+}
+
+fn page(
+    file: &mut TextFile,
+    start: u64,
+    end: u64,
+    first_line: Option<u64>,
+) -> Result<TextRange, AppError> {
+    let bytes = file.read_range(start, end)?;
+    let (content, line_count) = file.decode(&bytes);
+
+    Ok(TextRange {
+        content,
+        line_count,
+        start_offset: start,
+        end_offset: end,
+        first_line,
+        file_len: file.len,
+        at_start: start <= file.data_start,
+        at_end: end >= file.len,
+        encoding: file.encoding.name().to_string(),
+    })
+}
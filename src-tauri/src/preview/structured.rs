@@ -0,0 +1,203 @@
+// Structured previews of data files: CSV/TSV as a table, JSON/YAML/TOML as a
+// tree and Markdown as HTML. Everything is parsed here so the frontend only
+// renders. Works for archive members too.
+
+use super::encoding;
+use super::markdown::{self, Markdown};
+use super::table::{self, Table, TableOptions};
+use super::tree::{self, Tree, TreeFormat};
+use crate::error::{AppError, PathContext};
+use encoding_rs::{UTF_16BE, UTF_16LE, UTF_8};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+/// Largest document parsed into a tree or rendered as Markdown
+const MAX_DOCUMENT_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Largest UTF-16 table, which is converted in memory
+const MAX_UTF16_TABLE_SIZE: u64 = 64 * 1024 * 1024;
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StructuredFormat {
+    Csv,
+    Tsv,
+    Json,
+    Yaml,
+    Toml,
+    Markdown,
+}
+
+impl StructuredFormat {
+    /// Guess the format from the file name
+    pub fn for_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name == "cargo.lock" || name == "pipfile" || name == "poetry.lock" {
+            return Some(StructuredFormat::Toml);
+        }
+        let extension = name.rsplit_once('.')?.1;
+        Some(match extension {
+            "csv" => StructuredFormat::Csv,
+            "tsv" | "tab" => StructuredFormat::Tsv,
+            "json" | "geojson" | "webmanifest" | "ipynb" => StructuredFormat::Json,
+            "yaml" | "yml" => StructuredFormat::Yaml,
+            "toml" => StructuredFormat::Toml,
+            "md" | "markdown" | "mdown" | "mkd" => StructuredFormat::Markdown,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum StructuredPreview {
+    Table(Table),
+    Tree(Tree),
+    Markdown(Markdown),
+}
+
+/// Parse a data file for a structured preview. The format is guessed from the
+/// extension unless given; table options only apply to CSV and TSV.
+#[tauri::command]
+pub async fn read_structured_preview(
+    path: String,
+    format: Option<StructuredFormat>,
+    options: Option<TableOptions>,
+) -> Result<StructuredPreview, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = Path::new(&path);
+        let format = format
+            .or_else(|| StructuredFormat::for_path(path))
+            .ok_or_else(|| {
+                AppError::unsupported(path, "No structured preview for this file type")
+            })?;
+        structured_preview(path, format, &options.unwrap_or_default())
+    })
+    .await
+    .map_err(AppError::other)?
+}
+
+fn structured_preview(
+    path: &Path,
+    format: StructuredFormat,
+    options: &TableOptions,
+) -> Result<StructuredPreview, AppError> {
+    let member = crate::archive::member_path(path);
+    if member.is_none() && path.is_dir() {
+        return Err(AppError::is_a_directory(path));
+    }
+
+    if matches!(format, StructuredFormat::Csv | StructuredFormat::Tsv) {
+        let is_tsv = format == StructuredFormat::Tsv;
+        return match member {
+            Some((archive, inner)) => {
+                let data = crate::archive::read_member(
+                    &archive,
+                    &inner,
+                    crate::archive::MEMBER_READ_LIMIT,
+                )?;
+                read_table(Cursor::new(data), is_tsv, options, path)
+            }
+            None => read_table(File::open(path).at(path)?, is_tsv, options, path),
+        };
+    }
+
+    let text = read_document(path, member)?;
+    Ok(match format {
+        StructuredFormat::Json => StructuredPreview::Tree(tree::parse(&text, TreeFormat::Json)),
+        StructuredFormat::Yaml => StructuredPreview::Tree(tree::parse(&text, TreeFormat::Yaml)),
+        StructuredFormat::Toml => StructuredPreview::Tree(tree::parse(&text, TreeFormat::Toml)),
+        _ => StructuredPreview::Markdown(markdown::render(&text)),
+    })
+}
+
+/// Read a whole document as text, in whatever encoding it's in
+fn read_document(
+    path: &Path,
+    member: Option<(std::path::PathBuf, String)>,
+) -> Result<String, AppError> {
+    let data = match member {
+        Some((archive, inner)) => crate::archive::read_member(&archive, &inner, MAX_DOCUMENT_SIZE)?,
+        None => {
+            let file = File::open(path).at(path)?;
+            if file.metadata().at(path)?.len() > MAX_DOCUMENT_SIZE {
+                return Err(AppError::unsupported(
+                    path,
+                    "Too large for a structured preview",
+                ));
+            }
+            let mut data = Vec::new();
+            file.take(MAX_DOCUMENT_SIZE)
+                .read_to_end(&mut data)
+                .at(path)?;
+            data
+        }
+    };
+
+    let detected = encoding::detect(&data[..data.len().min(encoding::SAMPLE_SIZE)])
+        .ok_or_else(|| AppError::unsupported(path, "Cannot preview binary files"))?;
+    let (text, _) = detected
+        .encoding
+        .decode_without_bom_handling(&data[detected.bom_len..]);
+    Ok(text.into_owned())
+}
+
+/// Tables are read a page at a time. UTF-16 files, which the CSV reader can't
+/// split, are converted to UTF-8 in memory first.
+fn read_table<R: Read + std::io::Seek>(
+    mut reader: R,
+    is_tsv: bool,
+    options: &TableOptions,
+    path: &Path,
+) -> Result<StructuredPreview, AppError> {
+    let mut sample = Vec::with_capacity(encoding::SAMPLE_SIZE);
+    reader
+        .by_ref()
+        .take(encoding::SAMPLE_SIZE as u64)
+        .read_to_end(&mut sample)
+        .at(path)?;
+    let detected = encoding::detect(&sample)
+        .ok_or_else(|| AppError::unsupported(path, "Cannot preview binary files"))?;
+
+    if detected.encoding == UTF_16LE || detected.encoding == UTF_16BE {
+        let mut data = sample;
+        reader
+            .take(MAX_UTF16_TABLE_SIZE)
+            .read_to_end(&mut data)
+            .at(path)?;
+        let (text, _) = detected
+            .encoding
+            .decode_without_bom_handling(&data[detected.bom_len..]);
+        let text = text.into_owned();
+        let mut sample_end = text.len().min(encoding::SAMPLE_SIZE);
+        while !text.is_char_boundary(sample_end) {
+            sample_end -= 1;
+        }
+        let table = table::read(
+            Cursor::new(text.as_bytes()),
+            &text[..sample_end],
+            0,
+            UTF_8,
+            is_tsv,
+            options,
+            path,
+        )?;
+        return Ok(StructuredPreview::Table(table));
+    }
+
+    let (sample_text, _) = detected
+        .encoding
+        .decode_without_bom_handling(&sample[detected.bom_len..]);
+    let table = table::read(
+        reader,
+        &sample_text,
+        detected.bom_len as u64,
+        detected.encoding,
+        is_tsv,
+        options,
+        path,
+    )?;
+    Ok(StructuredPreview::Table(table))
+}
@@ -0,0 +1,194 @@
+// CSV and TSV files as a paged table.
+// The delimiter is guessed from the first lines unless given; pages are read
+// from a byte offset like text ranges, so any page of a large file is quick.
+
+use crate::error::AppError;
+use csv::{ByteRecord, Position, ReaderBuilder};
+use encoding_rs::Encoding;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Seek};
+use std::path::Path;
+
+/// Most rows in one page
+const MAX_ROWS: usize = 10_000;
+
+/// Fields are cut after this many characters
+const MAX_FIELD_LENGTH: usize = 4096;
+
+/// Lines looked at to guess the delimiter
+const DELIMITER_SAMPLE_LINES: usize = 20;
+
+/// Delimiters tried, in order of preference on a tie
+const DELIMITERS: [char; 4] = [',', '\t', ';', '|'];
+
+#[derive(Deserialize, Default)]
+pub struct TableOptions {
+    pub offset: Option<u64>, // Byte offset of the first row, from a previous page's next_offset
+    pub max_rows: Option<usize>,
+    pub delimiter: Option<char>,
+    pub has_header: Option<bool>, // Defaults to true
+}
+
+#[derive(Serialize)]
+pub struct Table {
+    pub delimiter: char,
+    pub headers: Option<Vec<String>>,
+    pub rows: Vec<Vec<String>>,
+    pub column_count: usize, // Widest row, headers included
+    pub start_offset: u64,
+    pub next_offset: Option<u64>, // None on the last page
+    pub encoding: String,
+}
+
+/// Read a page of rows from UTF-8 or single-byte text starting at data_start
+/// (past any BOM)
+pub fn read<R: Read + Seek>(
+    reader: R,
+    sample: &str,
+    data_start: u64,
+    encoding: &'static Encoding,
+    is_tsv: bool,
+    options: &TableOptions,
+    path: &Path,
+) -> Result<Table, AppError> {
+    let delimiter = options
+        .delimiter
+        .filter(char::is_ascii)
+        .unwrap_or_else(|| detect_delimiter(sample, is_tsv));
+    let max_rows = options.max_rows.unwrap_or(100).clamp(1, MAX_ROWS);
+
+    let mut csv = ReaderBuilder::new()
+        .delimiter(delimiter as u8)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(reader);
+    let decode = |record: &ByteRecord| -> Vec<String> {
+        record
+            .iter()
+            .map(|field| {
+                let (text, _) = encoding.decode_without_bom_handling(field);
+                match text.char_indices().nth(MAX_FIELD_LENGTH) {
+                    Some((end, _)) => text[..end].to_string(),
+                    None => text.into_owned(),
+                }
+            })
+            .collect()
+    };
+
+    let mut record = ByteRecord::new();
+    let seek_to = |offset: u64| {
+        let mut position = Position::new();
+        position.set_byte(offset);
+        position
+    };
+    csv.seek(seek_to(data_start))
+        .map_err(|e| csv_error(e, path))?;
+
+    let headers = if options.has_header.unwrap_or(true) {
+        csv.read_byte_record(&mut record)
+            .map_err(|e| csv_error(e, path))?
+            .then(|| decode(&record))
+    } else {
+        None
+    };
+
+    let start = options
+        .offset
+        .unwrap_or(csv.position().byte())
+        .max(data_start);
+    csv.seek(seek_to(start)).map_err(|e| csv_error(e, path))?;
+
+    let mut rows = Vec::new();
+    let mut at_end = false;
+    while rows.len() < max_rows {
+        if !csv
+            .read_byte_record(&mut record)
+            .map_err(|e| csv_error(e, path))?
+        {
+            at_end = true;
+            break;
+        }
+        rows.push(decode(&record));
+    }
+    if !at_end {
+        // Another record may or may not follow
+        at_end = !csv
+            .read_byte_record(&mut record)
+            .map_err(|e| csv_error(e, path))?;
+    }
+    let next_offset = (!at_end).then(|| record.position().map_or(0, |p| p.byte()));
+
+    let column_count = headers
+        .iter()
+        .chain(rows.iter())
+        .map(Vec::len)
+        .max()
+        .unwrap_or(0);
+
+    Ok(Table {
+        delimiter,
+        headers,
+        rows,
+        column_count,
+        start_offset: start,
+        next_offset,
+        encoding: encoding.name().to_string(),
+    })
+}
+
+fn csv_error(error: csv::Error, path: &Path) -> AppError {
+    match error.into_kind() {
+        csv::ErrorKind::Io(e) => AppError::io(e, path),
+        kind => AppError::unsupported(path, format!("Cannot read table: {:?}", kind)),
+    }
+}
+
+/// Pick the delimiter that splits the first lines into the same number of
+/// fields most often. Quoted fields are skipped.
+fn detect_delimiter(sample: &str, is_tsv: bool) -> char {
+    let lines: Vec<&str> = sample
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .take(DELIMITER_SAMPLE_LINES)
+        .collect();
+
+    let mut best: Option<(char, usize, usize)> = None; // (delimiter, consistent lines, fields)
+    for delimiter in DELIMITERS {
+        let counts: Vec<usize> = lines
+            .iter()
+            .map(|l| count_outside_quotes(l, delimiter))
+            .collect();
+        // Most common non-zero count
+        let Some((count, lines)) = counts
+            .iter()
+            .filter(|&&c| c > 0)
+            .map(|&c| (c, counts.iter().filter(|&&other| other == c).count()))
+            .max_by_key(|&(c, n)| (n, c))
+        else {
+            continue;
+        };
+        if best.is_none_or(|(_, best_lines, best_count)| (lines, count) > (best_lines, best_count))
+        {
+            best = Some((delimiter, lines, count));
+        }
+    }
+
+    match best {
+        Some((delimiter, _, _)) => delimiter,
+        None if is_tsv => '\t',
+        None => ',',
+    }
+}
+
+fn count_outside_quotes(line: &str, delimiter: char) -> usize {
+    let mut quoted = false;
+    let mut count = 0;
+    for c in line.chars() {
+        if c == '"' {
+            quoted = !quoted;
+        } else if c == delimiter && !quoted {
+            count += 1;
+        }
+    }
+    count
+}
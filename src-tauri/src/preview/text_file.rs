@@ -0,0 +1,298 @@
+// Random access to the lines of a text file by byte offset.
+// Line breaks are found on the raw bytes, so a page can start anywhere in a
+// huge file without decoding what comes before it.
+
+use super::encoding;
+use crate::error::{AppError, PathContext};
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Most bytes read for one page; longer lines are cut
+pub const MAX_PAGE_BYTES: u64 = 1024 * 1024;
+
+/// Size of the blocks read while looking for line breaks
+const BLOCK_SIZE: u64 = 64 * 1024;
+
+/// How a line break is written in the file's encoding
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Newline {
+    Byte,    // 0x0A, in UTF-8 and every ASCII-compatible code page
+    Utf16Le, // 0A 00 at an even offset
+    Utf16Be, // 00 0A at an even offset
+}
+
+impl Newline {
+    pub fn for_encoding(encoding: &'static Encoding) -> Self {
+        if encoding == UTF_16LE {
+            Newline::Utf16Le
+        } else if encoding == UTF_16BE {
+            Newline::Utf16Be
+        } else {
+            Newline::Byte
+        }
+    }
+
+    /// Bytes per code unit: offsets must be multiples of it
+    pub fn unit(self) -> u64 {
+        match self {
+            Newline::Byte => 1,
+            _ => 2,
+        }
+    }
+
+    /// Offsets just past each line break in buf, which starts at an aligned offset base
+    pub fn ends_in(self, buf: &[u8], base: u64) -> Vec<u64> {
+        match self {
+            Newline::Byte => buf
+                .iter()
+                .enumerate()
+                .filter(|(_, &b)| b == b'\n')
+                .map(|(i, _)| base + i as u64 + 1)
+                .collect(),
+            Newline::Utf16Le | Newline::Utf16Be => {
+                let pattern = if self == Newline::Utf16Le {
+                    [0x0a, 0]
+                } else {
+                    [0, 0x0a]
+                };
+                buf.chunks_exact(2)
+                    .enumerate()
+                    .filter(|(_, unit)| *unit == pattern)
+                    .map(|(i, _)| base + i as u64 * 2 + 2)
+                    .collect()
+            }
+        }
+    }
+}
+
+/// An open text file with its detected encoding
+pub struct TextFile {
+    pub path: PathBuf,
+    file: File,
+    pub len: u64,
+    pub data_start: u64, // First byte after the BOM
+    pub encoding: &'static Encoding,
+    pub newline: Newline,
+}
+
+impl TextFile {
+    pub fn open(path: &Path) -> Result<TextFile, AppError> {
+        if crate::archive::member_path(path).is_some() {
+            return Err(AppError::unsupported(
+                path,
+                "Not available for files inside archives",
+            ));
+        }
+        if path.is_dir() {
+            return Err(AppError::is_a_directory(path));
+        }
+
+        let mut file = File::open(path).at(path)?;
+        let len = file.metadata().at(path)?.len();
+
+        let mut sample = Vec::with_capacity(encoding::SAMPLE_SIZE);
+        file.by_ref()
+            .take(encoding::SAMPLE_SIZE as u64)
+            .read_to_end(&mut sample)
+            .at(path)?;
+        let detected = encoding::detect(&sample)
+            .ok_or_else(|| AppError::unsupported(path, "Cannot preview binary files"))?;
+
+        Ok(TextFile {
+            path: path.to_path_buf(),
+            file,
+            len,
+            data_start: detected.bom_len as u64,
+            encoding: detected.encoding,
+            newline: Newline::for_encoding(detected.encoding),
+        })
+    }
+
+    /// Pick up a size change, e.g. of a log that is being written
+    pub fn refresh_len(&mut self) -> Result<u64, AppError> {
+        self.len = self.file.metadata().at(&self.path)?.len();
+        Ok(self.len)
+    }
+
+    /// Read the bytes in start..end (clamped to the file)
+    pub fn read_range(&mut self, start: u64, end: u64) -> Result<Vec<u8>, AppError> {
+        let end = end.min(self.len);
+        let mut buf = Vec::with_capacity(end.saturating_sub(start) as usize);
+        if start < end {
+            self.file.seek(SeekFrom::Start(start)).at(&self.path)?;
+            (&mut self.file)
+                .take(end - start)
+                .read_to_end(&mut buf)
+                .at(&self.path)?;
+        }
+        Ok(buf)
+    }
+
+    /// Round an offset down to a code unit boundary within the text
+    pub fn align(&self, offset: u64) -> u64 {
+        let offset = offset.clamp(self.data_start, self.len.max(self.data_start));
+        offset - (offset - self.data_start) % self.newline.unit()
+    }
+
+    /// Start of the first line at or after offset
+    pub fn line_start_at_or_after(&mut self, offset: u64) -> Result<u64, AppError> {
+        let offset = self.align(offset);
+        if offset == self.data_start {
+            return Ok(offset);
+        }
+
+        // Already at a line start if a line break ends just before
+        let unit = self.newline.unit();
+        let before = self.read_range(offset - unit, offset)?;
+        if !self.newline.ends_in(&before, offset - unit).is_empty() {
+            return Ok(offset);
+        }
+
+        let (end, _) = self.forward(offset, 1)?;
+        Ok(end)
+    }
+
+    /// Scan forward from a line start over up to max_lines lines.
+    /// Returns the offset past the last line and the number of lines passed.
+    pub fn forward(&mut self, start: u64, max_lines: usize) -> Result<(u64, usize), AppError> {
+        let limit = self.len.min(start + MAX_PAGE_BYTES);
+        let mut lines = 0;
+        let mut pos = start;
+        let mut last_end = start;
+
+        while pos < limit && lines < max_lines {
+            let block_end = (pos + BLOCK_SIZE).min(limit);
+            let block = self.read_range(pos, block_end)?;
+            if block.is_empty() {
+                break;
+            }
+            for end in self.newline.ends_in(&block, pos) {
+                lines += 1;
+                last_end = end;
+                if lines == max_lines {
+                    return Ok((end, lines));
+                }
+            }
+            pos += block.len() as u64;
+        }
+
+        // The last line has no line break, or the page is full
+        let end = self.align(pos.min(limit));
+        if end > last_end {
+            lines += 1;
+        }
+        Ok((self.char_boundary(start, end)?, lines))
+    }
+
+    /// Scan backward over up to max_lines lines ending at end (a line start or
+    /// the end of the file). Returns the start of the first of those lines.
+    pub fn backward(&mut self, end: u64, max_lines: usize) -> Result<u64, AppError> {
+        let limit = end.saturating_sub(MAX_PAGE_BYTES).max(self.data_start);
+        let mut pos = end;
+        let mut lines = 0;
+
+        while pos > limit {
+            let block_start = self.align(pos.saturating_sub(BLOCK_SIZE).max(limit));
+            let block = self.read_range(block_start, pos)?;
+
+            for line_end in self.newline.ends_in(&block, block_start).into_iter().rev() {
+                // The break ending the page's last line starts no new line
+                if line_end == end {
+                    continue;
+                }
+                lines += 1;
+                if lines == max_lines {
+                    return Ok(line_end);
+                }
+            }
+            pos = block_start;
+        }
+
+        Ok(pos)
+    }
+
+    /// Skip up to n line breaks from a line start, with no limit on line length.
+    /// Returns the offset reached and the number of lines skipped.
+    pub fn skip_lines(&mut self, start: u64, n: u64) -> Result<(u64, u64), AppError> {
+        let mut pos = start;
+        let mut skipped = 0;
+
+        while skipped < n && pos < self.len {
+            let block = self.read_range(pos, pos + BLOCK_SIZE)?;
+            let usable = block.len() as u64 - block.len() as u64 % self.newline.unit();
+            if usable == 0 {
+                break;
+            }
+            for end in self.newline.ends_in(&block[..usable as usize], pos) {
+                skipped += 1;
+                if skipped == n {
+                    return Ok((end, skipped));
+                }
+            }
+            pos += usable;
+        }
+
+        Ok((pos, skipped))
+    }
+
+    /// Count the line breaks in start..end
+    pub fn count_lines(&mut self, start: u64, end: u64) -> Result<u64, AppError> {
+        let mut pos = start;
+        let mut count = 0;
+        while pos < end {
+            let block = self.read_range(pos, (pos + BLOCK_SIZE).min(end))?;
+            if block.is_empty() {
+                break;
+            }
+            count += self.newline.ends_in(&block, pos).len() as u64;
+            pos += block.len() as u64;
+        }
+        Ok(count)
+    }
+
+    /// Move a cut-off page end back so it doesn't split a character. start
+    /// is a line start, where a character begins in every encoding.
+    pub fn char_boundary(&mut self, start: u64, end: u64) -> Result<u64, AppError> {
+        if end >= self.len || self.newline != Newline::Byte || end <= start {
+            return Ok(end);
+        }
+        if self.encoding == UTF_8 {
+            let from = end.saturating_sub(3).max(start);
+            let bytes = self.read_range(from, end + 1)?;
+            let mut pos = end;
+            while pos > from && bytes[(pos - from) as usize] & 0xc0 == 0x80 {
+                pos -= 1;
+            }
+            return Ok(pos);
+        }
+        if self.encoding.is_single_byte() {
+            return Ok(end);
+        }
+
+        // Shift_JIS, GBK, Big5 and EUC trail bytes can look like lead bytes or
+        // ASCII, so only decoding from the start tells where characters end:
+        // cut off partway through one, the page decodes to a replacement
+        // character at the end. No character is longer than 4 bytes.
+        let bytes = self.read_range(start, end)?;
+        for back in 0..4 {
+            let Some(len) = bytes.len().checked_sub(back).filter(|&len| len > 0) else {
+                break;
+            };
+            let (text, _) = self.encoding.decode_without_bom_handling(&bytes[..len]);
+            if !text.ends_with(char::REPLACEMENT_CHARACTER) {
+                return Ok(start + len as u64);
+            }
+        }
+        Ok(end)
+    }
+
+    /// Decode bytes of whole lines, dropping line breaks' "\r".
+    /// Returns the lines joined with "\n" and their number.
+    pub fn decode(&self, bytes: &[u8]) -> (String, usize) {
+        let (text, _) = self.encoding.decode_without_bom_handling(bytes);
+        let lines: Vec<&str> = text.lines().collect();
+        (lines.join("\n"), lines.len())
+    }
+}
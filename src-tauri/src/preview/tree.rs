@@ -0,0 +1,286 @@
+// JSON, YAML and TOML documents as one kind of tree.
+// Every format is deserialized straight into TreeNode, which keeps keys in
+// file order. Parse errors come back with their line and column instead of
+// failing the preview.
+
+use serde::de::{self, Deserialize, Deserializer, EnumAccess, MapAccess, SeqAccess, VariantAccess};
+use serde::Serialize;
+use std::fmt;
+
+/// Most nodes sent to the frontend; deeper and later ones are cut
+const MAX_NODES: usize = 20_000;
+
+/// Key toml uses to pass a date-time through serde
+const TOML_DATETIME_KEY: &str = "$__toml_private_datetime";
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeKind {
+    Object,
+    Array,
+    String,
+    Number,
+    Bool,
+    Null,
+    Date, // TOML date-times
+}
+
+#[derive(Serialize)]
+pub struct TreeNode {
+    pub key: Option<String>, // Key in the parent object; None for array items and the root
+    pub kind: NodeKind,
+    pub value: Option<String>, // Scalars as text
+    pub tag: Option<String>,   // YAML tag, like "!Ref"
+    pub children: Vec<TreeNode>,
+    pub child_count: usize, // Can be more than children.len() when the tree was cut
+}
+
+/// Where a document failed to parse
+#[derive(Serialize)]
+pub struct ParseError {
+    pub message: String,
+    pub line: Option<usize>, // 1-based
+    pub column: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct Tree {
+    pub root: Option<TreeNode>, // None if the document doesn't parse
+    pub error: Option<ParseError>,
+    pub node_count: usize,
+    pub truncated: bool,
+}
+
+#[derive(Clone, Copy)]
+pub enum TreeFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+/// Parse a document into a tree
+pub fn parse(text: &str, format: TreeFormat) -> Tree {
+    let parsed = match format {
+        TreeFormat::Json => serde_json::from_str::<TreeNode>(text).map_err(|e| ParseError {
+            message: strip_location(&e.to_string()),
+            line: Some(e.line()),
+            column: Some(e.column()),
+        }),
+        TreeFormat::Yaml => parse_yaml(text),
+        TreeFormat::Toml => toml::from_str::<TreeNode>(text).map_err(|e| {
+            let (line, column) = e
+                .span()
+                .map(|span| line_and_column(text, span.start))
+                .unzip();
+            ParseError {
+                message: e.message().to_string(),
+                line,
+                column,
+            }
+        }),
+    };
+
+    match parsed {
+        Ok(mut root) => {
+            let mut budget = MAX_NODES;
+            let truncated = prune(&mut root, &mut budget);
+            Tree {
+                root: Some(root),
+                error: None,
+                node_count: MAX_NODES - budget,
+                truncated,
+            }
+        }
+        Err(error) => Tree {
+            root: None,
+            error: Some(error),
+            node_count: 0,
+            truncated: false,
+        },
+    }
+}
+
+/// A YAML stream with several documents becomes an array of them
+fn parse_yaml(text: &str) -> Result<TreeNode, ParseError> {
+    let mut documents = Vec::new();
+    for document in serde_yaml_ng::Deserializer::from_str(text) {
+        let node = TreeNode::deserialize(document).map_err(|e| {
+            let location = e.location();
+            ParseError {
+                message: strip_location(&e.to_string()),
+                line: location.as_ref().map(|l| l.line()),
+                column: location.as_ref().map(|l| l.column()),
+            }
+        })?;
+        documents.push(node);
+    }
+
+    match documents.len() {
+        0 => Ok(TreeNode::scalar(NodeKind::Null, None)),
+        1 => Ok(documents.remove(0)),
+        _ => Ok(TreeNode::parent(NodeKind::Array, documents)),
+    }
+}
+
+/// Drop the " at line X column Y" the error messages end with; it's sent separately
+fn strip_location(message: &str) -> String {
+    match message.find(" at line ") {
+        Some(i) => message[..i].to_string(),
+        None => message.to_string(),
+    }
+}
+
+fn line_and_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+    (line, column)
+}
+
+/// Keep the first MAX_NODES nodes in document order. Returns true if any were cut.
+fn prune(node: &mut TreeNode, budget: &mut usize) -> bool {
+    *budget = budget.saturating_sub(1);
+    let mut truncated = false;
+    let mut kept = 0;
+    for child in node.children.iter_mut() {
+        if *budget == 0 {
+            break;
+        }
+        truncated |= prune(child, budget);
+        kept += 1;
+    }
+    if kept < node.children.len() {
+        node.children.truncate(kept);
+        truncated = true;
+    }
+    truncated
+}
+
+impl TreeNode {
+    fn scalar(kind: NodeKind, value: Option<String>) -> TreeNode {
+        TreeNode {
+            key: None,
+            kind,
+            value,
+            tag: None,
+            children: Vec::new(),
+            child_count: 0,
+        }
+    }
+
+    fn parent(kind: NodeKind, children: Vec<TreeNode>) -> TreeNode {
+        TreeNode {
+            key: None,
+            kind,
+            value: None,
+            tag: None,
+            child_count: children.len(),
+            children,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TreeNode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(NodeVisitor)
+    }
+}
+
+struct NodeVisitor;
+
+impl<'de> de::Visitor<'de> for NodeVisitor {
+    type Value = TreeNode;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<TreeNode, E> {
+        Ok(TreeNode::scalar(NodeKind::Bool, Some(v.to_string())))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<TreeNode, E> {
+        Ok(TreeNode::scalar(NodeKind::Number, Some(v.to_string())))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<TreeNode, E> {
+        Ok(TreeNode::scalar(NodeKind::Number, Some(v.to_string())))
+    }
+
+    fn visit_i128<E>(self, v: i128) -> Result<TreeNode, E> {
+        Ok(TreeNode::scalar(NodeKind::Number, Some(v.to_string())))
+    }
+
+    fn visit_u128<E>(self, v: u128) -> Result<TreeNode, E> {
+        Ok(TreeNode::scalar(NodeKind::Number, Some(v.to_string())))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<TreeNode, E> {
+        Ok(TreeNode::scalar(NodeKind::Number, Some(v.to_string())))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<TreeNode, E> {
+        Ok(TreeNode::scalar(NodeKind::String, Some(v.to_string())))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<TreeNode, E> {
+        Ok(TreeNode::scalar(
+            NodeKind::String,
+            Some(String::from_utf8_lossy(v).to_string()),
+        ))
+    }
+
+    fn visit_unit<E>(self) -> Result<TreeNode, E> {
+        Ok(TreeNode::scalar(NodeKind::Null, None))
+    }
+
+    fn visit_none<E>(self) -> Result<TreeNode, E> {
+        Ok(TreeNode::scalar(NodeKind::Null, None))
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<TreeNode, D::Error> {
+        TreeNode::deserialize(deserializer)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<TreeNode, D::Error> {
+        TreeNode::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<TreeNode, A::Error> {
+        let mut children = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(child) = seq.next_element::<TreeNode>()? {
+            children.push(child);
+        }
+        Ok(TreeNode::parent(NodeKind::Array, children))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<TreeNode, A::Error> {
+        let mut children = Vec::with_capacity(map.size_hint().unwrap_or(0).min(4096));
+        while let Some(key) = map.next_key::<TreeNode>()? {
+            if key.value.as_deref() == Some(TOML_DATETIME_KEY) {
+                let date: String = map.next_value()?;
+                return Ok(TreeNode::scalar(NodeKind::Date, Some(date)));
+            }
+            let mut child = map.next_value::<TreeNode>()?;
+            // YAML allows any value as a key; show scalars as they are
+            child.key = Some(key.value.unwrap_or_else(|| match key.kind {
+                NodeKind::Null => "null".to_string(),
+                NodeKind::Array => "[…]".to_string(),
+                _ => "{…}".to_string(),
+            }));
+            children.push(child);
+        }
+        Ok(TreeNode::parent(NodeKind::Object, children))
+    }
+
+    /// YAML tags like "!Ref name" come in as enums
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<TreeNode, A::Error> {
+        let (tag, variant) = data.variant::<String>()?;
+        let mut node = variant.newtype_variant::<TreeNode>()?;
+        node.tag = Some(format!("!{}", tag));
+        Ok(node)
+    }
+}
@@ -0,0 +1,385 @@
+// The thumb://, image-tile:// and file-content:// URI schemes, which send
+// thumbnails, viewer tiles and file bodies to the webview as plain HTTP
+// responses, so <img>, <video> and <audio> load them straight from disk rather
+// than as base64 through IPC. The path is the percent-encoded URL path, as
+// convertFileSrc writes it. file-content:// answers Range requests, which
+// media elements need to seek.
+
+use crate::archive;
+use crate::error::{AppError, PathContext};
+use crate::thumbnails;
+use crate::viewer::{self, Region};
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tauri::http::{header, Request, Response, StatusCode};
+use tauri::{Runtime, UriSchemeContext, UriSchemeResponder};
+
+/// Most bytes sent for a range with no end ("bytes=100-"), which is how
+/// media elements ask; they come back for the rest
+const MAX_OPEN_RANGE: u64 = 4 * 1024 * 1024;
+
+/// Largest file sent whole to a request without a Range header; larger ones
+/// are refused with 413 rather than read into memory, since responses can't
+/// be streamed. Media elements always ask for ranges.
+const MAX_WHOLE_BODY: u64 = 32 * 1024 * 1024;
+
+type HttpResponse = Response<Cow<'static, [u8]>>;
+
+/// The archive member served last: a media element seeking through it asks
+/// for one range after another
+static LAST_MEMBER: Mutex<Option<CachedMember>> = Mutex::new(None);
+
+struct CachedMember {
+    path: PathBuf,
+    modified: Option<SystemTime>, // The archive's
+    data: Arc<Vec<u8>>,
+}
+
+/// thumb://localhost/<path>?size=<n>: a PNG thumbnail, as the scheduler makes
+/// them
+pub fn thumb<R: Runtime>(
+    _: UriSchemeContext<'_, R>,
+    request: Request<Vec<u8>>,
+    responder: UriSchemeResponder,
+) {
+    tauri::async_runtime::spawn_blocking(move || {
+        let response = match AppError::catch(|| thumb_response(&request)) {
+            Ok(response) => response,
+            Err(e) => error_response(&e),
+        };
+        responder.respond(response);
+    });
+}
+
+/// image-tile://localhost/<path>?x=&y=&width=&height=&scale=: a region of a
+/// picture open in the viewer (all of it without x, y, width and height),
+/// scaled down by scale (1 by default)
+pub fn image_tile<R: Runtime>(
+    _: UriSchemeContext<'_, R>,
+    request: Request<Vec<u8>>,
+    responder: UriSchemeResponder,
+) {
+    tauri::async_runtime::spawn_blocking(move || {
+        let response = match AppError::catch(|| image_tile_response(&request)) {
+            Ok(response) => response,
+            Err(e) => error_response(&e),
+        };
+        responder.respond(response);
+    });
+}
+
+/// file-content://localhost/<path>: the file itself, or a byte range of it.
+/// Files inside archives are served too, unless they're larger than
+/// MEMBER_READ_LIMIT.
+pub fn file_content<R: Runtime>(
+    _: UriSchemeContext<'_, R>,
+    request: Request<Vec<u8>>,
+    responder: UriSchemeResponder,
+) {
+    tauri::async_runtime::spawn_blocking(move || {
+        let response = match AppError::catch(|| file_content_response(&request)) {
+            Ok(response) => response,
+            Err(e) => error_response(&e),
+        };
+        responder.respond(response);
+    });
+}
+
+fn thumb_response(request: &Request<Vec<u8>>) -> Result<HttpResponse, AppError> {
+    let path = request_path(request);
+    let size = request
+        .uri()
+        .query()
+        .and_then(|query| {
+            url::form_urlencoded::parse(query.as_bytes())
+                .find(|(key, _)| key == "size")
+                .and_then(|(_, value)| value.parse().ok())
+        })
+        .unwrap_or(thumbnails::DEFAULT_SIZE);
+
+    let png = thumbnails::thumbnail(&path, size)?;
+    Response::builder()
+        .header(header::CONTENT_TYPE, "image/png")
+        // Asked again each time, since the file may change; our caches answer
+        .header(header::CACHE_CONTROL, "no-cache")
+        .body(png.into())
+        .map_err(AppError::other)
+}
+
+fn image_tile_response(request: &Request<Vec<u8>>) -> Result<HttpResponse, AppError> {
+    let path = request_path(request);
+    let query: Vec<(Cow<str>, Cow<str>)> = request
+        .uri()
+        .query()
+        .map(|query| url::form_urlencoded::parse(query.as_bytes()).collect())
+        .unwrap_or_default();
+    let param = |name: &str| {
+        query
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_ref())
+    };
+    let number = |name: &str| param(name).and_then(|value| value.parse::<u32>().ok());
+
+    let region = match (number("x"), number("y"), number("width"), number("height")) {
+        (Some(x), Some(y), Some(width), Some(height)) => Some(Region {
+            x,
+            y,
+            width,
+            height,
+        }),
+        _ => None,
+    };
+    let scale = match param("scale") {
+        Some(scale) => scale
+            .parse()
+            .map_err(|_| AppError::invalid_input("Scale is not a number"))?,
+        None => 1.0,
+    };
+
+    let (body, content_type) = viewer::tile(Path::new(&path), region, scale)?;
+    Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::CACHE_CONTROL, "no-cache")
+        .body(body.into())
+        .map_err(AppError::other)
+}
+
+fn file_content_response(request: &Request<Vec<u8>>) -> Result<HttpResponse, AppError> {
+    let path = request_path(request);
+    let path = Path::new(&path);
+
+    // Archive members are read whole, then sliced
+    let (mut body, total) = if let Some((archive_path, inner)) = archive::member_path(path) {
+        let data = member(path, &archive_path, &inner)?;
+        let total = data.len() as u64;
+        (Body::Memory(data), total)
+    } else {
+        if path.is_dir() {
+            return Err(AppError::is_a_directory(path));
+        }
+        let file = File::open(path).at(path)?;
+        let total = file.metadata().at(path)?.len();
+        (Body::File(file), total)
+    };
+
+    let builder = Response::builder()
+        .header(header::CONTENT_TYPE, content_type(path))
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::CACHE_CONTROL, "no-cache");
+
+    // A Range header that makes no sense is ignored, as if there were none
+    let range = request
+        .headers()
+        .get(header::RANGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|range| parse_range(range, total));
+    let response = match range {
+        None if total > MAX_WHOLE_BODY => {
+            let error = AppError::unsupported(path, "Too large to send whole; ask for a range");
+            return Ok(error_body(StatusCode::PAYLOAD_TOO_LARGE, &error));
+        }
+        None => builder
+            .header(header::CONTENT_LENGTH, total)
+            .body(body.read(0, total).at(path)?.into()),
+        Some(Some((start, end))) => builder
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header(
+                header::CONTENT_RANGE,
+                format!("bytes {}-{}/{}", start, end - 1, total),
+            )
+            .header(header::CONTENT_LENGTH, end - start)
+            .body(body.read(start, end - start).at(path)?.into()),
+        Some(None) => builder
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(header::CONTENT_RANGE, format!("bytes */{}", total))
+            .body(Vec::new().into()),
+    };
+    response.map_err(AppError::other)
+}
+
+/// An archive member's bytes, from LAST_MEMBER if it's the one served last
+/// and the archive hasn't changed
+fn member(path: &Path, archive_path: &Path, inner: &str) -> Result<Arc<Vec<u8>>, AppError> {
+    let modified = thumbnails::modified(archive_path);
+    if let Some(last) = LAST_MEMBER
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+    {
+        if last.path == path && last.modified == modified {
+            return Ok(last.data.clone());
+        }
+    }
+    let data = Arc::new(archive::read_whole_member(archive_path, inner)?);
+    *LAST_MEMBER.lock().unwrap_or_else(|e| e.into_inner()) = Some(CachedMember {
+        path: path.to_path_buf(),
+        modified,
+        data: data.clone(),
+    });
+    Ok(data)
+}
+
+/// Where a response's bytes come from
+enum Body {
+    File(File),
+    Memory(Arc<Vec<u8>>),
+}
+
+impl Body {
+    fn read(&mut self, start: u64, len: u64) -> std::io::Result<Vec<u8>> {
+        match self {
+            Body::File(file) => {
+                file.seek(SeekFrom::Start(start))?;
+                let mut data = Vec::with_capacity(len as usize);
+                file.take(len).read_to_end(&mut data)?;
+                Ok(data)
+            }
+            Body::Memory(data) => {
+                let start = start as usize;
+                Ok(data[start..start + len as usize].to_vec())
+            }
+        }
+    }
+}
+
+/// The file path in a request URL
+fn request_path(request: &Request<Vec<u8>>) -> String {
+    // Skip the leading "/"; convertFileSrc encodes the separators inside
+    let path = request.uri().path();
+    let encoded = path.strip_prefix('/').unwrap_or(path);
+    percent_encoding::percent_decode_str(encoded)
+        .decode_utf8_lossy()
+        .into_owned()
+}
+
+/// The bytes asked for by a Range header, as start and end (exclusive).
+/// Some(None) when the range is outside the file, and None when the header
+/// isn't a valid range at all; only the first of several ranges is served.
+fn parse_range(range: &str, total: u64) -> Option<Option<(u64, u64)>> {
+    let spec = range.strip_prefix("bytes=")?.split(',').next()?.trim();
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        // The last n bytes
+        ("", suffix) => {
+            let n: u64 = suffix.parse().ok()?;
+            (total.saturating_sub(n), total)
+        }
+        (start, "") => {
+            let start: u64 = start.parse().ok()?;
+            (start, total.min(start.saturating_add(MAX_OPEN_RANGE)))
+        }
+        (start, end) => {
+            let start: u64 = start.parse().ok()?;
+            let end: u64 = end.parse().ok()?;
+            if end < start {
+                return None;
+            }
+            (start, total.min(end.saturating_add(1)))
+        }
+    };
+    Some((start < end).then_some((start, end)))
+}
+
+fn error_response(error: &AppError) -> HttpResponse {
+    let status = match error {
+        AppError::NotFound { .. } => StatusCode::NOT_FOUND,
+        AppError::PermissionDenied { .. } => StatusCode::FORBIDDEN,
+        AppError::Unsupported { .. } => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        AppError::IsADirectory { .. } | AppError::InvalidInput { .. } => StatusCode::BAD_REQUEST,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    error_body(status, error)
+}
+
+/// The error as commands return it, for fetch() callers
+fn error_body(status: StatusCode, error: &AppError) -> HttpResponse {
+    let body = serde_json::to_vec(error).unwrap_or_default();
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(body.into())
+        .unwrap_or_default()
+}
+
+/// Content type by extension; what the webview can play or show, and text
+fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "bmp" => "image/bmp",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "ico" => "image/x-icon",
+        "svg" => "image/svg+xml",
+        "tif" | "tiff" => "image/tiff",
+        "mp4" | "m4v" => "video/mp4",
+        "mov" => "video/quicktime",
+        "webm" => "video/webm",
+        "mkv" => "video/x-matroska",
+        "ogv" => "video/ogg",
+        "mp3" => "audio/mpeg",
+        "m4a" | "m4b" => "audio/mp4",
+        "aac" => "audio/aac",
+        "flac" => "audio/flac",
+        "ogg" | "oga" | "opus" => "audio/ogg",
+        "wav" => "audio/wav",
+        "pdf" => "application/pdf",
+        "json" => "application/json",
+        // HTML and scripts stay text, so nothing loaded this way runs
+        "txt" | "md" | "log" | "csv" | "tsv" | "html" | "htm" | "js" | "css" | "xml" => {
+            "text/plain; charset=utf-8"
+        }
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges() {
+        assert_eq!(parse_range("bytes=0-4", 10), Some(Some((0, 5))));
+        assert_eq!(parse_range("bytes=5-100", 10), Some(Some((5, 10))));
+        assert_eq!(parse_range("bytes=2-3, 6-7", 10), Some(Some((2, 4))));
+        assert_eq!(
+            parse_range("bytes=3-", u64::MAX),
+            Some(Some((3, 3 + MAX_OPEN_RANGE)))
+        );
+        assert_eq!(parse_range("bytes=3-", 10), Some(Some((3, 10))));
+    }
+
+    #[test]
+    fn suffix_ranges() {
+        assert_eq!(parse_range("bytes=-5", 10), Some(Some((5, 10))));
+        assert_eq!(parse_range("bytes=-20", 10), Some(Some((0, 10))));
+        // Nothing to send
+        assert_eq!(parse_range("bytes=-0", 10), Some(None));
+    }
+
+    #[test]
+    fn unsatisfiable_ranges() {
+        assert_eq!(parse_range("bytes=10-", 10), Some(None));
+        assert_eq!(parse_range("bytes=12-20", 10), Some(None));
+        assert_eq!(parse_range("bytes=0-", 0), Some(None));
+    }
+
+    #[test]
+    fn invalid_ranges_are_ignored() {
+        assert_eq!(parse_range("bytes=5-3", 10), None);
+        assert_eq!(parse_range("bytes=5", 10), None);
+        assert_eq!(parse_range("bytes=a-b", 10), None);
+        assert_eq!(parse_range("bytes=-", 10), None);
+        assert_eq!(parse_range("items=0-4", 10), None);
+    }
+}
@@ -0,0 +1,389 @@
+// New-file templates: built-ins plus templates found in the user's Templates
+// folder (XDG on Linux, ShellNew on Windows) and the app's own template folder.
+// Text templates may contain placeholders such as {{date}} and {{user}}.
+
+use crate::error::{AppError, PathContext};
+use chrono::{DateTime, Local, SecondsFormat};
+use serde::Serialize;
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+/// A template the user can create a new file from
+#[derive(Serialize)]
+pub struct FileTemplate {
+    pub id: String,        // "builtin:<name>" or the template file's path
+    pub name: String,      // Display name
+    pub file_name: String, // Suggested name for the new file
+    pub extension: String,
+    pub source: String, // "builtin", "user" or "app"
+}
+
+enum BuiltinContent {
+    Text(&'static str),
+    Docx,
+    Xlsx,
+}
+
+struct BuiltinTemplate {
+    id: &'static str,
+    name: &'static str,
+    file_name: &'static str,
+    content: BuiltinContent,
+}
+
+const BUILTIN_TEMPLATES: &[BuiltinTemplate] = &[
+    BuiltinTemplate {
+        id: "builtin:text",
+        name: "Text Document",
+        file_name: "New Text Document.txt",
+        content: BuiltinContent::Text(""),
+    },
+    BuiltinTemplate {
+        id: "builtin:markdown",
+        name: "Markdown Document",
+        file_name: "New Document.md",
+        content: BuiltinContent::Text("# {{name}}\n\n"),
+    },
+    BuiltinTemplate {
+        id: "builtin:json",
+        name: "JSON File",
+        file_name: "New File.json",
+        content: BuiltinContent::Text("{}\n"),
+    },
+    BuiltinTemplate {
+        id: "builtin:gitignore",
+        name: "Git Ignore",
+        file_name: ".gitignore",
+        content: BuiltinContent::Text(GITIGNORE_TEMPLATE),
+    },
+    BuiltinTemplate {
+        id: "builtin:docx",
+        name: "Word Document",
+        file_name: "New Document.docx",
+        content: BuiltinContent::Docx,
+    },
+    BuiltinTemplate {
+        id: "builtin:xlsx",
+        name: "Excel Workbook",
+        file_name: "New Workbook.xlsx",
+        content: BuiltinContent::Xlsx,
+    },
+];
+
+const GITIGNORE_TEMPLATE: &str = "# Dependencies
+node_modules/
+
+# Build output
+target/
+dist/
+build/
+
+# Editor and OS files
+.vscode/
+.idea/
+.DS_Store
+Thumbs.db
+";
+
+/// Values substituted for {{placeholders}} in templates
+struct PlaceholderContext {
+    name: String,
+    user: String,
+    now: DateTime<Local>,
+}
+
+impl PlaceholderContext {
+    fn new(dest: &Path) -> Self {
+        let name = dest
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let user = std::env::var("USERNAME")
+            .or_else(|_| std::env::var("USER"))
+            .unwrap_or_default();
+
+        PlaceholderContext {
+            name,
+            user,
+            now: Local::now(),
+        }
+    }
+
+    /// Replace every known placeholder; values are XML-escaped for OOXML parts
+    fn expand(&self, text: &str, xml: bool) -> String {
+        let escape = |value: &str| {
+            if xml {
+                value
+                    .replace('&', "&amp;")
+                    .replace('<', "&lt;")
+                    .replace('>', "&gt;")
+                    .replace('"', "&quot;")
+            } else {
+                value.to_string()
+            }
+        };
+
+        text.replace("{{date}}", &self.now.format("%Y-%m-%d").to_string())
+            .replace("{{time}}", &self.now.format("%H:%M").to_string())
+            .replace(
+                "{{datetime}}",
+                &self.now.to_rfc3339_opts(SecondsFormat::Secs, false),
+            )
+            .replace("{{year}}", &self.now.format("%Y").to_string())
+            .replace("{{user}}", &escape(&self.user))
+            .replace("{{name}}", &escape(&self.name))
+    }
+}
+
+/// List the built-in templates followed by templates found on disk
+#[tauri::command]
+pub fn list_templates(app: tauri::AppHandle) -> Vec<FileTemplate> {
+    let mut templates: Vec<FileTemplate> = BUILTIN_TEMPLATES
+        .iter()
+        .map(|t| FileTemplate {
+            id: t.id.to_string(),
+            name: t.name.to_string(),
+            file_name: t.file_name.to_string(),
+            extension: Path::new(t.file_name)
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default(),
+            source: "builtin".to_string(),
+        })
+        .collect();
+
+    for (dir, source) in template_dirs(&app) {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+
+        let mut found: Vec<FileTemplate> = entries
+            .flatten()
+            .filter(|e| e.metadata().map(|m| m.is_file()).unwrap_or(false))
+            .map(|e| {
+                let path = e.path();
+                let file_name = e.file_name().to_string_lossy().to_string();
+                FileTemplate {
+                    id: path.to_string_lossy().to_string(),
+                    name: path
+                        .file_stem()
+                        .map(|s| s.to_string_lossy().to_string())
+                        .unwrap_or_else(|| file_name.clone()),
+                    extension: path
+                        .extension()
+                        .map(|e| e.to_string_lossy().to_lowercase())
+                        .unwrap_or_default(),
+                    file_name,
+                    source: source.to_string(),
+                }
+            })
+            .collect();
+
+        found.sort_by_key(|t| t.name.to_lowercase());
+        templates.extend(found);
+    }
+
+    templates
+}
+
+/// Create a new file, optionally from a template.
+/// Returns the path of the created file, made unique if the name is taken.
+#[tauri::command]
+pub fn create_file(
+    app: tauri::AppHandle,
+    path: String,
+    name: String,
+    template: Option<String>,
+) -> Result<String, AppError> {
+    let dir = Path::new(&path);
+
+    if !dir.is_dir() {
+        return Err(AppError::not_a_directory(dir));
+    }
+
+    crate::validation::check_path(dir)?;
+    crate::validation::check_name(&name)?;
+
+    let dest = crate::get_unique_path(dir, &name, false);
+    let context = PlaceholderContext::new(&dest);
+
+    let content = match template.as_deref() {
+        None => Vec::new(),
+        Some(id) => match BUILTIN_TEMPLATES.iter().find(|t| t.id == id) {
+            Some(builtin) => render_builtin(builtin, &context)?,
+            None => render_template_file(&app, Path::new(id), &context)?,
+        },
+    };
+
+    // create_new so a file appearing in the meantime is never overwritten
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&dest)
+        .at(&dest)?;
+    file.write_all(&content).at(&dest)?;
+
+    Ok(dest.to_string_lossy().to_string())
+}
+
+/// Folders that templates are read from, paired with their source label
+fn template_dirs(app: &tauri::AppHandle) -> Vec<(PathBuf, &'static str)> {
+    let mut dirs = Vec::new();
+
+    if let Some(dir) = user_templates_dir() {
+        dirs.push((dir, "user"));
+    }
+
+    if let Ok(data_dir) = app.path().app_data_dir() {
+        dirs.push((data_dir.join("templates"), "app"));
+    }
+
+    dirs
+}
+
+/// The user's Templates folder: XDG_TEMPLATES_DIR on Linux
+#[cfg(not(target_os = "windows"))]
+fn user_templates_dir() -> Option<PathBuf> {
+    let home = PathBuf::from(std::env::var_os("HOME")?);
+
+    if let Some(dir) = std::env::var_os("XDG_TEMPLATES_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+
+    // user-dirs.dirs holds lines like XDG_TEMPLATES_DIR="$HOME/Templates"
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home.join(".config"));
+    if let Ok(user_dirs) = fs::read_to_string(config_dir.join("user-dirs.dirs")) {
+        for line in user_dirs.lines() {
+            if let Some(value) = line.trim().strip_prefix("XDG_TEMPLATES_DIR=") {
+                let value = value.trim_matches('"');
+                let dir = match value.strip_prefix("$HOME") {
+                    Some(rest) => home.join(rest.trim_start_matches('/')),
+                    None => PathBuf::from(value),
+                };
+                // Pointing the templates dir at $HOME is how users disable it
+                return if dir == home { None } else { Some(dir) };
+            }
+        }
+    }
+
+    Some(home.join("Templates"))
+}
+
+/// The user's Templates folder: the ShellNew templates folder on Windows
+#[cfg(target_os = "windows")]
+fn user_templates_dir() -> Option<PathBuf> {
+    let appdata = std::env::var_os("APPDATA")?;
+    Some(
+        Path::new(&appdata)
+            .join("Microsoft")
+            .join("Windows")
+            .join("Templates"),
+    )
+}
+
+/// Read a template from disk, expanding placeholders if it is a text file
+fn render_template_file(
+    app: &tauri::AppHandle,
+    template: &Path,
+    context: &PlaceholderContext,
+) -> Result<Vec<u8>, AppError> {
+    // Only files inside a known template folder may be used as templates
+    let canonical = template.canonicalize().at(template)?;
+    let allowed = template_dirs(app).iter().any(|(dir, _)| {
+        dir.canonicalize()
+            .map(|d| canonical.starts_with(d))
+            .unwrap_or(false)
+    });
+    if !allowed || !canonical.is_file() {
+        return Err(AppError::unsupported(template, "Not a template"));
+    }
+
+    let bytes = fs::read(&canonical).at(template)?;
+
+    Ok(match String::from_utf8(bytes) {
+        Ok(text) => context.expand(&text, false).into_bytes(),
+        Err(e) => e.into_bytes(), // Binary templates are copied as-is
+    })
+}
+
+fn render_builtin(
+    template: &BuiltinTemplate,
+    context: &PlaceholderContext,
+) -> Result<Vec<u8>, AppError> {
+    match template.content {
+        BuiltinContent::Text(text) => Ok(context.expand(text, false).into_bytes()),
+        BuiltinContent::Docx => build_ooxml(DOCX_PARTS, context),
+        BuiltinContent::Xlsx => build_ooxml(XLSX_PARTS, context),
+    }
+}
+
+/// Zip the given OOXML parts into a package, expanding placeholders in each part
+fn build_ooxml(parts: &[(&str, &str)], context: &PlaceholderContext) -> Result<Vec<u8>, AppError> {
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+
+    for (name, content) in parts {
+        zip.start_file(*name, options).map_err(AppError::other)?;
+        zip.write_all(context.expand(content, true).as_bytes())
+            .map_err(AppError::other)?;
+    }
+
+    let cursor = zip.finish().map_err(AppError::other)?;
+    Ok(cursor.into_inner())
+}
+
+const CORE_PROPERTIES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:dcterms="http://purl.org/dc/terms/" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"><dc:title>{{name}}</dc:title><dc:creator>{{user}}</dc:creator><dcterms:created xsi:type="dcterms:W3CDTF">{{datetime}}</dcterms:created></cp:coreProperties>"#;
+
+const DOCX_PARTS: &[(&str, &str)] = &[
+    (
+        "[Content_Types].xml",
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/><Override PartName="/docProps/core.xml" ContentType="application/vnd.openxmlformats-package.core-properties+xml"/></Types>"#,
+    ),
+    (
+        "_rels/.rels",
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties" Target="docProps/core.xml"/></Relationships>"#,
+    ),
+    (
+        "word/document.xml",
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body><w:p/></w:body></w:document>"#,
+    ),
+    ("docProps/core.xml", CORE_PROPERTIES),
+];
+
+const XLSX_PARTS: &[(&str, &str)] = &[
+    (
+        "[Content_Types].xml",
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/><Override PartName="/docProps/core.xml" ContentType="application/vnd.openxmlformats-package.core-properties+xml"/></Types>"#,
+    ),
+    (
+        "_rels/.rels",
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties" Target="docProps/core.xml"/></Relationships>"#,
+    ),
+    (
+        "xl/workbook.xml",
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets><sheet name="Sheet1" sheetId="1" r:id="rId1"/></sheets></workbook>"#,
+    ),
+    (
+        "xl/_rels/workbook.xml.rels",
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/></Relationships>"#,
+    ),
+    (
+        "xl/worksheets/sheet1.xml",
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData/></worksheet>"#,
+    ),
+    ("docProps/core.xml", CORE_PROPERTIES),
+];
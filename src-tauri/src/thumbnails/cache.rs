@@ -0,0 +1,317 @@
+// The freedesktop.org thumbnail cache: thumbnails/{normal,large,x-large,
+// xx-large}/<MD5 of the file URI>.png, with the URI and the file's mtime in
+// tEXt chunks so stale entries are spotted. On Linux these are the
+// directories file managers and image viewers use, so thumbnails are shared
+// both ways; on Windows the same layout lives in the app's local data folder.
+// Only the thumbnails this app wrote, marked by their Software chunk, count
+// towards MAX_CACHE_SIZE: past it the least recently used of them are removed,
+// and clearing the cache removes just those. Other apps' are left alone.
+// Telling them apart means opening every PNG in a cache that may hold tens of
+// thousands, so counting and eviction run on a thread of their own, never in
+// the way of a thumbnail being stored.
+
+use crate::error::AppError;
+use filetime::FileTime;
+use md5::{Digest, Md5};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Directories and their largest thumbnail side, from the spec
+const SIZES: &[(&str, u32)] = &[
+    ("normal", 128),
+    ("large", 256),
+    ("x-large", 512),
+    ("xx-large", 1024),
+];
+
+/// Total size of the cache directories before old thumbnails are removed
+const MAX_CACHE_SIZE: u64 = 512 * 1024 * 1024;
+
+/// Eviction frees down to this, so it doesn't run again on the next store
+const EVICT_TO_SIZE: u64 = MAX_CACHE_SIZE / 10 * 9;
+
+/// Software chunk of the thumbnails this app writes
+const SOFTWARE: &str = "EdoriFile Explorer";
+
+/// Folder under %LOCALAPPDATA% on Windows, as Tauri names the app's folders
+const WINDOWS_APP_DIR: &str = "com.edgar.edorifile-explorer";
+
+/// Bytes of this app's thumbnails; None until the first scan has counted them
+static CACHE_USAGE: Mutex<Option<u64>> = Mutex::new(None);
+
+/// Set while a scan walks the cache directories
+static SCANNING: AtomicBool = AtomicBool::new(false);
+
+/// Makes temporary file names unique between threads
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Serialize)]
+pub struct ClearedCache {
+    pub files: u64,
+    pub bytes: u64,
+}
+
+/// Where a file's thumbnail goes and what it must record
+struct CacheEntry {
+    thumbnail: PathBuf,
+    uri: String,
+    mtime: u64, // Seconds since the epoch
+    size: u64,
+}
+
+/// Side of the cached thumbnail used for a requested size
+pub fn cache_size(size: u32) -> u32 {
+    bucket(size).1
+}
+
+/// The smallest cache directory whose thumbnails are at least size
+fn bucket(size: u32) -> (&'static str, u32) {
+    SIZES
+        .iter()
+        .copied()
+        .find(|&(_, side)| side >= size)
+        .unwrap_or(SIZES[SIZES.len() - 1])
+}
+
+fn cache_root() -> Option<PathBuf> {
+    if cfg!(windows) {
+        let local = std::env::var_os("LOCALAPPDATA")?;
+        return Some(Path::new(&local).join(WINDOWS_APP_DIR).join("thumbnails"));
+    }
+    // The spec says to ignore a relative XDG_CACHE_HOME
+    let cache = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))?;
+    Some(cache.join("thumbnails"))
+}
+
+fn cache_entry(file: &Path, size: u32) -> Option<CacheEntry> {
+    let root = cache_root()?;
+    let file = std::path::absolute(file).ok()?;
+    // Thumbnails of thumbnails would never end
+    if file.starts_with(&root) {
+        return None;
+    }
+    let metadata = fs::metadata(&file).ok()?;
+    let mtime = metadata
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_secs();
+    let uri = url::Url::from_file_path(&file).ok()?.to_string();
+    let name = format!("{:x}.png", Md5::digest(uri.as_bytes()));
+    Some(CacheEntry {
+        thumbnail: root.join(bucket(size).0).join(name),
+        uri,
+        mtime,
+        size: metadata.len(),
+    })
+}
+
+/// The cached PNG thumbnail of a file, if there's one for its current version
+pub fn lookup(file: &Path, size: u32) -> Option<Vec<u8>> {
+    let entry = cache_entry(file, size)?;
+    let png = fs::read(&entry.thumbnail).ok()?;
+    let text = text_chunks(&png)?;
+
+    // Some writers store the mtime with a fraction; Thumb::Size is optional
+    let mtime = text.get("Thumb::MTime")?.parse::<f64>().ok()? as u64;
+    let size_matches = text
+        .get("Thumb::Size")
+        .is_none_or(|s| s.parse::<u64>().ok() == Some(entry.size));
+    if text.get("Thumb::URI") != Some(&entry.uri) || mtime != entry.mtime || !size_matches {
+        return None;
+    }
+
+    // The cache file's own mtime is when it was last used, for eviction
+    let _ = filetime::set_file_mtime(&entry.thumbnail, FileTime::now());
+    Some(png)
+}
+
+/// Encode a thumbnail as PNG and save it in the cache. Returns the PNG even
+/// when it can't be saved; a cache that can't be written just stays empty.
+pub fn store(file: &Path, size: u32, thumbnail: &image::DynamicImage) -> Result<Vec<u8>, AppError> {
+    let entry = cache_entry(file, size);
+    let rgba = thumbnail.to_rgba8();
+
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, rgba.width(), rgba.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    if let Some(entry) = &entry {
+        let chunks = [
+            ("Thumb::URI", entry.uri.clone()),
+            ("Thumb::MTime", entry.mtime.to_string()),
+            ("Thumb::Size", entry.size.to_string()),
+            ("Software", SOFTWARE.to_string()),
+        ];
+        for (keyword, text) in chunks {
+            encoder
+                .add_text_chunk(keyword.to_string(), text)
+                .map_err(AppError::other)?;
+        }
+    }
+    let mut writer = encoder.write_header().map_err(AppError::other)?;
+    writer.write_image_data(&rgba).map_err(AppError::other)?;
+    writer.finish().map_err(AppError::other)?;
+
+    if let Some(entry) = entry {
+        if write_atomic(&entry.thumbnail, &png).is_ok() {
+            account(png.len() as u64);
+        }
+    }
+    Ok(png)
+}
+
+/// Remove every thumbnail this app made; other apps' in the shared cache stay
+#[tauri::command]
+pub async fn clear_thumbnail_cache() -> Result<ClearedCache, AppError> {
+    tauri::async_runtime::spawn_blocking(clear)
+        .await
+        .map_err(AppError::other)
+}
+
+fn clear() -> ClearedCache {
+    let mut cleared = ClearedCache { files: 0, bytes: 0 };
+    for (path, size, _) in cache_files() {
+        if fs::remove_file(&path).is_ok() {
+            cleared.files += 1;
+            cleared.bytes += size;
+        }
+    }
+    if let Some(total) = CACHE_USAGE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_mut()
+    {
+        *total = total.saturating_sub(cleared.bytes);
+    }
+    cleared
+}
+
+/// tEXt chunks ahead of the image data, which is where the spec puts them
+fn text_chunks(png: &[u8]) -> Option<HashMap<String, String>> {
+    let reader = png::Decoder::new(Cursor::new(png)).read_info().ok()?;
+    Some(
+        reader
+            .info()
+            .uncompressed_latin1_text
+            .iter()
+            .map(|chunk| (chunk.keyword.clone(), chunk.text.clone()))
+            .collect(),
+    )
+}
+
+/// Write through a temporary file and rename, so readers never see half a
+/// thumbnail. The spec asks for private directories and files.
+fn write_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(dir)?;
+
+    let temp = dir.join(format!(
+        ".{}-{}.tmp",
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let result = options
+        .open(&temp)
+        .and_then(|mut file| file.write_all(data))
+        .and_then(|_| fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Count a new thumbnail towards the cache size, starting a scan if the cache
+/// hasn't been counted yet or has grown too big
+fn account(added: u64) {
+    let scan_needed = match CACHE_USAGE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_mut()
+    {
+        Some(total) => {
+            *total += added;
+            *total > MAX_CACHE_SIZE
+        }
+        None => true,
+    };
+    if scan_needed && !SCANNING.swap(true, Ordering::AcqRel) {
+        std::thread::spawn(scan);
+    }
+}
+
+/// Count this app's thumbnails, and if they're over MAX_CACHE_SIZE remove the
+/// least recently used down to EVICT_TO_SIZE. Thumbnails stored meanwhile are
+/// counted if the walk gets to them.
+fn scan() {
+    let mut files = cache_files();
+    let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+    if total > MAX_CACHE_SIZE {
+        files.sort_by_key(|(_, _, used)| *used);
+        for (path, size, _) in files {
+            if total <= EVICT_TO_SIZE {
+                break;
+            }
+            if fs::remove_file(&path).is_ok() {
+                total -= size;
+            }
+        }
+    }
+    *CACHE_USAGE.lock().unwrap_or_else(|e| e.into_inner()) = Some(total);
+    SCANNING.store(false, Ordering::Release);
+}
+
+/// This app's thumbnails in every size directory: path, size and last use
+fn cache_files() -> Vec<(PathBuf, u64, SystemTime)> {
+    let Some(root) = cache_root() else {
+        return Vec::new();
+    };
+    SIZES
+        .iter()
+        .filter_map(|(dir, _)| fs::read_dir(root.join(dir)).ok())
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().extension().is_some_and(|e| e == "png"))
+        .filter(|entry| written_here(&entry.path()))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            let used = metadata.modified().unwrap_or(UNIX_EPOCH);
+            Some((entry.path(), metadata.len(), used))
+        })
+        .collect()
+}
+
+/// Whether a cached thumbnail is one of this app's. Only the chunks ahead of
+/// the image data are read.
+fn written_here(path: &Path) -> bool {
+    let Ok(file) = fs::File::open(path) else {
+        return false;
+    };
+    let Ok(reader) = png::Decoder::new(io::BufReader::new(file)).read_info() else {
+        return false;
+    };
+    reader
+        .info()
+        .uncompressed_latin1_text
+        .iter()
+        .any(|chunk| chunk.keyword == "Software" && chunk.text == SOFTWARE)
+}
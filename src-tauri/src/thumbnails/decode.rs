@@ -0,0 +1,216 @@
+// Decoding images into thumbnails that look the way the image is meant to:
+// turned by the EXIF orientation, converted to sRGB when there's an ICC
+// profile, and shrunk with premultiplied alpha so transparent edges don't go
+// dark. A JPEG whose EXIF thumbnail is at least the size asked for uses that
+// instead of decoding the whole photo.
+
+use super::{Source, Thumbnailer};
+use crate::error::AppError;
+use exif::{In, Tag};
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader, Limits, RgbaImage};
+use moxcms::{ColorProfile, DataColorSpace, Layout, TransformOptions};
+use std::io::{BufRead, Seek};
+
+/// Most an EXIF thumbnail's aspect ratio may differ from the photo's; some
+/// cameras letterbox them to 4:3 or 16:9
+const ASPECT_TOLERANCE: f64 = 0.02;
+
+/// Most memory a decoder may use; the image crate's default of 512 MB turns
+/// away 16-bit scans and photos of 100 megapixels and up
+const MAX_DECODE_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+/// Photos and other raster images
+pub struct Images;
+
+impl Thumbnailer for Images {
+    fn extensions(&self) -> &'static [&'static str] {
+        &[
+            "jpg", "jpeg", "png", "gif", "bmp", "webp", "ico", "tiff", "tif", "qoi", "tga", "dds",
+        ]
+    }
+
+    fn thumbnail(&self, source: &Source, size: u32) -> Result<DynamicImage, AppError> {
+        // TGA has no signature to go by
+        let mut reader = source.image_reader()?;
+        if let Ok(format) = ImageFormat::from_path(source.path()) {
+            reader.set_format(format);
+        }
+        thumbnail(reader, size)
+    }
+}
+
+/// AVIF, decoded with dav1d; only when built with the avif feature, since it
+/// needs the library. Without it this says why there's no thumbnail, unless
+/// the Windows Shell has one.
+pub struct Avif;
+
+impl Thumbnailer for Avif {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["avif"]
+    }
+
+    #[cfg(feature = "avif")]
+    fn thumbnail(&self, source: &Source, size: u32) -> Result<DynamicImage, AppError> {
+        thumbnail(source.image_reader()?, size)
+    }
+
+    #[cfg(not(feature = "avif"))]
+    fn thumbnail(&self, source: &Source, _: u32) -> Result<DynamicImage, AppError> {
+        Err(AppError::unsupported(
+            source.path(),
+            "This build can't decode AVIF; it needs the avif feature and dav1d",
+        ))
+    }
+}
+
+/// An image shrunk to fit in size × size (never enlarged), upright and in
+/// sRGB
+pub fn thumbnail<R: BufRead + Seek>(
+    reader: ImageReader<R>,
+    size: u32,
+) -> Result<DynamicImage, AppError> {
+    decode(reader, size, None)
+}
+
+/// Like thumbnail, but turned by an orientation from elsewhere (the RAW file
+/// a preview came out of) instead of the image's own
+pub fn thumbnail_oriented<R: BufRead + Seek>(
+    reader: ImageReader<R>,
+    size: u32,
+    orientation: Orientation,
+) -> Result<DynamicImage, AppError> {
+    decode(reader, size, Some(orientation))
+}
+
+fn decode<R: BufRead + Seek>(
+    reader: ImageReader<R>,
+    size: u32,
+    orientation: Option<Orientation>,
+) -> Result<DynamicImage, AppError> {
+    let mut reader = reader.with_guessed_format().map_err(AppError::other)?;
+    let mut limits = Limits::default();
+    limits.max_alloc = Some(MAX_DECODE_BYTES);
+    reader.limits(limits);
+    let format = reader.format();
+    let mut decoder = reader.into_decoder()?;
+    // Broken metadata shouldn't cost the thumbnail
+    let orientation =
+        orientation.unwrap_or_else(|| decoder.orientation().unwrap_or(Orientation::NoTransforms));
+    let icc = decoder.icc_profile().ok().flatten();
+
+    let embedded = if format == Some(ImageFormat::Jpeg) {
+        let (width, height) = decoder.dimensions();
+        decoder
+            .exif_metadata()
+            .ok()
+            .flatten()
+            .and_then(|exif| exif_thumbnail(&exif, width, height, size))
+    } else {
+        None
+    };
+    let img = match embedded {
+        Some(img) => img,
+        None => DynamicImage::from_decoder(decoder)?,
+    };
+
+    // Cheaper on the small image than the large one
+    let mut thumbnail = shrink(img, size);
+    thumbnail.apply_orientation(orientation);
+    if let Some(icc) = icc {
+        thumbnail = to_srgb(thumbnail, &icc);
+    }
+    Ok(thumbnail)
+}
+
+/// The JPEG thumbnail in an EXIF block, if it's big enough and has the
+/// photo's shape
+fn exif_thumbnail(exif: &[u8], width: u32, height: u32, size: u32) -> Option<DynamicImage> {
+    let parsed = exif::Reader::new().read_raw(exif.to_vec()).ok()?;
+    let field = |tag| {
+        parsed
+            .get_field(tag, In::THUMBNAIL)
+            .and_then(|f| f.value.get_uint(0))
+            .map(|v| v as usize)
+    };
+    // The offset counts from the TIFF header, where the EXIF block starts
+    let offset = field(Tag::JPEGInterchangeFormat)?;
+    let length = field(Tag::JPEGInterchangeFormatLength)?;
+    let jpeg = exif.get(offset..offset.checked_add(length)?)?;
+
+    let img = image::load_from_memory_with_format(jpeg, ImageFormat::Jpeg).ok()?;
+    let (w, h) = (img.width() as f64, img.height() as f64);
+    let aspect_difference = (w * height as f64) / (h * width as f64) - 1.0;
+    let big_enough = img.width().max(img.height()) >= size;
+    (big_enough && aspect_difference.abs() <= ASPECT_TOLERANCE).then_some(img)
+}
+
+/// Scale down to fit in size × size; images with alpha are scaled
+/// premultiplied, so colour under transparent pixels doesn't bleed in
+pub fn shrink(img: DynamicImage, size: u32) -> DynamicImage {
+    if img.width() <= size && img.height() <= size {
+        return img;
+    }
+    if !img.color().has_alpha() {
+        return img.thumbnail(size, size);
+    }
+
+    let scale = size as f64 / img.width().max(img.height()) as f64;
+    let width = ((img.width() as f64 * scale).round() as u32).max(1);
+    let height = ((img.height() as f64 * scale).round() as u32).max(1);
+    let mut rgba = img.into_rgba8();
+    for pixel in rgba.pixels_mut() {
+        let [r, g, b, a] = pixel.0;
+        let premultiply = |c: u8| ((c as u32 * a as u32 + 127) / 255) as u8;
+        pixel.0 = [premultiply(r), premultiply(g), premultiply(b), a];
+    }
+    let mut small: RgbaImage = image::imageops::thumbnail(&rgba, width, height);
+    for pixel in small.pixels_mut() {
+        let [r, g, b, a] = pixel.0;
+        if a > 0 {
+            let unpremultiply = |c: u8| ((c as u32 * 255 + a as u32 / 2) / a as u32).min(255) as u8;
+            pixel.0 = [unpremultiply(r), unpremultiply(g), unpremultiply(b), a];
+        }
+    }
+    DynamicImage::ImageRgba8(small)
+}
+
+/// Convert from an embedded ICC profile to sRGB. Profiles that can't be read
+/// or applied (CMYK, which the decoder has already turned into RGB) leave the
+/// image as it is.
+pub fn to_srgb(img: DynamicImage, icc: &[u8]) -> DynamicImage {
+    let Ok(profile) = ColorProfile::new_from_slice(icc) else {
+        return img;
+    };
+    let srgb = ColorProfile::new_srgb();
+    let options = TransformOptions::default();
+    match profile.color_space {
+        DataColorSpace::Rgb => {
+            let Ok(transform) =
+                profile.create_transform_8bit(Layout::Rgba, &srgb, Layout::Rgba, options)
+            else {
+                return img;
+            };
+            let rgba = img.into_rgba8();
+            let mut converted = RgbaImage::new(rgba.width(), rgba.height());
+            match transform.transform(&rgba, &mut converted) {
+                Ok(()) => DynamicImage::ImageRgba8(converted),
+                Err(_) => DynamicImage::ImageRgba8(rgba),
+            }
+        }
+        DataColorSpace::Gray => {
+            let Ok(transform) =
+                profile.create_transform_8bit(Layout::GrayAlpha, &srgb, Layout::Rgba, options)
+            else {
+                return img;
+            };
+            let gray = img.to_luma_alpha8();
+            let mut converted = RgbaImage::new(gray.width(), gray.height());
+            match transform.transform(&gray, &mut converted) {
+                Ok(()) => DynamicImage::ImageRgba8(converted),
+                Err(_) => img,
+            }
+        }
+        _ => img,
+    }
+}
@@ -0,0 +1,221 @@
+// Thumbnails of zip-based documents, from the preview image or cover they
+// carry: OOXML (docProps/thumbnail.jpeg, or wherever the package relationships
+// point), OpenDocument (Thumbnails/thumbnail.png), EPUB (the cover named in
+// the package document) and CBZ (the first page).
+
+use super::{decode, ReadSeek, Source, Thumbnailer};
+use crate::archive;
+use crate::error::AppError;
+use image::{DynamicImage, ImageReader};
+use quick_xml::events::Event;
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::path::Path;
+use zip::ZipArchive;
+
+/// Extensions of the page images in a comic book archive
+const PAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "gif", "webp", "bmp"];
+
+/// Relationship type of an OOXML package's thumbnail
+const THUMBNAIL_RELATIONSHIP: &str =
+    "http://schemas.openxmlformats.org/package/2006/relationships/metadata/thumbnail";
+
+pub struct Ooxml;
+pub struct Odf;
+pub struct Epub;
+pub struct Cbz;
+
+impl Thumbnailer for Ooxml {
+    fn extensions(&self) -> &'static [&'static str] {
+        &[
+            "docx", "docm", "dotx", "xlsx", "xlsm", "xltx", "pptx", "pptm", "potx", "ppsx",
+        ]
+    }
+
+    fn thumbnail(&self, source: &Source, size: u32) -> Result<DynamicImage, AppError> {
+        let mut zip = open(source)?;
+        let target = read_entry(&mut zip, "_rels/.rels")
+            .and_then(|rels| {
+                elements(&String::from_utf8_lossy(&rels), b"Relationship")
+                    .into_iter()
+                    .find(|r| r.get("Type").map(String::as_str) == Some(THUMBNAIL_RELATIONSHIP))
+                    .and_then(|r| r.get("Target").cloned())
+            })
+            .map(|target| resolve("", &target))
+            .unwrap_or_else(|| "docProps/thumbnail.jpeg".to_string());
+        embedded_image(source, &mut zip, &target, size)
+    }
+}
+
+impl Thumbnailer for Odf {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["odt", "ods", "odp", "odg", "ott", "ots", "otp", "otg"]
+    }
+
+    fn thumbnail(&self, source: &Source, size: u32) -> Result<DynamicImage, AppError> {
+        let mut zip = open(source)?;
+        embedded_image(source, &mut zip, "Thumbnails/thumbnail.png", size)
+    }
+}
+
+impl Thumbnailer for Epub {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["epub"]
+    }
+
+    fn thumbnail(&self, source: &Source, size: u32) -> Result<DynamicImage, AppError> {
+        let mut zip = open(source)?;
+        let no_cover = || AppError::unsupported(source.path(), "No cover image");
+
+        // The container names the package document, which lists the cover
+        let container = read_entry(&mut zip, "META-INF/container.xml").ok_or_else(no_cover)?;
+        let package = elements(&String::from_utf8_lossy(&container), b"rootfile")
+            .into_iter()
+            .find_map(|r| r.get("full-path").cloned())
+            .ok_or_else(no_cover)?;
+        let opf = read_entry(&mut zip, &package).ok_or_else(no_cover)?;
+        let opf = String::from_utf8_lossy(&opf);
+        let items = elements(&opf, b"item");
+        let is_image = |item: &&HashMap<String, String>| {
+            item.get("media-type")
+                .is_some_and(|t| t.starts_with("image/"))
+        };
+
+        // EPUB 3 marks the cover; EPUB 2 names it in a meta; older books
+        // just call it "cover"
+        let cover_id = elements(&opf, b"meta")
+            .into_iter()
+            .find(|m| m.get("name").map(String::as_str) == Some("cover"))
+            .and_then(|m| m.get("content").cloned());
+        let cover = items
+            .iter()
+            .find(|i| {
+                i.get("properties")
+                    .is_some_and(|p| p.split_whitespace().any(|p| p == "cover-image"))
+            })
+            .or_else(|| {
+                items
+                    .iter()
+                    .filter(is_image)
+                    .find(|i| cover_id.is_some() && i.get("id") == cover_id.as_ref())
+            })
+            .or_else(|| {
+                items.iter().filter(is_image).find(|i| {
+                    let named = |key| {
+                        i.get(key)
+                            .is_some_and(|v| v.to_lowercase().contains("cover"))
+                    };
+                    named("id") || named("href")
+                })
+            })
+            .and_then(|i| i.get("href"))
+            .ok_or_else(no_cover)?;
+
+        let dir = package.rsplit_once('/').map_or("", |(dir, _)| dir);
+        embedded_image(source, &mut zip, &resolve(dir, cover), size)
+    }
+}
+
+impl Thumbnailer for Cbz {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["cbz"]
+    }
+
+    fn thumbnail(&self, source: &Source, size: u32) -> Result<DynamicImage, AppError> {
+        let mut zip = open(source)?;
+        // Pages are named to sort in reading order
+        let first_page = zip
+            .file_names()
+            .filter(|name| {
+                let path = Path::new(name);
+                let hidden = path.components().any(|c| {
+                    let c = c.as_os_str().to_string_lossy();
+                    c.starts_with('.') || c == "__MACOSX"
+                });
+                let extension = path
+                    .extension()
+                    .map(|e| e.to_string_lossy().to_lowercase())
+                    .unwrap_or_default();
+                !hidden && PAGE_EXTENSIONS.contains(&extension.as_str())
+            })
+            .min_by_key(|name| name.to_lowercase())
+            .map(str::to_string)
+            .ok_or_else(|| AppError::unsupported(source.path(), "No page images"))?;
+        embedded_image(source, &mut zip, &first_page, size)
+    }
+}
+
+type Zip<'a> = ZipArchive<Box<dyn ReadSeek + 'a>>;
+
+fn open(source: &Source) -> Result<Zip<'_>, AppError> {
+    ZipArchive::new(source.reader()?)
+        .map_err(|e| AppError::unsupported(source.path(), format!("Not a valid document: {}", e)))
+}
+
+/// A member's content, if it's there and not unreasonably large
+fn read_entry(zip: &mut Zip, name: &str) -> Option<Vec<u8>> {
+    let entry = zip.by_name(name).ok()?;
+    let mut data = Vec::new();
+    entry
+        .take(archive::MEMBER_READ_LIMIT)
+        .read_to_end(&mut data)
+        .ok()?;
+    Some(data)
+}
+
+fn embedded_image(
+    source: &Source,
+    zip: &mut Zip,
+    name: &str,
+    size: u32,
+) -> Result<DynamicImage, AppError> {
+    let data = read_entry(zip, name)
+        .ok_or_else(|| AppError::unsupported(source.path(), "No embedded thumbnail"))?;
+    decode::thumbnail(ImageReader::new(Cursor::new(data)), size)
+}
+
+/// The attributes of every element with this local name, in document order
+fn elements(xml: &str, name: &[u8]) -> Vec<HashMap<String, String>> {
+    let mut reader = quick_xml::Reader::from_str(xml);
+    let mut found = Vec::new();
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e) | Event::Empty(e)) if e.local_name().as_ref() == name => {
+                let attributes = e
+                    .attributes()
+                    .flatten()
+                    .filter_map(|a| {
+                        let key = String::from_utf8_lossy(a.key.local_name().as_ref()).to_string();
+                        Some((key, a.unescape_value().ok()?.to_string()))
+                    })
+                    .collect();
+                found.push(attributes);
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+    found
+}
+
+/// A member name from a URL-encoded reference relative to a folder in the zip
+fn resolve(dir: &str, href: &str) -> String {
+    let href = percent_encoding::percent_decode_str(href).decode_utf8_lossy();
+    let href = href.split('#').next().unwrap_or_default();
+    // Absolute references start at the root of the package
+    let (base, href) = match href.strip_prefix('/') {
+        Some(href) => ("", href),
+        None => (dir, href),
+    };
+    let mut parts: Vec<&str> = base.split('/').filter(|p| !p.is_empty()).collect();
+    for part in href.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
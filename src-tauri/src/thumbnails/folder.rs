@@ -0,0 +1,74 @@
+// Folder thumbnails: a mosaic of the first few pictures found in the folder,
+// in order of name. Each picture's thumbnail comes from thumbnail(), so it's
+// cached on disk like any other; the mosaic itself is only kept in memory,
+// and so is a folder having no pictures.
+
+use super::{Source, Thumbnailer};
+use crate::error::AppError;
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, ImageFormat, RgbaImage};
+
+/// Most pictures in a mosaic, and most files tried to find them
+const TILES: usize = 4;
+const CANDIDATES: usize = 12;
+
+/// Gap between tiles, as a share of the thumbnail size
+const GAP: f32 = 0.04;
+
+pub struct Folder;
+
+impl Thumbnailer for Folder {
+    fn extensions(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    fn folders(&self) -> bool {
+        true
+    }
+
+    fn thumbnail(&self, source: &Source, size: u32) -> Result<DynamicImage, AppError> {
+        // Pictures that fail are skipped, as long as a few candidates remain
+        let tiles: Vec<DynamicImage> = super::pictures_in(source.path(), CANDIDATES)?
+            .iter()
+            .filter_map(|path| super::thumbnail(&path.to_string_lossy(), size).ok())
+            .filter_map(|png| image::load_from_memory_with_format(&png, ImageFormat::Png).ok())
+            .take(TILES)
+            .collect();
+        if tiles.is_empty() {
+            return Err(AppError::unsupported(
+                source.path(),
+                "No pictures in folder",
+            ));
+        }
+
+        // One picture fills the square; two split it down the middle; a third
+        // and fourth split the right half, then the left
+        let gap = (size as f32 * GAP).round() as u32;
+        let half = size.saturating_sub(gap) / 2;
+        let far = size - half;
+        let cells = match tiles.len() {
+            1 => vec![(0, 0, size, size)],
+            2 => vec![(0, 0, half, size), (far, 0, half, size)],
+            3 => vec![
+                (0, 0, half, size),
+                (far, 0, half, half),
+                (far, far, half, half),
+            ],
+            _ => vec![
+                (0, 0, half, half),
+                (far, 0, half, half),
+                (0, far, half, half),
+                (far, far, half, half),
+            ],
+        };
+
+        let mut canvas = RgbaImage::new(size, size);
+        for (tile, (x, y, width, height)) in tiles.iter().zip(cells) {
+            let cropped = tile
+                .resize_to_fill(width.max(1), height.max(1), FilterType::Triangle)
+                .into_rgba8();
+            imageops::overlay(&mut canvas, &cropped, x as i64, y as i64);
+        }
+        Ok(DynamicImage::ImageRgba8(canvas))
+    }
+}
@@ -0,0 +1,77 @@
+// Font thumbnails: a sample set in the font, black on white. Symbol fonts
+// without Latin letters show the first characters they do have.
+
+use super::{Source, Thumbnailer};
+use crate::error::AppError;
+use ab_glyph::{point, Font, FontRef, GlyphId, PxScale, ScaleFont};
+use image::{DynamicImage, Rgba, RgbaImage};
+
+/// Text drawn in the font
+const SAMPLE: &str = "Aa";
+
+/// Share of the thumbnail the sample may take across and down
+const FILL: f32 = 0.8;
+
+pub struct Fonts;
+
+impl Thumbnailer for Fonts {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["ttf", "otf", "ttc", "otc"]
+    }
+
+    fn thumbnail(&self, source: &Source, size: u32) -> Result<DynamicImage, AppError> {
+        let data = source.bytes()?;
+        // The first face of a collection
+        let font = FontRef::try_from_slice_and_index(&data, 0)
+            .map_err(|_| AppError::unsupported(source.path(), "Not a readable font"))?;
+
+        let mut glyphs: Vec<GlyphId> = SAMPLE.chars().map(|c| font.glyph_id(c)).collect();
+        if glyphs.iter().any(|g| g.0 == 0) {
+            let mut fallback: Vec<(GlyphId, char)> = font
+                .codepoint_ids()
+                .filter(|&(g, c)| g.0 != 0 && !c.is_whitespace() && !c.is_control())
+                .collect();
+            fallback.sort_by_key(|&(_, c)| c);
+            glyphs = fallback.into_iter().take(2).map(|(g, _)| g).collect();
+        }
+        if glyphs.is_empty() {
+            return Err(AppError::unsupported(source.path(), "Font has no glyphs"));
+        }
+
+        // Size the sample to fit, from its measurements at 1px
+        let unit = font.as_scaled(PxScale::from(1.0));
+        let width: f32 = glyphs.iter().map(|&g| unit.h_advance(g)).sum();
+        let height = unit.ascent() - unit.descent();
+        if !(width > 0.0 && height > 0.0 && width.is_finite() && height.is_finite()) {
+            return Err(AppError::unsupported(
+                source.path(),
+                "Font has nothing to draw",
+            ));
+        }
+        let px = (size as f32 * FILL / width.max(height)).max(1.0);
+        let scaled = font.as_scaled(PxScale::from(px));
+
+        let mut canvas = RgbaImage::from_pixel(size, size, Rgba([255, 255, 255, 255]));
+        let mut x = (size as f32 - width * px) / 2.0;
+        let baseline = (size as f32 - height * px) / 2.0 + scaled.ascent();
+        for &id in &glyphs {
+            let glyph = id.with_scale_and_position(px, point(x, baseline));
+            x += scaled.h_advance(id);
+            let Some(outline) = font.outline_glyph(glyph) else {
+                continue;
+            };
+            let bounds = outline.px_bounds();
+            outline.draw(|gx, gy, coverage| {
+                let px = bounds.min.x as i64 + gx as i64;
+                let py = bounds.min.y as i64 + gy as i64;
+                if (0..size as i64).contains(&px) && (0..size as i64).contains(&py) {
+                    let shade = (255.0 * (1.0 - coverage.clamp(0.0, 1.0))).round() as u8;
+                    let pixel = canvas.get_pixel_mut(px as u32, py as u32);
+                    let shade = shade.min(pixel.0[0]);
+                    *pixel = Rgba([shade, shade, shade, 255]);
+                }
+            });
+        }
+        Ok(DynamicImage::ImageRgba8(canvas))
+    }
+}
@@ -0,0 +1,357 @@
+// Thumbnails for the grid view. Each kind of file has a Thumbnailer, listed
+// in THUMBNAILERS: images are decoded with the image crate, upright and in
+// sRGB (decode), camera RAW files show their embedded preview (raw) and
+// Photoshop files their composite (psd); SVGs, fonts, documents and text
+// files have portable thumbnailers; on Windows, videos and PDFs go through the
+// Shell, as do HEIC and, without the avif feature, AVIF. Elsewhere a build
+// without it makes no AVIF thumbnails, and says so. Folders show
+// a mosaic of the pictures in them (folder). Thumbnails of local files
+// are kept in the shared on-disk cache (cache), so they're only made once per
+// file version. Folder views queue their requests with the scheduler, then
+// load each finished thumbnail from its thumb:// URL.
+
+pub mod cache;
+mod decode;
+mod documents;
+mod folder;
+mod font;
+mod psd;
+mod raw;
+pub mod scheduler;
+#[cfg(windows)]
+mod shell;
+mod svg;
+mod text;
+
+use crate::archive;
+use crate::error::{AppError, PathContext};
+use image::{DynamicImage, ImageFormat, ImageReader};
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read, Seek};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Thumbnail size when the frontend doesn't give one
+pub const DEFAULT_SIZE: u32 = 96;
+
+/// Thumbnails kept in memory, so the thumb:// request that follows a
+/// scheduler result doesn't make it again; at most this many, and this size
+const RECENT_COUNT: usize = 256;
+const RECENT_BYTES: usize = 32 * 1024 * 1024;
+
+/// Recently made thumbnails, newest last. Folders that have none are kept
+/// too, so scrolling past them doesn't list them again until they change.
+static RECENT: Mutex<VecDeque<Recent>> = Mutex::new(VecDeque::new());
+
+struct Recent {
+    path: String,
+    size: u32,
+    modified: Option<SystemTime>, // Of the file, or of the archive holding it
+    png: Result<Vec<u8>, AppError>,
+}
+
+/// Makes thumbnails of some kinds of file
+trait Thumbnailer: Sync {
+    /// Lowercase extensions of the files it takes
+    fn extensions(&self) -> &'static [&'static str];
+
+    /// Whether it takes folders, which have no extension to go by
+    fn folders(&self) -> bool {
+        false
+    }
+
+    /// An image that fits in size × size; small images needn't be enlarged
+    fn thumbnail(&self, source: &Source, size: u32) -> Result<DynamicImage, AppError>;
+}
+
+/// Every thumbnailer, tried in this order for a file's extension until one
+/// succeeds
+static THUMBNAILERS: &[&dyn Thumbnailer] = &[
+    &decode::Images,
+    &decode::Avif,
+    &raw::Raw,
+    &psd::Psd,
+    &svg::Svg,
+    &font::Fonts,
+    // Explorer's own thumbnails, where it has them, ahead of the embedded ones
+    #[cfg(windows)]
+    &shell::Shell,
+    &documents::Ooxml,
+    &documents::Odf,
+    &documents::Epub,
+    &documents::Cbz,
+    &text::Text,
+    &folder::Folder,
+];
+
+/// Thumbnailers of the files that count as pictures: the ones a folder shows,
+/// and the viewer opens at full size
+static PICTURES: &[&dyn Thumbnailer] = &[
+    &decode::Images,
+    #[cfg(feature = "avif")]
+    &decode::Avif,
+    &raw::Raw,
+    &psd::Psd,
+];
+
+/// A file to make a thumbnail of: on disk, or read out of an archive
+pub struct Source {
+    path: PathBuf,
+    data: Option<Vec<u8>>, // Archive members
+}
+
+/// Readers that thumbnailers can seek in: files and archive members
+trait ReadSeek: Read + Seek {}
+impl<T: Read + Seek> ReadSeek for T {}
+
+impl Source {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The file on disk, for the Shell; None for archive members
+    #[cfg(windows)]
+    fn local_path(&self) -> Option<&Path> {
+        self.data.is_none().then_some(self.path.as_path())
+    }
+
+    /// The whole file
+    fn bytes(&self) -> Result<Cow<'_, [u8]>, AppError> {
+        match &self.data {
+            Some(data) => Ok(Cow::Borrowed(data)),
+            None => std::fs::read(&self.path).at(&self.path).map(Cow::Owned),
+        }
+    }
+
+    fn reader(&self) -> Result<Box<dyn ReadSeek + '_>, AppError> {
+        match &self.data {
+            Some(data) => Ok(Box::new(Cursor::new(data.as_slice()))),
+            None => Ok(Box::new(BufReader::new(
+                File::open(&self.path).at(&self.path)?,
+            ))),
+        }
+    }
+
+    fn image_reader(&self) -> Result<ImageReader<BufReader<Box<dyn ReadSeek + '_>>>, AppError> {
+        Ok(ImageReader::new(BufReader::new(self.reader()?)))
+    }
+}
+
+/// The thumbnailers for an extension, or for folders, in the order to try
+/// them
+fn thumbnailers(
+    extension: &str,
+    folder: bool,
+) -> impl Iterator<Item = &'static dyn Thumbnailer> + '_ {
+    THUMBNAILERS.iter().copied().filter(move |t| {
+        if folder {
+            t.folders()
+        } else {
+            t.extensions().contains(&extension)
+        }
+    })
+}
+
+/// Check if file supports any thumbnail generation
+fn is_thumbnail_supported(extension: &str) -> bool {
+    thumbnailers(&extension.to_lowercase(), false)
+        .next()
+        .is_some()
+}
+
+/// Extensions there's a thumbnailer for on this platform
+#[tauri::command]
+pub fn get_thumbnail_extensions() -> Vec<&'static str> {
+    let mut extensions: Vec<&str> = THUMBNAILERS
+        .iter()
+        .flat_map(|t| t.extensions().iter().copied())
+        .collect();
+    extensions.sort_unstable();
+    extensions.dedup();
+    extensions
+}
+
+/// A thumbnail as PNG, from memory or the disk cache when it's there
+pub fn thumbnail(path: &str, thumb_size: u32) -> Result<Vec<u8>, AppError> {
+    let modified = modified(Path::new(path));
+    {
+        let recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+        let hit = recent
+            .iter()
+            .find(|r| r.path == path && r.size == thumb_size && r.modified == modified);
+        if let Some(hit) = hit {
+            return hit.png.clone();
+        }
+    }
+
+    // Other failures may be passing, like a file that's still being written
+    let png = make_thumbnail(path, thumb_size);
+    if png.is_err() && !Path::new(path).is_dir() {
+        return png;
+    }
+    let len = |png: &Result<Vec<u8>, AppError>| png.as_ref().map_or(0, Vec::len);
+    let mut recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+    recent.retain(|r| r.path != path || r.size != thumb_size);
+    let mut bytes = recent.iter().map(|r| len(&r.png)).sum::<usize>() + len(&png);
+    while recent.len() >= RECENT_COUNT || (bytes > RECENT_BYTES && !recent.is_empty()) {
+        if let Some(oldest) = recent.pop_front() {
+            bytes -= len(&oldest.png);
+        }
+    }
+    recent.push_back(Recent {
+        path: path.to_string(),
+        size: thumb_size,
+        modified,
+        png: png.clone(),
+    });
+    png
+}
+
+/// Whether a file is a picture the viewer can open, going by its extension
+pub fn is_picture(path: &Path) -> bool {
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    PICTURES
+        .iter()
+        .any(|t| t.extensions().contains(&extension.as_str()))
+}
+
+/// The pictures in a folder, by name, leaving out hidden files. The listing
+/// stops at limit pictures, so those are the first found rather than the
+/// first by name.
+pub fn pictures_in(dir: &Path, limit: usize) -> Result<Vec<PathBuf>, AppError> {
+    let mut pictures: Vec<PathBuf> = std::fs::read_dir(dir)
+        .at(dir)?
+        .flatten()
+        .filter(|entry| {
+            let path = entry.path();
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            // The entry's type comes with the listing; only links need a stat
+            let is_file = entry
+                .file_type()
+                .is_ok_and(|t| t.is_file() || (t.is_symlink() && path.is_file()));
+            !hidden && is_picture(&path) && is_file
+        })
+        .map(|entry| entry.path())
+        .take(limit)
+        .collect();
+    pictures.sort_by_key(|path| path.file_name().map(|n| n.to_string_lossy().to_lowercase()));
+    Ok(pictures)
+}
+
+/// A picture at full size, upright and in sRGB, for the viewer; RAW files give
+/// their largest preview
+pub fn full_image(path: &Path) -> Result<DynamicImage, AppError> {
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let source = match archive::member_path(path) {
+        Some((archive_path, inner)) => Source {
+            path: path.to_path_buf(),
+            data: Some(archive::read_member(
+                &archive_path,
+                &inner,
+                archive::MEMBER_READ_LIMIT,
+            )?),
+        },
+        None if path.is_dir() => return Err(AppError::is_a_directory(path)),
+        None => Source {
+            path: path.to_path_buf(),
+            data: None,
+        },
+    };
+
+    // Asked for an unbounded size, they leave the picture as large as it is
+    let mut error = AppError::unsupported(path, "Not a picture");
+    for thumbnailer in PICTURES
+        .iter()
+        .filter(|t| t.extensions().contains(&extension.as_str()))
+    {
+        match thumbnailer.thumbnail(&source, u32::MAX) {
+            Ok(image) => return Ok(image),
+            Err(e) => error = e,
+        }
+    }
+    Err(error)
+}
+
+/// When a file, or the archive it's in, last changed
+pub fn modified(path: &Path) -> Option<SystemTime> {
+    let file = archive::member_path(path).map_or(path.to_path_buf(), |(archive, _)| archive);
+    std::fs::metadata(file).and_then(|m| m.modified()).ok()
+}
+
+fn make_thumbnail(path: &str, thumb_size: u32) -> Result<Vec<u8>, AppError> {
+    let file_path = Path::new(path);
+
+    // Folders aren't kept in the disk cache, which is for files; the pictures
+    // in them are
+    if file_path.is_dir() {
+        let source = Source {
+            path: file_path.to_path_buf(),
+            data: None,
+        };
+        return encode_png(&generate(&source, None, thumb_size)?);
+    }
+
+    // Get extension
+    let extension = file_path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if !is_thumbnail_supported(&extension) {
+        return Err(AppError::unsupported(file_path, "Unsupported file type"));
+    }
+
+    // Files inside archives are read into memory and not cached on disk
+    if let Some((archive_path, inner)) = archive::member_path(file_path) {
+        let data = archive::read_member(&archive_path, &inner, archive::MEMBER_READ_LIMIT)?;
+        let source = Source {
+            path: file_path.to_path_buf(),
+            data: Some(data),
+        };
+        return encode_png(&generate(&source, Some(&extension), thumb_size)?);
+    }
+
+    if !file_path.exists() {
+        return Err(AppError::not_found(file_path));
+    }
+
+    // A cached thumbnail is at least as large as asked for, in one of the
+    // spec's sizes; the view scales it down
+    if let Some(png) = cache::lookup(file_path, thumb_size) {
+        return Ok(png);
+    }
+    let source = Source {
+        path: file_path.to_path_buf(),
+        data: None,
+    };
+    let thumbnail = generate(&source, Some(&extension), cache::cache_size(thumb_size))?;
+    cache::store(file_path, thumb_size, &thumbnail)
+}
+
+/// Try each thumbnailer for the extension, or for folders when there's none;
+/// the last one's error if none manages
+fn generate(source: &Source, extension: Option<&str>, size: u32) -> Result<DynamicImage, AppError> {
+    let mut error = AppError::unsupported(source.path(), "Unsupported file type");
+    for thumbnailer in thumbnailers(extension.unwrap_or_default(), extension.is_none()) {
+        match thumbnailer.thumbnail(source, size) {
+            Ok(thumbnail) => return Ok(thumbnail),
+            Err(e) => error = e,
+        }
+    }
+    Err(error)
+}
+
+/// Encode a thumbnail as PNG in memory
+fn encode_png(thumbnail: &DynamicImage) -> Result<Vec<u8>, AppError> {
+    let mut buffer = Cursor::new(Vec::new());
+    thumbnail.write_to(&mut buffer, ImageFormat::Png)?;
+    Ok(buffer.into_inner())
+}
@@ -0,0 +1,320 @@
+// Photoshop thumbnails, from the composite image PSD and PSB files keep after
+// their layers (saved with "maximize compatibility"). 8- and 16-bit RGB,
+// grayscale, indexed and CMYK composites are read, raw or RLE-compressed; for
+// anything else, or a composite too large to decode, the JPEG thumbnail among
+// the image resources stands in. The embedded ICC profile applies.
+
+use super::{decode, ReadSeek, Source, Thumbnailer};
+use crate::error::AppError;
+use crate::metadata::image_info::photoshop_resource;
+use image::{DynamicImage, GrayImage, ImageReader, RgbImage};
+use std::io::{Cursor, Read, SeekFrom};
+
+/// Largest composite decoded, in pixels
+const MAX_PIXELS: u64 = 64_000_000;
+
+/// Largest colour mode or image resources section read
+const MAX_SECTION_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Image resources: the JPEG thumbnail, after a 28-byte header, and the ICC
+/// profile
+const THUMBNAIL_RESOURCE: u16 = 1036;
+const ICC_RESOURCE: u16 = 1039;
+
+pub struct Psd;
+
+/// What a Photoshop file has to make a thumbnail from
+struct Document {
+    composite: Option<DynamicImage>,
+    thumbnail: Option<Vec<u8>>, // JPEG
+    icc: Option<Vec<u8>>,
+}
+
+/// Header fields that describe the composite
+struct Header {
+    large: bool, // PSB: wider lengths
+    channels: usize,
+    width: u32,
+    height: u32,
+    depth: u16,
+    mode: u16,
+}
+
+impl Thumbnailer for Psd {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["psd", "psb"]
+    }
+
+    fn thumbnail(&self, source: &Source, size: u32) -> Result<DynamicImage, AppError> {
+        let document = read(&mut source.reader()?)
+            .ok_or_else(|| AppError::unsupported(source.path(), "Not a readable Photoshop file"))?;
+        if let Some(composite) = document.composite {
+            let thumbnail = decode::shrink(composite, size);
+            return Ok(match document.icc {
+                Some(icc) => decode::to_srgb(thumbnail, &icc),
+                None => thumbnail,
+            });
+        }
+        let jpeg = document
+            .thumbnail
+            .ok_or_else(|| AppError::unsupported(source.path(), "No readable composite image"))?;
+        decode::thumbnail(ImageReader::new(Cursor::new(jpeg)), size)
+    }
+}
+
+fn read(reader: &mut dyn ReadSeek) -> Option<Document> {
+    let mut head = [0; 26];
+    reader.read_exact(&mut head).ok()?;
+    if !head.starts_with(b"8BPS") {
+        return None;
+    }
+    let u16_at = |at: usize| u16::from_be_bytes([head[at], head[at + 1]]);
+    let u32_at =
+        |at: usize| u32::from_be_bytes([head[at], head[at + 1], head[at + 2], head[at + 3]]);
+    let header = Header {
+        large: match u16_at(4) {
+            1 => false,
+            2 => true,
+            _ => return None,
+        },
+        channels: u16_at(12) as usize,
+        height: u32_at(14),
+        width: u32_at(18),
+        depth: u16_at(22),
+        mode: u16_at(24),
+    };
+
+    let palette = section(reader)?;
+    let resources = section(reader)?;
+    // Layers and masks: only the composite after them is needed
+    let layers = if header.large {
+        u64::from_be_bytes(read_bytes(reader, 8)?.try_into().ok()?)
+    } else {
+        u32::from_be_bytes(read_bytes(reader, 4)?.try_into().ok()?) as u64
+    };
+    reader
+        .seek(SeekFrom::Current(i64::try_from(layers).ok()?))
+        .ok()?;
+
+    Some(Document {
+        composite: composite(reader, &header, &palette),
+        thumbnail: photoshop_resource(&resources, THUMBNAIL_RESOURCE)
+            .and_then(|r| r.get(28..))
+            .filter(|jpeg| jpeg.starts_with(&[0xff, 0xd8]))
+            .map(<[u8]>::to_vec),
+        icc: photoshop_resource(&resources, ICC_RESOURCE).map(<[u8]>::to_vec),
+    })
+}
+
+/// A section with a 4-byte length; empty if it's too large to bother with
+fn section(reader: &mut dyn ReadSeek) -> Option<Vec<u8>> {
+    let len = u32::from_be_bytes(read_bytes(reader, 4)?.try_into().ok()?) as u64;
+    if len > MAX_SECTION_SIZE {
+        reader.seek(SeekFrom::Current(len as i64)).ok()?;
+        return Some(Vec::new());
+    }
+    read_bytes(reader, len as usize)
+}
+
+fn read_bytes(reader: &mut dyn ReadSeek, len: usize) -> Option<Vec<u8>> {
+    let mut buf = Vec::new();
+    Read::take(&mut *reader, len as u64)
+        .read_to_end(&mut buf)
+        .ok()?;
+    (buf.len() == len).then_some(buf)
+}
+
+/// The merged image, in 8 bits per channel; None for colour modes and depths
+/// that aren't read, or if it's cut short
+fn composite(reader: &mut dyn ReadSeek, header: &Header, palette: &[u8]) -> Option<DynamicImage> {
+    // Grayscale, indexed, RGB, CMYK, duotone (stored as grayscale)
+    let colors = match header.mode {
+        1 | 2 | 8 => 1,
+        3 => 3,
+        4 => 4,
+        _ => return None,
+    };
+    let (width, height) = (header.width as usize, header.height as usize);
+    let pixels = header.width as u64 * header.height as u64;
+    if header.channels < colors
+        || !matches!(header.depth, 8 | 16)
+        || (header.mode == 2 && (header.depth != 8 || palette.len() < 768))
+        || pixels == 0
+        || pixels > MAX_PIXELS
+    {
+        return None;
+    }
+
+    // Channels are stored one after the other, colours first; rows are raw
+    // or PackBits, with the length of every row of every channel up front
+    let row_len = width * header.depth as usize / 8;
+    let compression = u16::from_be_bytes(read_bytes(reader, 2)?.try_into().ok()?);
+    let row_lengths: Option<Vec<usize>> = match compression {
+        0 => None,
+        1 => {
+            let width = if header.large { 4 } else { 2 };
+            let table = read_bytes(reader, header.channels * height * width)?;
+            Some(
+                table
+                    .chunks_exact(width)
+                    .map(|b| b.iter().fold(0usize, |len, &byte| len << 8 | byte as usize))
+                    .collect(),
+            )
+        }
+        _ => return None,
+    };
+
+    let mut planes = Vec::with_capacity(colors);
+    for channel in 0..colors {
+        let mut plane = Vec::with_capacity(width * height);
+        for row in 0..height {
+            let data = match &row_lengths {
+                None => read_bytes(reader, row_len)?,
+                Some(lengths) => unpack_bits(
+                    &read_bytes(reader, lengths[channel * height + row])?,
+                    row_len,
+                ),
+            };
+            // 16-bit samples are big endian; keep the high byte
+            match header.depth {
+                16 => plane.extend(data.iter().step_by(2)),
+                _ => plane.extend_from_slice(&data),
+            }
+        }
+        planes.push(plane);
+    }
+
+    let (w, h) = (header.width, header.height);
+    match header.mode {
+        1 | 8 => GrayImage::from_raw(w, h, planes.swap_remove(0)).map(DynamicImage::ImageLuma8),
+        2 => {
+            let rgb = planes[0]
+                .iter()
+                .flat_map(|&i| {
+                    let i = i as usize;
+                    [palette[i], palette[256 + i], palette[512 + i]]
+                })
+                .collect();
+            RgbImage::from_raw(w, h, rgb).map(DynamicImage::ImageRgb8)
+        }
+        3 => {
+            let rgb = (0..width * height)
+                .flat_map(|i| [planes[0][i], planes[1][i], planes[2][i]])
+                .collect();
+            RgbImage::from_raw(w, h, rgb).map(DynamicImage::ImageRgb8)
+        }
+        _ => {
+            // CMYK is stored inverted, 255 for no ink
+            let rgb = (0..width * height)
+                .flat_map(|i| {
+                    let k = planes[3][i] as u32;
+                    [0, 1, 2].map(|c| (planes[c][i] as u32 * k / 255) as u8)
+                })
+                .collect();
+            RgbImage::from_raw(w, h, rgb).map(DynamicImage::ImageRgb8)
+        }
+    }
+}
+
+/// Expand a PackBits-compressed row to len bytes
+fn unpack_bits(data: &[u8], len: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(len);
+    let mut i = 0;
+    while i < data.len() && out.len() < len {
+        let n = data[i] as i8;
+        i += 1;
+        if n >= 0 {
+            let end = (i + n as usize + 1).min(data.len());
+            out.extend_from_slice(&data[i..end]);
+            i = end;
+        } else if n != -128 {
+            let Some(&byte) = data.get(i) else {
+                break;
+            };
+            out.extend(std::iter::repeat_n(byte, (1 - n as isize) as usize));
+            i += 1;
+        }
+    }
+    out.resize(len, 0);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 2x2 8-bit RGB file with no colour mode data, resources or layers,
+    /// followed by the composite
+    fn psd(compression: u16, composite: &[u8]) -> Vec<u8> {
+        let mut file = b"8BPS".to_vec();
+        file.extend(1u16.to_be_bytes());
+        file.extend([0; 6]);
+        file.extend(3u16.to_be_bytes());
+        file.extend(2u32.to_be_bytes());
+        file.extend(2u32.to_be_bytes());
+        file.extend(8u16.to_be_bytes());
+        file.extend(3u16.to_be_bytes());
+        file.extend([0; 12]);
+        file.extend(compression.to_be_bytes());
+        file.extend(composite);
+        file
+    }
+
+    #[test]
+    fn packbits_row() {
+        // Literal run of 3, a repeat of 4, a no-op, then a literal of 1
+        let row = [0x02, 1, 2, 3, 0xfd, 9, 0x80, 0x00, 7];
+        assert_eq!(unpack_bits(&row, 8), [1, 2, 3, 9, 9, 9, 9, 7]);
+        // Runs past the row are cut off, short rows padded
+        assert_eq!(unpack_bits(&[0xfd, 9], 2), [9, 9]);
+        assert_eq!(unpack_bits(&[0x05, 1, 2], 4), [1, 2, 0, 0]);
+        assert_eq!(unpack_bits(&[0xfd], 2), [0, 0]);
+    }
+
+    #[test]
+    fn rle_row_table() {
+        // Every row of every channel is 2 bytes long: a repeat of 2, or a literal of 1
+        let rows: [[u8; 2]; 6] = [
+            [0xff, 10],
+            [0x00, 11],
+            [0xff, 20],
+            [0xff, 21],
+            [0xff, 30],
+            [0xff, 31],
+        ];
+        let mut composite: Vec<u8> = [2u16; 6].iter().flat_map(|l| l.to_be_bytes()).collect();
+        composite.extend(rows.concat());
+        let image = read(&mut Cursor::new(psd(1, &composite)))
+            .and_then(|document| document.composite)
+            .unwrap()
+            .to_rgb8();
+        assert_eq!(image.get_pixel(0, 0).0, [10, 20, 30]);
+        assert_eq!(image.get_pixel(1, 0).0, [10, 20, 30]);
+        // The literal row only has one byte for two pixels
+        assert_eq!(image.get_pixel(0, 1).0, [11, 21, 31]);
+        assert_eq!(image.get_pixel(1, 1).0, [0, 21, 31]);
+
+        // Cut off halfway through the rows
+        composite.truncate(12 + 6);
+        let document = read(&mut Cursor::new(psd(1, &composite))).unwrap();
+        assert!(document.composite.is_none());
+    }
+
+    #[test]
+    fn raw_composite() {
+        let composite: Vec<u8> = (1..=12).collect();
+        let image = read(&mut Cursor::new(psd(0, &composite)))
+            .and_then(|document| document.composite)
+            .unwrap()
+            .to_rgb8();
+        assert_eq!(image.get_pixel(1, 1).0, [4, 8, 12]);
+    }
+
+    #[test]
+    fn not_photoshop() {
+        assert!(read(&mut Cursor::new(b"8BPS".to_vec())).is_none());
+        let mut file = psd(0, &[]);
+        file[5] = 3; // Version
+        assert!(read(&mut Cursor::new(file)).is_none());
+    }
+}
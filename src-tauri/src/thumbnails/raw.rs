@@ -0,0 +1,358 @@
+// Camera RAW thumbnails, from the JPEG previews cameras store next to the
+// sensor data. Most RAW formats are TIFF underneath (DNG, CR2, NEF, ARW, ORF,
+// RW2, PEF, SRW...): the previews are JPEG images in the IFD chain or its
+// SubIFDs, plus the small EXIF-style thumbnail. The smallest preview at least
+// as large as the thumbnail is decoded. Fujifilm's RAF names its preview in
+// its own header. TIFF-based previews are turned by the RAW file's
+// orientation, since they rarely carry their own.
+
+use super::{decode, ReadSeek, Source, Thumbnailer};
+use crate::error::AppError;
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageFormat, ImageReader};
+use std::io::{Cursor, Read, SeekFrom};
+
+/// Most IFDs read, in case their offsets loop
+const MAX_IFDS: usize = 32;
+
+/// Most values read for one tag
+const MAX_VALUES: u32 = 64;
+
+/// Bytes read from the start of a preview to find its dimensions
+const PREVIEW_HEAD_SIZE: u64 = 256 * 1024;
+
+/// Largest preview read in full
+const MAX_PREVIEW_SIZE: u64 = 64 * 1024 * 1024;
+
+// TIFF tags
+const COMPRESSION: u16 = 0x103;
+const PHOTOMETRIC: u16 = 0x106;
+const STRIP_OFFSETS: u16 = 0x111;
+const ORIENTATION: u16 = 0x112;
+const STRIP_BYTE_COUNTS: u16 = 0x117;
+const SUB_IFDS: u16 = 0x14a;
+const JPEG_OFFSET: u16 = 0x201;
+const JPEG_LENGTH: u16 = 0x202;
+const PANASONIC_PREVIEW: u16 = 0x2e; // RW2's JpgFromRaw: the JPEG itself
+
+/// Compression values of JPEG strips, and photometric values of sensor data,
+/// which can be lossless JPEG but isn't a picture yet
+const JPEG_COMPRESSION: &[u32] = &[6, 7];
+const SENSOR_PHOTOMETRIC: &[u32] = &[32803, 34892];
+
+pub struct Raw;
+
+/// Where a JPEG preview is in the file
+#[derive(Clone, Copy, PartialEq)]
+struct Preview {
+    offset: u64,
+    len: u64,
+}
+
+impl Thumbnailer for Raw {
+    fn extensions(&self) -> &'static [&'static str] {
+        &[
+            "dng", "cr2", "nef", "nrw", "arw", "srf", "sr2", "orf", "rw2", "pef", "srw", "3fr",
+            "erf", "mef", "mos", "iiq", "raf",
+        ]
+    }
+
+    fn thumbnail(&self, source: &Source, size: u32) -> Result<DynamicImage, AppError> {
+        let mut reader = source.reader()?;
+        let (previews, orientation) = previews(&mut reader)
+            .ok_or_else(|| AppError::unsupported(source.path(), "Not a readable RAW file"))?;
+
+        // Large enough ones from the smallest up, then the rest from the
+        // largest down
+        let mut sized: Vec<(u32, Preview)> = previews
+            .into_iter()
+            .filter_map(|p| Some((longest_side(&mut reader, p)?, p)))
+            .collect();
+        sized.sort_by_key(|&(side, _)| {
+            if side >= size {
+                (false, side)
+            } else {
+                (true, u32::MAX - side)
+            }
+        });
+        sized.dedup_by_key(|(_, p)| *p);
+
+        let mut error = AppError::unsupported(source.path(), "No embedded preview");
+        for (_, preview) in sized {
+            let Some(data) = read_at(&mut reader, preview.offset, preview.len) else {
+                continue;
+            };
+            let jpeg = ImageReader::with_format(Cursor::new(data), ImageFormat::Jpeg);
+            let thumbnail = match orientation {
+                Some(orientation) => decode::thumbnail_oriented(jpeg, size, orientation),
+                None => decode::thumbnail(jpeg, size),
+            };
+            match thumbnail {
+                Ok(thumbnail) => return Ok(thumbnail),
+                Err(e) => error = e,
+            }
+        }
+        Err(error)
+    }
+}
+
+/// The JPEG previews in a RAW file, and the orientation to show them in;
+/// None if it's neither TIFF-based nor RAF
+fn previews(reader: &mut dyn ReadSeek) -> Option<(Vec<Preview>, Option<Orientation>)> {
+    let head = read_at(reader, 0, 92)?;
+    if head.starts_with(b"FUJIFILMCCD-RAW") {
+        let field = |at: usize| Some(u32::from_be_bytes(head.get(at..at + 4)?.try_into().ok()?));
+        let preview = Preview {
+            offset: field(84)? as u64,
+            len: field(88)? as u64,
+        };
+        return Some((vec![preview], None));
+    }
+
+    let tiff = Tiff {
+        big_endian: match head.get(0..2)? {
+            b"II" => false,
+            b"MM" => true,
+            _ => return None,
+        },
+    };
+    // Olympus and Panasonic change the magic number
+    if ![42, 0x4f52, 0x5352, 0x55].contains(&tiff.u16(head.get(2..)?)?) {
+        return None;
+    }
+
+    let mut previews = Vec::new();
+    let mut orientation = None;
+    let mut pending = vec![tiff.u32(head.get(4..)?)? as u64];
+    let mut seen = Vec::new();
+    while let Some(offset) = pending.pop() {
+        if offset == 0 || seen.contains(&offset) || seen.len() == MAX_IFDS {
+            continue;
+        }
+        seen.push(offset);
+        let Some(ifd) = tiff.ifd(reader, offset) else {
+            continue;
+        };
+        pending.push(ifd.next);
+        pending.extend(ifd.values(SUB_IFDS).iter().map(|&o| o as u64));
+
+        // The first IFD describes the picture as a whole
+        if seen.len() == 1 {
+            orientation = ifd
+                .value(ORIENTATION)
+                .and_then(|o| Orientation::from_exif(o as u8));
+        }
+        if let (Some(offset), Some(len)) = (ifd.value(JPEG_OFFSET), ifd.value(JPEG_LENGTH)) {
+            previews.push(Preview {
+                offset: offset as u64,
+                len: len as u64,
+            });
+        }
+        previews.extend(ifd.embedded);
+
+        let compression = ifd.value(COMPRESSION).unwrap_or_default();
+        let photometric = ifd.value(PHOTOMETRIC).unwrap_or_default();
+        if let ([offset], [len]) = (ifd.values(STRIP_OFFSETS), ifd.values(STRIP_BYTE_COUNTS)) {
+            if JPEG_COMPRESSION.contains(&compression) && !SENSOR_PHOTOMETRIC.contains(&photometric)
+            {
+                previews.push(Preview {
+                    offset: *offset as u64,
+                    len: *len as u64,
+                });
+            }
+        }
+    }
+    Some((previews, orientation))
+}
+
+/// The longer side of a preview, from its JPEG header
+fn longest_side(reader: &mut dyn ReadSeek, preview: Preview) -> Option<u32> {
+    let head = read_at(reader, preview.offset, preview.len.min(PREVIEW_HEAD_SIZE))?;
+    if !head.starts_with(&[0xff, 0xd8]) {
+        return None;
+    }
+    let (width, height) = ImageReader::with_format(Cursor::new(head), ImageFormat::Jpeg)
+        .into_dimensions()
+        .ok()?;
+    Some(width.max(height))
+}
+
+fn read_at(reader: &mut dyn ReadSeek, offset: u64, len: u64) -> Option<Vec<u8>> {
+    let len = len.min(MAX_PREVIEW_SIZE);
+    let mut buf = Vec::new();
+    reader.seek(SeekFrom::Start(offset)).ok()?;
+    Read::take(&mut *reader, len).read_to_end(&mut buf).ok()?;
+    Some(buf)
+}
+
+/// Byte order of a TIFF file
+struct Tiff {
+    big_endian: bool,
+}
+
+/// The tags of an IFD that matter here, and the offset of the next one
+struct Ifd {
+    values: Vec<(u16, Vec<u32>)>,
+    embedded: Option<Preview>, // RW2's preview
+    next: u64,
+}
+
+impl Ifd {
+    fn values(&self, tag: u16) -> &[u32] {
+        self.values
+            .iter()
+            .find(|(t, _)| *t == tag)
+            .map_or(&[], |(_, values)| values)
+    }
+
+    fn value(&self, tag: u16) -> Option<u32> {
+        self.values(tag).first().copied()
+    }
+}
+
+impl Tiff {
+    /// The number at the start of b; None if it's too short, as reads past
+    /// the end of a truncated file are
+    fn u16(&self, b: &[u8]) -> Option<u16> {
+        let b = b.get(..2)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u16::from_be_bytes(b)
+        } else {
+            u16::from_le_bytes(b)
+        })
+    }
+
+    fn u32(&self, b: &[u8]) -> Option<u32> {
+        let b = b.get(..4)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        })
+    }
+
+    fn ifd(&self, reader: &mut dyn ReadSeek, offset: u64) -> Option<Ifd> {
+        let count = self.u16(&read_at(reader, offset, 2)?)? as u64;
+        let data = read_at(reader, offset + 2, count * 12 + 4)?;
+        if data.len() as u64 != count * 12 + 4 {
+            return None;
+        }
+
+        let mut ifd = Ifd {
+            values: Vec::new(),
+            embedded: None,
+            next: self.u32(&data[count as usize * 12..])? as u64,
+        };
+        for entry in data[..count as usize * 12].chunks_exact(12) {
+            let tag = self.u16(&entry[0..2])?;
+            let kind = self.u16(&entry[2..4])?;
+            let count = self.u32(&entry[4..8])?;
+            let value = &entry[8..12];
+            match tag {
+                PANASONIC_PREVIEW => {
+                    ifd.embedded = Some(Preview {
+                        offset: self.u32(value)? as u64,
+                        len: count as u64,
+                    })
+                }
+                COMPRESSION | PHOTOMETRIC | STRIP_OFFSETS | ORIENTATION | STRIP_BYTE_COUNTS
+                | SUB_IFDS | JPEG_OFFSET | JPEG_LENGTH => {
+                    // Shorts, longs and IFD offsets; larger than 4 bytes, they're
+                    // stored elsewhere
+                    let width = match kind {
+                        3 => 2,
+                        4 | 13 => 4,
+                        _ => continue,
+                    };
+                    let count = count.min(MAX_VALUES);
+                    let len = (count * width) as u64;
+                    let bytes = if len <= 4 {
+                        value[..len as usize].to_vec()
+                    } else {
+                        match read_at(reader, self.u32(value)? as u64, len) {
+                            Some(bytes) => bytes,
+                            None => continue,
+                        }
+                    };
+                    let values = bytes
+                        .chunks_exact(width as usize)
+                        .filter_map(|b| {
+                            if width == 2 {
+                                self.u16(b).map(u32::from)
+                            } else {
+                                self.u32(b)
+                            }
+                        })
+                        .collect();
+                    ifd.values.push((tag, values));
+                }
+                _ => {}
+            }
+        }
+        Some(ifd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A little-endian TIFF header pointing at the first IFD
+    fn header(ifd: u32) -> Vec<u8> {
+        let mut tiff = b"II*\0".to_vec();
+        tiff.extend(ifd.to_le_bytes());
+        tiff
+    }
+
+    fn entry(tag: u16, kind: u16, count: u32, value: u32) -> Vec<u8> {
+        [
+            &tag.to_le_bytes()[..],
+            &kind.to_le_bytes(),
+            &count.to_le_bytes(),
+            &value.to_le_bytes(),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn ifd_past_end_of_file() {
+        let (previews, orientation) = previews(&mut Cursor::new(header(0x1000))).unwrap();
+        assert!(previews.is_empty());
+        assert!(orientation.is_none());
+    }
+
+    #[test]
+    fn ifd_cut_short() {
+        // Says it has 5 entries, then ends
+        let mut tiff = header(8);
+        tiff.extend(5u16.to_le_bytes());
+        tiff.extend(entry(ORIENTATION, 3, 1, 6));
+        let (previews, _) = previews(&mut Cursor::new(tiff)).unwrap();
+        assert!(previews.is_empty());
+    }
+
+    #[test]
+    fn next_ifd_past_end_of_file() {
+        let mut tiff = header(8);
+        tiff.extend(3u16.to_le_bytes());
+        tiff.extend(entry(ORIENTATION, 3, 1, 6));
+        tiff.extend(entry(JPEG_OFFSET, 4, 1, 0x200));
+        tiff.extend(entry(JPEG_LENGTH, 4, 1, 0x80));
+        tiff.extend(0xffff_fff0u32.to_le_bytes());
+        let (previews, orientation) = previews(&mut Cursor::new(tiff)).unwrap();
+        assert!(
+            previews
+                == [Preview {
+                    offset: 0x200,
+                    len: 0x80
+                }]
+        );
+        assert_eq!(orientation, Some(Orientation::Rotate90));
+    }
+
+    #[test]
+    fn not_tiff() {
+        assert!(previews(&mut Cursor::new(b"GIF89a".to_vec())).is_none());
+        assert!(previews(&mut Cursor::new(b"II".to_vec())).is_none());
+    }
+}
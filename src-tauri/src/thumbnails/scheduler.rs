@@ -0,0 +1,200 @@
+// Thumbnail scheduler for folder views. A fixed pool of workers takes queued
+// requests by priority: visible items first, then those near the viewport,
+// then the rest. Requests still queued can be cancelled when their items
+// scroll away. Results go out as "thumbnail-batch" events, a few at a time,
+// so a fast scroll doesn't turn into thousands of separate events; they only
+// say a thumbnail is ready, and the view loads it from its thumb:// URL.
+
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Most results sent in one event
+const MAX_BATCH: usize = 32;
+
+/// Longest a result waits for others to share its event
+const BATCH_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Most workers, whatever the core count; decoding is memory-bound too
+const MAX_WORKERS: usize = 4;
+
+/// Most urgent first
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    Visible,
+    Near, // Just outside the viewport
+    Background,
+}
+
+#[derive(Deserialize)]
+pub struct ThumbnailRequest {
+    pub path: String,
+    pub size: Option<u32>,
+    pub priority: Priority,
+}
+
+/// One finished request, in a "thumbnail-batch" event
+#[derive(Serialize, Clone)]
+pub struct ThumbnailResult {
+    pub path: String,
+    pub size: u32,
+    pub error: Option<AppError>, // None when thumb:// has it ready
+}
+
+/// Path and size
+type Key = (String, u32);
+
+/// Queued requests, in priority then arrival order
+#[derive(Default)]
+struct Queue {
+    order: BTreeMap<(Priority, u64), Key>,
+    queued: HashMap<Key, (Priority, u64)>,
+    next_seq: u64,
+}
+
+#[derive(Default)]
+struct Shared {
+    queue: Mutex<Queue>,
+    ready: Condvar,
+}
+
+/// The queue and its workers, which start with the first request
+#[derive(Default)]
+pub struct ThumbnailScheduler {
+    shared: Arc<Shared>,
+    started: OnceLock<()>,
+}
+
+impl Queue {
+    /// Add a request, or move a queued one to its new priority
+    fn push(&mut self, key: Key, priority: Priority) {
+        if let Some(&(queued, seq)) = self.queued.get(&key) {
+            if queued == priority {
+                return;
+            }
+            self.order.remove(&(queued, seq));
+        }
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.order.insert((priority, seq), key.clone());
+        self.queued.insert(key, (priority, seq));
+    }
+
+    fn pop(&mut self) -> Option<Key> {
+        let (_, key) = self.order.pop_first()?;
+        self.queued.remove(&key);
+        Some(key)
+    }
+
+    /// Drop the requests for some paths, at any size; all of them without paths
+    fn cancel(&mut self, paths: Option<&[String]>) -> usize {
+        let before = self.order.len();
+        match paths {
+            Some(paths) => {
+                let paths: HashSet<&String> = paths.iter().collect();
+                let order = &mut self.order;
+                self.queued.retain(|(path, _), position| {
+                    let cancel = paths.contains(path);
+                    if cancel {
+                        order.remove(position);
+                    }
+                    !cancel
+                });
+            }
+            None => {
+                self.order.clear();
+                self.queued.clear();
+            }
+        }
+        before - self.order.len()
+    }
+}
+
+impl ThumbnailScheduler {
+    fn start(&self, app: &AppHandle) {
+        self.started.get_or_init(|| {
+            let (sender, receiver) = mpsc::channel();
+            let workers = thread::available_parallelism()
+                .map_or(2, |n| n.get())
+                .saturating_sub(1) // Leave a core for the UI
+                .clamp(1, MAX_WORKERS);
+            for _ in 0..workers {
+                let shared = self.shared.clone();
+                let sender = sender.clone();
+                thread::spawn(move || work(&shared, &sender));
+            }
+            let app = app.clone();
+            thread::spawn(move || deliver(&app, receiver));
+        });
+    }
+
+    fn queue(&self) -> std::sync::MutexGuard<'_, Queue> {
+        self.shared.queue.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Queue thumbnails; each arrives in a "thumbnail-batch" event. Requesting a
+/// queued thumbnail again changes its priority.
+#[tauri::command]
+pub fn request_thumbnails(app: AppHandle, requests: Vec<ThumbnailRequest>) {
+    let scheduler = app.state::<ThumbnailScheduler>();
+    scheduler.start(&app);
+
+    let mut queue = scheduler.queue();
+    for request in requests {
+        let size = request.size.unwrap_or(super::DEFAULT_SIZE);
+        queue.push((request.path, size), request.priority);
+    }
+    scheduler.shared.ready.notify_all();
+}
+
+/// Drop queued requests for these paths, or all of them; thumbnails already
+/// being made still arrive. Returns how many were dropped.
+#[tauri::command]
+pub fn cancel_thumbnails(app: AppHandle, paths: Option<Vec<String>>) -> usize {
+    let scheduler = app.state::<ThumbnailScheduler>();
+    let mut queue = scheduler.queue();
+    queue.cancel(paths.as_deref())
+}
+
+fn work(shared: &Shared, results: &Sender<ThumbnailResult>) {
+    loop {
+        let (path, size) = {
+            let mut queue = shared.queue.lock().unwrap_or_else(|e| e.into_inner());
+            loop {
+                if let Some(key) = queue.pop() {
+                    break key;
+                }
+                queue = shared.ready.wait(queue).unwrap_or_else(|e| e.into_inner());
+            }
+        };
+
+        let error = AppError::catch(|| super::thumbnail(&path, size)).err();
+        let result = ThumbnailResult { path, size, error };
+        if results.send(result).is_err() {
+            return;
+        }
+    }
+}
+
+/// Gather results into batches and emit them
+fn deliver(app: &AppHandle, results: Receiver<ThumbnailResult>) {
+    while let Ok(first) = results.recv() {
+        let mut batch = vec![first];
+        let deadline = Instant::now() + BATCH_INTERVAL;
+        while batch.len() < MAX_BATCH {
+            let left = deadline.saturating_duration_since(Instant::now());
+            match results.recv_timeout(left) {
+                Ok(result) => batch.push(result),
+                Err(_) => break,
+            }
+        }
+        let _ = app.emit("thumbnail-batch", batch);
+    }
+}
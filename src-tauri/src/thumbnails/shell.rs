@@ -0,0 +1,135 @@
+// Thumbnails from the Windows Shell, the ones Explorer shows: videos, PDFs,
+// Office documents, and HEIF and AVIF images, through whatever thumbnail
+// handlers and codecs are installed. RAW files the portable thumbnailer can't
+// read come here too.
+
+use super::{Source, Thumbnailer};
+use crate::error::AppError;
+use image::DynamicImage;
+
+pub struct Shell;
+
+impl Thumbnailer for Shell {
+    fn extensions(&self) -> &'static [&'static str] {
+        &[
+            "mp4", "mkv", "avi", "mov", "wmv", "flv", "webm", "m4v", "mpeg", "mpg", "pdf", "doc",
+            "docx", "xls", "xlsx", "ppt", "pptx", "heic", "heif", "avif", "dng", "cr2", "cr3",
+            "nef", "nrw", "arw", "orf", "rw2", "pef", "srw", "raf",
+        ]
+    }
+
+    fn thumbnail(&self, source: &Source, size: u32) -> Result<DynamicImage, AppError> {
+        // The Shell only reads files on disk
+        let path = source
+            .local_path()
+            .ok_or_else(|| AppError::unsupported(source.path(), "Unsupported file type"))?;
+        let img = generate_shell_thumbnail(&path.to_string_lossy(), size)?;
+        Ok(if img.width() > size || img.height() > size {
+            img.thumbnail(size, size)
+        } else {
+            img
+        })
+    }
+}
+
+/// Generate a thumbnail using Windows Shell API (IShellItemImageFactory)
+/// This uses the same thumbnail system as Windows Explorer
+fn generate_shell_thumbnail(path: &str, size: u32) -> Result<image::DynamicImage, AppError> {
+    use windows::core::PCWSTR;
+    use windows::Win32::Graphics::Gdi::{
+        CreateCompatibleDC, DeleteDC, DeleteObject, GetDIBits, SelectObject, BITMAPINFO,
+        BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS,
+    };
+    use windows::Win32::System::Com::{CoInitializeEx, CoUninitialize, COINIT_APARTMENTTHREADED};
+    use windows::Win32::UI::Shell::{
+        IShellItemImageFactory, SHCreateItemFromParsingName, SIIGBF_THUMBNAILONLY,
+    };
+
+    unsafe {
+        // Initialize COM
+        let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+
+        // Convert path to wide string
+        let wide_path: Vec<u16> = path.encode_utf16().chain(std::iter::once(0)).collect();
+
+        // Create shell item
+        let shell_item: IShellItemImageFactory =
+            SHCreateItemFromParsingName(PCWSTR::from_raw(wide_path.as_ptr()), None)
+                .map_err(|e| AppError::other(format!("Failed to create shell item: {:?}", e)))?;
+
+        // Request thumbnail
+        let thumb_size = windows::Win32::Foundation::SIZE {
+            cx: size as i32,
+            cy: size as i32,
+        };
+
+        let hbitmap = shell_item
+            .GetImage(thumb_size, SIIGBF_THUMBNAILONLY)
+            .map_err(|e| AppError::other(format!("Failed to get thumbnail: {:?}", e)))?;
+
+        // Convert HBITMAP to PNG
+        let hdc = CreateCompatibleDC(None);
+        if hdc.is_invalid() {
+            DeleteObject(hbitmap);
+            CoUninitialize();
+            return Err(AppError::other("Failed to create DC"));
+        }
+
+        let old_bitmap = SelectObject(hdc, hbitmap);
+
+        // Get bitmap info
+        let mut bmi = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: size as i32,
+                biHeight: -(size as i32), // Negative for top-down
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB.0,
+                biSizeImage: 0,
+                biXPelsPerMeter: 0,
+                biYPelsPerMeter: 0,
+                biClrUsed: 0,
+                biClrImportant: 0,
+            },
+            bmiColors: [Default::default()],
+        };
+
+        // Allocate buffer for pixel data
+        let mut pixels: Vec<u8> = vec![0u8; (size * size * 4) as usize];
+
+        let result = GetDIBits(
+            hdc,
+            hbitmap,
+            0,
+            size,
+            Some(pixels.as_mut_ptr() as *mut _),
+            &mut bmi,
+            DIB_RGB_COLORS,
+        );
+
+        // Cleanup GDI objects
+        SelectObject(hdc, old_bitmap);
+        DeleteDC(hdc);
+        DeleteObject(hbitmap);
+        CoUninitialize();
+
+        if result == 0 {
+            return Err(AppError::other("Failed to get bitmap bits"));
+        }
+
+        // Convert BGRA to RGBA
+        for chunk in pixels.chunks_exact_mut(4) {
+            chunk.swap(0, 2); // Swap B and R
+        }
+
+        // Create image from raw pixels
+        let actual_width = bmi.bmiHeader.biWidth.unsigned_abs();
+        let actual_height = bmi.bmiHeader.biHeight.unsigned_abs();
+
+        let img_buffer = image::RgbaImage::from_raw(actual_width, actual_height, pixels)
+            .ok_or_else(|| AppError::other("Failed to create image buffer"))?;
+
+        Ok(image::DynamicImage::ImageRgba8(img_buffer))
+    }
+}
@@ -0,0 +1,116 @@
+// SVG thumbnails, rasterized with resvg. Text is set in the system fonts,
+// which are looked up once; the generic families (serif, sans-serif,
+// monospace) go to whichever common font is installed, since resvg's defaults
+// are Windows fonts. Images the SVG links to by path aren't loaded; embedded
+// ones are.
+
+use super::{Source, Thumbnailer};
+use crate::error::AppError;
+use image::{DynamicImage, RgbaImage};
+use resvg::tiny_skia::{Pixmap, Transform};
+use resvg::usvg::{fontdb, Options, Tree};
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+
+/// System fonts, loaded with the first SVG that needs them
+static FONTS: OnceLock<Arc<fontdb::Database>> = OnceLock::new();
+
+/// Fonts for the generic families, in order of preference
+const SERIF_FONTS: &[&str] = &[
+    "Times New Roman",
+    "Liberation Serif",
+    "DejaVu Serif",
+    "Noto Serif",
+];
+const SANS_SERIF_FONTS: &[&str] = &["Arial", "Liberation Sans", "DejaVu Sans", "Noto Sans"];
+const MONOSPACE_FONTS: &[&str] = &[
+    "Courier New",
+    "Liberation Mono",
+    "DejaVu Sans Mono",
+    "Noto Sans Mono",
+];
+
+pub struct Svg;
+
+impl Thumbnailer for Svg {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["svg", "svgz"]
+    }
+
+    fn thumbnail(&self, source: &Source, size: u32) -> Result<DynamicImage, AppError> {
+        render(&source.bytes()?, source.path(), size)
+    }
+}
+
+/// Rasterize an SVG document so its longer side is size; vectors scale up as
+/// well as down
+pub fn render(data: &[u8], path: &Path, size: u32) -> Result<DynamicImage, AppError> {
+    let fontdb = FONTS.get_or_init(|| Arc::new(system_fonts())).clone();
+    let options = Options {
+        fontdb,
+        ..Default::default()
+    };
+    let tree =
+        Tree::from_data(data, &options).map_err(|e| AppError::unsupported(path, e.to_string()))?;
+
+    let (width, height) = (tree.size().width(), tree.size().height());
+    let scale = size as f32 / width.max(height);
+    let mut pixmap = Pixmap::new(
+        ((width * scale).round() as u32).max(1),
+        ((height * scale).round() as u32).max(1),
+    )
+    .ok_or_else(|| AppError::unsupported(path, "Image has no size"))?;
+    resvg::render(
+        &tree,
+        Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+
+    // tiny-skia works premultiplied
+    let pixels = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|p| {
+            let c = p.demultiply();
+            [c.red(), c.green(), c.blue(), c.alpha()]
+        })
+        .collect();
+    let img = RgbaImage::from_raw(pixmap.width(), pixmap.height(), pixels)
+        .ok_or_else(|| AppError::other("Failed to create image buffer"))?;
+    Ok(DynamicImage::ImageRgba8(img))
+}
+
+fn system_fonts() -> fontdb::Database {
+    let mut fonts = fontdb::Database::new();
+    fonts.load_system_fonts();
+    let installed = |candidates: &[&str]| {
+        candidates
+            .iter()
+            .find(|name| {
+                fonts
+                    .faces()
+                    .any(|face| face.families.iter().any(|(family, _)| family == *name))
+            })
+            .map(|name| name.to_string())
+    };
+    let serif = installed(SERIF_FONTS);
+    let sans_serif = installed(SANS_SERIF_FONTS);
+    let monospace = installed(MONOSPACE_FONTS);
+    // Unstyled text falls back to serif; any font beats none
+    let any = fonts
+        .faces()
+        .next()
+        .and_then(|face| face.families.first())
+        .map(|(family, _)| family.clone());
+
+    if let Some(family) = serif.or_else(|| sans_serif.clone()).or(any) {
+        fonts.set_serif_family(family);
+    }
+    if let Some(family) = sans_serif {
+        fonts.set_sans_serif_family(family);
+    }
+    if let Some(family) = monospace {
+        fonts.set_monospace_family(family);
+    }
+    fonts
+}
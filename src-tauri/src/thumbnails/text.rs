@@ -0,0 +1,120 @@
+// Text file thumbnails: the first lines of the file on a small page, syntax
+// highlighted like the preview, then rasterized as an SVG. The encoding is
+// detected the way the preview does it; binary files get no thumbnail.
+
+use super::{svg, Source, Thumbnailer};
+use crate::error::{AppError, PathContext};
+use crate::preview::{encoding, highlight};
+use image::DynamicImage;
+use std::fmt::Write;
+use std::io::Read;
+
+/// Lines and columns of text on the page
+const LINES: usize = 18;
+const COLUMNS: usize = 28;
+
+/// Page size and layout, in SVG units
+const PAGE_WIDTH: f32 = 150.0;
+const PAGE_HEIGHT: f32 = 200.0;
+const MARGIN: f32 = 9.0;
+const FONT_SIZE: f32 = 8.0;
+const LINE_HEIGHT: f32 = 10.0;
+
+const TAB_WIDTH: usize = 4;
+
+pub struct Text;
+
+impl Thumbnailer for Text {
+    fn extensions(&self) -> &'static [&'static str] {
+        &[
+            "txt", "md", "json", "js", "ts", "jsx", "tsx", "css", "html", "xml", "yaml", "yml",
+            "toml", "ini", "cfg", "conf", "log", "sh", "bat", "ps1", "py", "rb", "go", "rs", "c",
+            "cpp", "h", "hpp", "java", "kt", "swift", "sql", "graphql", "vue", "svelte", "astro",
+            "csv", "tsv",
+        ]
+    }
+
+    fn thumbnail(&self, source: &Source, size: u32) -> Result<DynamicImage, AppError> {
+        let mut sample = Vec::with_capacity(encoding::SAMPLE_SIZE);
+        source
+            .reader()?
+            .take(encoding::SAMPLE_SIZE as u64)
+            .read_to_end(&mut sample)
+            .at(source.path())?;
+        let detected = encoding::detect(&sample)
+            .ok_or_else(|| AppError::unsupported(source.path(), "Not a text file"))?;
+        // A character cut off at the end of the sample is past the page
+        let (text, _) = detected
+            .encoding
+            .decode_without_bom_handling(&sample[detected.bom_len..]);
+
+        let lines: Vec<String> = text
+            .split_inclusive('\n')
+            .take(LINES)
+            .map(|line| {
+                let mut expanded = String::new();
+                for c in line.chars() {
+                    match c {
+                        '\t' => {
+                            let width = TAB_WIDTH - expanded.chars().count() % TAB_WIDTH;
+                            expanded.push_str(&" ".repeat(width));
+                        }
+                        '\n' => expanded.push(c),
+                        c if c.is_control() => {}
+                        c => expanded.push(c),
+                    }
+                    // Room for the highlighter to see a little past the edge
+                    if expanded.chars().count() > COLUMNS * 2 {
+                        expanded.push('\n');
+                        break;
+                    }
+                }
+                expanded
+            })
+            .collect();
+        let highlighted = highlight::highlight_lines(source.path(), &lines);
+
+        svg::render(page(&highlighted).as_bytes(), source.path(), size)
+    }
+}
+
+/// An SVG page with the lines on it, clipped to COLUMNS
+fn page(lines: &[Vec<highlight::Span>]) -> String {
+    let mut svg = format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{PAGE_WIDTH}" height="{PAGE_HEIGHT}"><rect x="0.5" y="0.5" width="{}" height="{}" fill="#ffffff" stroke="#cccccc"/><text font-family="monospace" font-size="{FONT_SIZE}" xml:space="preserve">"##,
+        PAGE_WIDTH - 1.0,
+        PAGE_HEIGHT - 1.0,
+    );
+    for (i, spans) in lines.iter().enumerate() {
+        let y = MARGIN + FONT_SIZE + i as f32 * LINE_HEIGHT;
+        let _ = write!(svg, r#"<tspan x="{MARGIN}" y="{y}">"#);
+        let mut room = COLUMNS;
+        for span in spans {
+            if room == 0 {
+                break;
+            }
+            let text: String = span.text.chars().take(room).collect();
+            room -= text.chars().count();
+            let weight = if span.bold {
+                r#" font-weight="bold""#
+            } else {
+                ""
+            };
+            let _ = write!(
+                svg,
+                r#"<tspan fill="{}"{weight}>{}</tspan>"#,
+                span.color,
+                escape(&text)
+            );
+        }
+        svg.push_str("</tspan>");
+    }
+    svg.push_str("</text></svg>");
+    svg
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,446 @@
+// Copy and move operations that go beyond plain fs::copy / fs::rename:
+// timestamps, permissions, extended attributes and symlinks are carried over,
+// copied files can optionally be verified against the source by hash, and
+// cross-filesystem moves never leave a half-written destination behind.
+
+use crate::error::{AppError, PathContext};
+use filetime::FileTime;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Options for a metadata-preserving copy
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(default)]
+pub struct CopyOptions {
+    /// Keep mtime/atime, permissions, xattrs and recreate symlinks instead of following them
+    pub preserve_metadata: bool,
+    /// Hash every copied file and compare it with the source
+    pub verify: bool,
+}
+
+/// A copied file whose content does not match its source
+#[derive(Serialize)]
+pub struct CopyMismatch {
+    pub source: String,
+    pub destination: String,
+    pub reason: String,
+}
+
+/// Summary of a copy operation
+#[derive(Serialize)]
+pub struct CopyReport {
+    pub path: String,
+    pub files_copied: u64,
+    pub bytes_copied: u64,
+    pub files_verified: u64,
+    pub mismatches: Vec<CopyMismatch>,
+    pub warnings: Vec<String>, // Metadata that could not be preserved
+}
+
+impl CopyReport {
+    fn new(path: &Path) -> Self {
+        CopyReport {
+            path: path.to_string_lossy().to_string(),
+            files_copied: 0,
+            bytes_copied: 0,
+            files_verified: 0,
+            mismatches: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+}
+
+/// Copy a file or folder to destination, optionally preserving metadata and verifying content
+#[tauri::command]
+pub fn copy_item_with_options(
+    source: String,
+    destination: String,
+    options: Option<CopyOptions>,
+) -> Result<CopyReport, AppError> {
+    let src = Path::new(&source);
+    let src_name = src
+        .file_name()
+        .ok_or_else(|| AppError::invalid_input("Cannot copy a root folder"))?;
+    let dest_dir = Path::new(&destination);
+    let options = options.unwrap_or_default();
+
+    // symlink_metadata so that dangling links can still be copied as links
+    let src_meta = fs::symlink_metadata(src).at(src)?;
+    let is_dir = if options.preserve_metadata {
+        src_meta.is_dir()
+    } else {
+        src.is_dir()
+    };
+
+    crate::validation::check_path(dest_dir)?;
+    crate::validation::check_not_inside(src, dest_dir)?;
+
+    let dest_path = crate::get_unique_path(dest_dir, src_name.to_str().unwrap_or(""), is_dir);
+
+    let mut report = CopyReport::new(&dest_path);
+    copy_entry(src, &dest_path, &options, &mut report)?;
+
+    Ok(report)
+}
+
+/// Copy a single entry (file, directory or symlink) honoring the given options
+pub fn copy_entry(
+    src: &Path,
+    dest: &Path,
+    options: &CopyOptions,
+    report: &mut CopyReport,
+) -> Result<(), AppError> {
+    let metadata = if options.preserve_metadata {
+        fs::symlink_metadata(src)
+    } else {
+        fs::metadata(src)
+    }
+    .at(src)?;
+
+    if metadata.file_type().is_symlink() {
+        copy_symlink(src, dest, &metadata).at(dest)?;
+        // Links carry no permissions of their own; only their timestamps are kept
+        let atime = FileTime::from_last_access_time(&metadata);
+        let mtime = FileTime::from_last_modification_time(&metadata);
+        if let Err(e) = filetime::set_symlink_file_times(dest, atime, mtime) {
+            report.warnings.push(format!(
+                "{}: timestamps not preserved ({})",
+                dest.display(),
+                e
+            ));
+        }
+    } else if metadata.is_dir() {
+        fs::create_dir_all(dest).at(dest)?;
+
+        for entry in fs::read_dir(src).at(src)? {
+            let entry = entry.at(src)?;
+            let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+            let child = crate::child_path(dest, &entry.file_name(), is_dir);
+            if child.file_name() != Some(entry.file_name().as_os_str()) {
+                report.warnings.push(format!(
+                    "{}: name clashes on this case-insensitive volume, copied as {}",
+                    entry.path().display(),
+                    child.display()
+                ));
+            }
+            copy_entry(&entry.path(), &child, options, report)?;
+        }
+
+        // Applied after the children so their creation doesn't bump the folder's mtime
+        if options.preserve_metadata {
+            apply_metadata(src, dest, &metadata, report);
+        }
+    } else {
+        let bytes = fs::copy(src, dest).at(src)?;
+        report.files_copied += 1;
+        report.bytes_copied += bytes;
+
+        if options.preserve_metadata {
+            apply_metadata(src, dest, &metadata, report);
+        }
+
+        if options.verify {
+            verify_file(src, dest, report);
+        }
+    }
+
+    Ok(())
+}
+
+/// Recreate a symlink pointing at the same target as the source link
+fn copy_symlink(src: &Path, dest: &Path, _metadata: &fs::Metadata) -> io::Result<()> {
+    let target = fs::read_link(src)?;
+
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(&target, dest)
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::FileTypeExt;
+        if _metadata.file_type().is_symlink_dir() {
+            std::os::windows::fs::symlink_dir(&target, dest)
+        } else {
+            std::os::windows::fs::symlink_file(&target, dest)
+        }
+    }
+}
+
+/// Copy xattrs, permissions and timestamps from src to dest.
+/// Failures are recorded as warnings: the data itself has already been copied.
+fn apply_metadata(src: &Path, dest: &Path, metadata: &fs::Metadata, report: &mut CopyReport) {
+    // Extended attributes first, a read-only permission set would block them
+    #[cfg(unix)]
+    if let Err(e) = copy_xattrs(src, dest) {
+        report.warnings.push(format!(
+            "{}: extended attributes not preserved ({})",
+            dest.display(),
+            e
+        ));
+    }
+    #[cfg(not(unix))]
+    let _ = src;
+
+    if let Err(e) = fs::set_permissions(dest, metadata.permissions()) {
+        report.warnings.push(format!(
+            "{}: permissions not preserved ({})",
+            dest.display(),
+            e
+        ));
+    }
+
+    let atime = FileTime::from_last_access_time(metadata);
+    let mtime = FileTime::from_last_modification_time(metadata);
+    if let Err(e) = filetime::set_file_times(dest, atime, mtime) {
+        report.warnings.push(format!(
+            "{}: timestamps not preserved ({})",
+            dest.display(),
+            e
+        ));
+    }
+}
+
+#[cfg(unix)]
+fn copy_xattrs(src: &Path, dest: &Path) -> io::Result<()> {
+    if !xattr::SUPPORTED_PLATFORM {
+        return Ok(());
+    }
+
+    for name in xattr::list(src)? {
+        if let Some(value) = xattr::get(src, &name)? {
+            xattr::set(dest, &name, &value)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Compare source and destination by size and BLAKE3 hash
+fn verify_file(src: &Path, dest: &Path, report: &mut CopyReport) {
+    let mismatch = |reason: String| CopyMismatch {
+        source: src.to_string_lossy().to_string(),
+        destination: dest.to_string_lossy().to_string(),
+        reason,
+    };
+
+    match (hash_file(src), hash_file(dest)) {
+        (Ok((src_len, src_hash)), Ok((dest_len, dest_hash))) => {
+            report.files_verified += 1;
+            if src_len != dest_len {
+                report.mismatches.push(mismatch(format!(
+                    "Size differs: {} bytes, copy has {} bytes",
+                    src_len, dest_len
+                )));
+            } else if src_hash != dest_hash {
+                report.mismatches.push(mismatch(format!(
+                    "Hash differs: {}, copy has {}",
+                    src_hash.to_hex(),
+                    dest_hash.to_hex()
+                )));
+            }
+        }
+        (Err(e), _) | (_, Err(e)) => {
+            report
+                .mismatches
+                .push(mismatch(format!("Could not verify: {}", e)));
+        }
+    }
+}
+
+/// Hash a file's content, returning its length and BLAKE3 digest
+pub fn hash_file(path: &Path) -> io::Result<(u64, blake3::Hash)> {
+    let file = fs::File::open(path)?;
+    let mut hasher = blake3::Hasher::new();
+    hasher.update_reader(file)?;
+    Ok((hasher.count(), hasher.finalize()))
+}
+
+/// Prefix for hidden staging entries of moves and archive jobs in flight
+pub const PARTIAL_PREFIX: &str = ".edorifile-partial-";
+
+/// Move src to dest when a plain rename is not possible (e.g. across filesystems).
+///
+/// The data is copied into a hidden sibling of dest, flushed to disk and then
+/// renamed into place, so dest either appears complete or not at all. The
+/// source is only deleted once dest is in place.
+pub fn move_across_filesystems(src: &Path, dest: &Path) -> Result<(), AppError> {
+    let (Some(dest_dir), Some(dest_name)) = (dest.parent(), dest.file_name()) else {
+        return Err(AppError::invalid_input("Cannot move onto a root folder"));
+    };
+    let dest_name = dest_name.to_string_lossy();
+
+    let staging = crate::get_unique_path(
+        dest_dir,
+        &format!("{}{}-{}", PARTIAL_PREFIX, std::process::id(), dest_name),
+        true,
+    );
+
+    // On failure in steps 1 to 3 nothing has changed yet, only the staging
+    // entry is cleaned up
+
+    // 1. Copy into the staging entry
+    let options = CopyOptions {
+        preserve_metadata: true,
+        verify: false,
+    };
+    let mut report = CopyReport::new(&staging);
+    if let Err(e) = copy_entry(src, &staging, &options, &mut report) {
+        discard_staging(&staging);
+        return Err(e);
+    }
+
+    // 2. Make sure the copy is on disk before the source is touched
+    if let Err(e) = sync_tree(&staging) {
+        discard_staging(&staging);
+        return Err(AppError::io(e, dest));
+    }
+
+    // 3. Publish the copy under its final name, unless something has taken
+    // it since the move started
+    if let Err(e) = rename_no_replace(&staging, dest) {
+        discard_staging(&staging);
+        return Err(AppError::io(e, dest));
+    }
+    let _ = sync_dir(dest_dir);
+
+    // 4. Only now remove the source. The destination is complete either way.
+    let removed = if fs::symlink_metadata(src)
+        .map(|m| m.is_dir())
+        .unwrap_or(false)
+    {
+        fs::remove_dir_all(src)
+    } else {
+        fs::remove_file(src)
+    };
+    removed.map_err(|_| AppError::SourceNotRemoved {
+        path: src.to_string_lossy().to_string(),
+        destination: dest.to_string_lossy().to_string(),
+    })
+}
+
+/// Rename from to to, failing with AlreadyExists instead of replacing
+/// whatever is at to, even if it appeared a moment ago
+#[cfg(unix)]
+pub fn rename_no_replace(from: &Path, to: &Path) -> io::Result<()> {
+    match rename_exclusive(from, to) {
+        Some(result) => result,
+        None => claim_and_rename(from, to),
+    }
+}
+
+#[cfg(windows)]
+pub fn rename_no_replace(from: &Path, to: &Path) -> io::Result<()> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows::core::PCWSTR;
+    use windows::Win32::Storage::FileSystem::{MoveFileExW, MOVE_FILE_FLAGS};
+
+    let wide =
+        |path: &Path| -> Vec<u16> { path.as_os_str().encode_wide().chain(Some(0)).collect() };
+    let (from, to) = (wide(from), wide(to));
+    // Without MOVEFILE_REPLACE_EXISTING an existing destination is an error
+    unsafe {
+        MoveFileExW(
+            PCWSTR(from.as_ptr()),
+            PCWSTR(to.as_ptr()),
+            MOVE_FILE_FLAGS(0),
+        )
+    }
+    .map_err(|e| io::Error::from_raw_os_error(e.code().0 & 0xFFFF))
+}
+
+/// renameat2 with RENAME_NOREPLACE, or renamex_np with RENAME_EXCL; None when
+/// the kernel or file system doesn't do exclusive renames
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn rename_exclusive(from: &Path, to: &Path) -> Option<io::Result<()>> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = |path: &Path| CString::new(path.as_os_str().as_bytes());
+    let (Ok(from), Ok(to)) = (c_path(from), c_path(to)) else {
+        return Some(Err(io::ErrorKind::InvalidInput.into()));
+    };
+    #[cfg(target_os = "linux")]
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_renameat2,
+            libc::AT_FDCWD,
+            from.as_ptr(),
+            libc::AT_FDCWD,
+            to.as_ptr(),
+            libc::RENAME_NOREPLACE,
+        )
+    };
+    #[cfg(target_os = "macos")]
+    let result = unsafe { libc::renamex_np(from.as_ptr(), to.as_ptr(), libc::RENAME_EXCL) };
+    if result == 0 {
+        return Some(Ok(()));
+    }
+    let error = io::Error::last_os_error();
+    match error.raw_os_error() {
+        Some(libc::EINVAL | libc::ENOSYS | libc::ENOTSUP) => None,
+        _ => Some(Err(error)),
+    }
+}
+
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+fn rename_exclusive(_: &Path, _: &Path) -> Option<io::Result<()>> {
+    None
+}
+
+/// Take the name first with a hard link or an empty folder, which fail if it
+/// exists, then move the entry in. A rename only replaces a folder while it's
+/// still empty.
+#[cfg(unix)]
+fn claim_and_rename(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(from)?.is_dir() {
+        fs::create_dir(to)?;
+        fs::rename(from, to).inspect_err(|_| {
+            let _ = fs::remove_dir(to);
+        })
+    } else {
+        fs::hard_link(from, to)?;
+        // The entry is in place; a leftover link to it is only clutter
+        let _ = fs::remove_file(from);
+        Ok(())
+    }
+}
+
+/// Best-effort removal of a staging entry after a failed move
+fn discard_staging(staging: &Path) {
+    let _ = if staging.is_dir() {
+        fs::remove_dir_all(staging)
+    } else {
+        fs::remove_file(staging)
+    };
+}
+
+/// Flush every file and directory under path to disk
+fn sync_tree(path: &Path) -> io::Result<()> {
+    for entry in walkdir::WalkDir::new(path) {
+        let entry = entry.map_err(io::Error::other)?;
+        let file_type = entry.file_type();
+
+        if file_type.is_file() {
+            fs::File::open(entry.path())?.sync_all()?;
+        } else if file_type.is_dir() {
+            sync_dir(entry.path())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Flush a directory's entries to disk (a no-op where directories can't be opened)
+fn sync_dir(path: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        fs::File::open(path)?.sync_all()
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        Ok(())
+    }
+}
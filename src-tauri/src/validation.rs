@@ -0,0 +1,402 @@
+// Validation of user-supplied names and paths before anything touches the disk.
+// Names are checked against the rules of the platform (Windows or Unix), and
+// every rejection carries a code the UI can turn into an explanation.
+
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Component, Path};
+
+/// Longest file name, in bytes on Unix and UTF-16 units on Windows
+const MAX_NAME_LEN: usize = 255;
+
+/// Longest path, in the same units
+#[cfg(windows)]
+const MAX_PATH_LEN: usize = 32767;
+#[cfg(not(windows))]
+const MAX_PATH_LEN: usize = 4096;
+
+/// Characters Windows doesn't allow anywhere in a name
+const WINDOWS_RESERVED_CHARS: &[char] = &['<', '>', ':', '"', '|', '?', '*'];
+
+/// Device names Windows reserves, with or without an extension ("CON.txt")
+const WINDOWS_RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "COM¹", "COM²", "COM³", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8",
+    "LPT9", "LPT¹", "LPT²", "LPT³", "CONIN$", "CONOUT$",
+];
+
+/// Why a name or path was rejected
+#[derive(Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationCode {
+    Empty,
+    TooLong,
+    DotName,   // "." or ".."
+    Separator, // '/' or, on Windows, '\'
+    Nul,
+    ControlCharacter,   // Windows only
+    ReservedCharacter,  // Windows only: < > : " | ? *
+    ReservedName,       // Windows only: CON, NUL, COM1, ...
+    TrailingDotOrSpace, // Windows only
+    RelativePath,
+    ParentReference, // A ".." component in a path
+    PathTooLong,
+    InsideSource, // Copying or moving a folder into itself
+}
+
+/// A rejected name or path
+#[derive(Serialize, Clone, Debug)]
+pub struct ValidationError {
+    pub code: ValidationCode,
+    pub value: String,   // The offending name or path
+    pub message: String, // Human readable explanation
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Naming rules to check against
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Platform {
+    Windows,
+    Unix,
+}
+
+impl Platform {
+    pub fn current() -> Self {
+        if cfg!(windows) {
+            Platform::Windows
+        } else {
+            Platform::Unix
+        }
+    }
+}
+
+fn invalid(code: ValidationCode, value: &str, message: String) -> ValidationError {
+    ValidationError {
+        code,
+        value: value.to_string(),
+        message,
+    }
+}
+
+/// Check a single file or folder name against the current platform's rules
+pub fn check_name(name: &str) -> Result<(), ValidationError> {
+    check_name_for(name, Platform::current())
+}
+
+/// Check a single file or folder name against the rules of a platform
+pub fn check_name_for(name: &str, platform: Platform) -> Result<(), ValidationError> {
+    use ValidationCode::*;
+
+    if name.trim().is_empty() {
+        return Err(invalid(Empty, name, "Name cannot be empty".to_string()));
+    }
+
+    if name == "." || name == ".." {
+        return Err(invalid(
+            DotName,
+            name,
+            format!("'{}' is not a valid name", name),
+        ));
+    }
+
+    if name.contains('\0') {
+        return Err(invalid(
+            Nul,
+            name,
+            "Name cannot contain a NUL character".to_string(),
+        ));
+    }
+
+    let separators: &[char] = match platform {
+        Platform::Windows => &['/', '\\'],
+        Platform::Unix => &['/'],
+    };
+    if let Some(c) = name.chars().find(|c| separators.contains(c)) {
+        return Err(invalid(
+            Separator,
+            name,
+            format!("Name cannot contain '{}'", c),
+        ));
+    }
+
+    let len = match platform {
+        Platform::Windows => name.encode_utf16().count(),
+        Platform::Unix => name.len(),
+    };
+    if len > MAX_NAME_LEN {
+        return Err(invalid(
+            TooLong,
+            name,
+            format!("Name is too long (maximum {} characters)", MAX_NAME_LEN),
+        ));
+    }
+
+    if platform == Platform::Windows {
+        check_windows_name(name)?;
+    }
+
+    Ok(())
+}
+
+fn check_windows_name(name: &str) -> Result<(), ValidationError> {
+    use ValidationCode::*;
+
+    if name.chars().any(|c| c.is_ascii_control()) {
+        return Err(invalid(
+            ControlCharacter,
+            name,
+            "Name cannot contain control characters".to_string(),
+        ));
+    }
+
+    if let Some(c) = name.chars().find(|c| WINDOWS_RESERVED_CHARS.contains(c)) {
+        return Err(invalid(
+            ReservedCharacter,
+            name,
+            format!(
+                "Name cannot contain '{}' (not allowed: {})",
+                c,
+                WINDOWS_RESERVED_CHARS.iter().collect::<String>()
+            ),
+        ));
+    }
+
+    if name.ends_with('.') || name.ends_with(' ') {
+        return Err(invalid(
+            TrailingDotOrSpace,
+            name,
+            "Name cannot end with a dot or a space".to_string(),
+        ));
+    }
+
+    // "con.txt" and "CON .log" are the device too
+    let stem = name.split('.').next().unwrap_or(name).trim_end();
+    if WINDOWS_RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(stem))
+    {
+        return Err(invalid(
+            ReservedName,
+            name,
+            format!("'{}' is a reserved name on Windows", stem),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Check a user-supplied absolute path: no "..", no NUL, not too long
+pub fn check_path(path: &Path) -> Result<(), ValidationError> {
+    use ValidationCode::*;
+
+    let display = path.to_string_lossy();
+
+    if display.contains('\0') {
+        return Err(invalid(
+            Nul,
+            &display,
+            "Path cannot contain a NUL character".to_string(),
+        ));
+    }
+
+    if !path.is_absolute() {
+        return Err(invalid(
+            RelativePath,
+            &display,
+            format!("Path must be absolute: {}", display),
+        ));
+    }
+
+    if path.components().any(|c| c == Component::ParentDir) {
+        return Err(invalid(
+            ParentReference,
+            &display,
+            format!("Path cannot contain '..': {}", display),
+        ));
+    }
+
+    let len = if cfg!(windows) {
+        display.encode_utf16().count()
+    } else {
+        path.as_os_str().len()
+    };
+    if len > MAX_PATH_LEN {
+        return Err(invalid(
+            PathTooLong,
+            &display,
+            format!("Path is too long (maximum {} characters)", MAX_PATH_LEN),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Check that dest_dir is not src itself or a folder inside it,
+/// which would make a copy or move recurse into its own output
+pub fn check_not_inside(src: &Path, dest_dir: &Path) -> Result<(), ValidationError> {
+    let (Ok(src), Ok(dest)) = (src.canonicalize(), dest_dir.canonicalize()) else {
+        return Ok(()); // Missing paths are reported by the caller
+    };
+
+    if src.is_dir() && dest.starts_with(&src) {
+        let display = dest_dir.to_string_lossy();
+        return Err(invalid(
+            ValidationCode::InsideSource,
+            &display,
+            format!(
+                "Cannot put '{}' inside itself",
+                src.file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| src.to_string_lossy().to_string())
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Check a name before creating or renaming, so the UI can explain problems
+/// while the user types. Defaults to the current platform's rules.
+#[tauri::command]
+pub fn validate_name(name: String, platform: Option<Platform>) -> Result<(), AppError> {
+    Ok(check_name_for(
+        &name,
+        platform.unwrap_or_else(Platform::current),
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code(name: &str, platform: Platform) -> Option<ValidationCode> {
+        check_name_for(name, platform).err().map(|e| e.code)
+    }
+
+    #[test]
+    fn ordinary_names() {
+        for name in [
+            "notes.txt",
+            ".hidden",
+            "a b",
+            "ünïcødé",
+            "CONSOLE",
+            "con1",
+            "LPT10",
+        ] {
+            assert_eq!(code(name, Platform::Windows), None, "{name}");
+            assert_eq!(code(name, Platform::Unix), None, "{name}");
+        }
+    }
+
+    #[test]
+    fn empty_and_dot_names() {
+        assert_eq!(code("", Platform::Unix), Some(ValidationCode::Empty));
+        assert_eq!(code("   ", Platform::Windows), Some(ValidationCode::Empty));
+        assert_eq!(code(".", Platform::Unix), Some(ValidationCode::DotName));
+        assert_eq!(code("..", Platform::Windows), Some(ValidationCode::DotName));
+    }
+
+    #[test]
+    fn reserved_names() {
+        for name in [
+            "CON",
+            "con",
+            "nul.txt",
+            "Aux.tar.gz",
+            "COM1",
+            "lpt9.log",
+            "COM¹",
+            "CON .txt",
+            "CONIN$",
+            "conout$",
+            "CONIN$.txt",
+        ] {
+            assert_eq!(
+                code(name, Platform::Windows),
+                Some(ValidationCode::ReservedName),
+                "{name}"
+            );
+            assert_eq!(code(name, Platform::Unix), None, "{name}");
+        }
+    }
+
+    #[test]
+    fn trailing_dots_and_spaces() {
+        for name in ["name.", "name ", "name. ", "..."] {
+            assert_eq!(
+                code(name, Platform::Windows),
+                Some(ValidationCode::TrailingDotOrSpace),
+                "{name}"
+            );
+            assert_eq!(code(name, Platform::Unix), None, "{name}");
+        }
+    }
+
+    #[test]
+    fn illegal_characters() {
+        assert_eq!(code("a/b", Platform::Unix), Some(ValidationCode::Separator));
+        assert_eq!(
+            code("a/b", Platform::Windows),
+            Some(ValidationCode::Separator)
+        );
+        assert_eq!(
+            code("a\\b", Platform::Windows),
+            Some(ValidationCode::Separator)
+        );
+        assert_eq!(code("a\\b", Platform::Unix), None);
+        assert_eq!(code("a\0b", Platform::Unix), Some(ValidationCode::Nul));
+        assert_eq!(
+            code("tab\there", Platform::Windows),
+            Some(ValidationCode::ControlCharacter)
+        );
+        assert_eq!(code("tab\there", Platform::Unix), None);
+        for c in WINDOWS_RESERVED_CHARS {
+            let name = format!("a{c}b");
+            assert_eq!(
+                code(&name, Platform::Windows),
+                Some(ValidationCode::ReservedCharacter),
+                "{name}"
+            );
+            assert_eq!(code(&name, Platform::Unix), None, "{name}");
+        }
+    }
+
+    #[test]
+    fn name_length() {
+        let longest = "a".repeat(MAX_NAME_LEN);
+        let too_long = "a".repeat(MAX_NAME_LEN + 1);
+        for platform in [Platform::Windows, Platform::Unix] {
+            assert_eq!(code(&longest, platform), None);
+            assert_eq!(code(&too_long, platform), Some(ValidationCode::TooLong));
+        }
+        // Bytes on Unix, UTF-16 units on Windows
+        let wide = "é".repeat(200);
+        assert_eq!(code(&wide, Platform::Windows), None);
+        assert_eq!(code(&wide, Platform::Unix), Some(ValidationCode::TooLong));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn paths() {
+        let path_code = |path: &str| check_path(Path::new(path)).err().map(|e| e.code);
+        assert_eq!(path_code("/home/user/file.txt"), None);
+        assert_eq!(
+            path_code("relative/file"),
+            Some(ValidationCode::RelativePath)
+        );
+        assert_eq!(
+            path_code("/home/../etc/passwd"),
+            Some(ValidationCode::ParentReference)
+        );
+        let too_long = format!("/{}", "a/".repeat(MAX_PATH_LEN / 2));
+        assert_eq!(path_code(&too_long), Some(ValidationCode::PathTooLong));
+    }
+}
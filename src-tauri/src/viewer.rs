@@ -0,0 +1,290 @@
+// The image viewer: pictures are decoded once at full size into a cache
+// bounded by memory, then served a region at a time over image-tile://, scaled
+// to whatever zoom the view is at. Each cached picture keeps halved copies as
+// they're asked for, so tiles of a zoomed-out 100-megapixel scan are cut from
+// a small copy rather than scaled down from the whole image every time.
+// Opening a picture decodes the ones before and after it in the folder in the
+// background, so a slideshow doesn't wait.
+
+use crate::error::AppError;
+use crate::thumbnails;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+/// Memory the decoded pictures may take together; the one on screen stays
+/// even if it's larger on its own
+const CACHE_BYTES: usize = 1024 * 1024 * 1024;
+
+/// Largest tile served, in pixels
+const MAX_TILE_PIXELS: u64 = 4096 * 4096;
+
+/// Opaque tiles are sent as JPEG, which encodes far faster than PNG
+const JPEG_QUALITY: u8 = 90;
+
+/// Decoded pictures, most recently used last
+static CACHE: Mutex<VecDeque<Arc<Entry>>> = Mutex::new(VecDeque::new());
+
+/// Bumped by every open_image, so prefetches for pictures the user has moved
+/// on from are dropped
+static PREFETCH_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+struct Entry {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    // Decoded on first use; others asking meanwhile wait for it
+    picture: OnceLock<Result<Arc<Picture>, AppError>>,
+}
+
+/// A picture at full size and halved as many times as tiles have needed
+struct Picture {
+    levels: Mutex<Vec<Arc<DynamicImage>>>,
+    bytes: AtomicUsize, // Of all the levels, so eviction needn't lock them
+}
+
+/// What the viewer needs to lay out a picture
+#[derive(Serialize)]
+pub struct ImageInfo {
+    pub width: u32,
+    pub height: u32,
+    pub has_alpha: bool,
+    pub previous: Option<String>, // Neighbouring pictures in the folder, by name
+    pub next: Option<String>,
+}
+
+/// A region of a picture, in full-size pixels
+#[derive(Clone, Copy)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Picture {
+    fn new(image: DynamicImage) -> Picture {
+        // 8 bits per channel, and alpha only where there is some
+        let image = if image.color().has_alpha() {
+            DynamicImage::ImageRgba8(image.into_rgba8())
+        } else {
+            DynamicImage::ImageRgb8(image.into_rgb8())
+        };
+        Picture {
+            bytes: AtomicUsize::new(image.as_bytes().len()),
+            levels: Mutex::new(vec![Arc::new(image)]),
+        }
+    }
+
+    fn full(&self) -> Arc<DynamicImage> {
+        self.levels.lock().unwrap_or_else(|e| e.into_inner())[0].clone()
+    }
+
+    /// The picture halved level times, or as far as it goes
+    fn level(&self, level: usize) -> (usize, Arc<DynamicImage>) {
+        loop {
+            let (next, last) = {
+                let levels = self.levels.lock().unwrap_or_else(|e| e.into_inner());
+                let last = &levels[levels.len() - 1];
+                if levels.len() > level || (last.width() == 1 && last.height() == 1) {
+                    let level = level.min(levels.len() - 1);
+                    return (level, levels[level].clone());
+                }
+                (levels.len(), last.clone())
+            };
+            // Halved without the lock, so tiles from the levels already there
+            // aren't held up
+            let half = last.resize_exact(
+                last.width().div_ceil(2),
+                last.height().div_ceil(2),
+                FilterType::Triangle,
+            );
+            let mut levels = self.levels.lock().unwrap_or_else(|e| e.into_inner());
+            // Unless another tile got there first
+            if levels.len() == next {
+                self.bytes
+                    .fetch_add(half.as_bytes().len(), Ordering::Relaxed);
+                levels.push(Arc::new(half));
+            }
+        }
+    }
+
+    fn bytes(&self) -> usize {
+        self.bytes.load(Ordering::Relaxed)
+    }
+}
+
+/// Decode a picture for viewing, and start on its neighbours
+#[tauri::command]
+pub async fn open_image(path: String) -> Result<ImageInfo, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = PathBuf::from(path);
+        let full = picture(&path)?.full();
+        let (previous, next) = neighbours(&path);
+
+        let generation = PREFETCH_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+        for neighbour in [&next, &previous].into_iter().flatten() {
+            let neighbour = PathBuf::from(neighbour);
+            tauri::async_runtime::spawn_blocking(move || {
+                if PREFETCH_GENERATION.load(Ordering::SeqCst) == generation {
+                    let _ = picture(&neighbour);
+                }
+            });
+        }
+
+        Ok(ImageInfo {
+            width: full.width(),
+            height: full.height(),
+            has_alpha: full.color().has_alpha(),
+            previous,
+            next,
+        })
+    })
+    .await
+    .map_err(AppError::other)?
+}
+
+/// A region of a picture (all of it by default) scaled by scale, which is at
+/// most 1; the view enlarges past that itself. Returns the encoded tile and
+/// its content type.
+pub fn tile(
+    path: &Path,
+    region: Option<Region>,
+    scale: f64,
+) -> Result<(Vec<u8>, &'static str), AppError> {
+    if !(scale > 0.0 && scale <= 1.0) {
+        return Err(AppError::invalid_input(
+            "Scale must be above 0 and at most 1",
+        ));
+    }
+    let picture = picture(path)?;
+    let full = picture.full();
+
+    // Clip the region to the picture
+    let region = region.unwrap_or(Region {
+        x: 0,
+        y: 0,
+        width: full.width(),
+        height: full.height(),
+    });
+    let x = region.x.min(full.width());
+    let y = region.y.min(full.height());
+    let width = region.width.min(full.width() - x);
+    let height = region.height.min(full.height() - y);
+    if width == 0 || height == 0 {
+        return Err(AppError::invalid_input("Region is outside the image"));
+    }
+    let out_width = ((width as f64 * scale).round() as u32).max(1);
+    let out_height = ((height as f64 * scale).round() as u32).max(1);
+    if out_width as u64 * out_height as u64 > MAX_TILE_PIXELS {
+        return Err(AppError::invalid_input("Tile is too large"));
+    }
+
+    // Cut from the smallest copy that's still at least as large as the tile
+    let (level, source) = picture.level((1.0 / scale).log2().floor() as usize);
+    let factor = 1u32 << level;
+    let (lx, ly) = (x / factor, y / factor);
+    let lw = ((x + width).div_ceil(factor)).min(source.width()) - lx;
+    let lh = ((y + height).div_ceil(factor)).min(source.height()) - ly;
+    let mut tile = source.crop_imm(lx, ly, lw.max(1), lh.max(1));
+    if tile.width() != out_width || tile.height() != out_height {
+        tile = tile.resize_exact(out_width, out_height, FilterType::Triangle);
+    }
+
+    let mut buffer = Cursor::new(Vec::new());
+    if tile.color().has_alpha() {
+        tile.write_to(&mut buffer, ImageFormat::Png)?;
+        Ok((buffer.into_inner(), "image/png"))
+    } else {
+        JpegEncoder::new_with_quality(&mut buffer, JPEG_QUALITY).encode_image(&tile)?;
+        Ok((buffer.into_inner(), "image/jpeg"))
+    }
+}
+
+/// A picture from the cache, decoded first if it isn't there or has changed
+fn picture(path: &Path) -> Result<Arc<Picture>, AppError> {
+    let modified = thumbnails::modified(path);
+    let entry = {
+        let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+        cache.retain(|e| e.path != path || e.modified == modified);
+        let cached = cache
+            .iter()
+            .position(|e| e.path == path)
+            .and_then(|i| cache.remove(i));
+        let entry = cached.unwrap_or_else(|| {
+            Arc::new(Entry {
+                path: path.to_path_buf(),
+                modified,
+                picture: OnceLock::new(),
+            })
+        });
+        cache.push_back(entry.clone());
+        entry
+    };
+
+    let decoded = entry
+        .picture
+        .get_or_init(|| thumbnails::full_image(path).map(|image| Arc::new(Picture::new(image))));
+    match decoded {
+        Ok(picture) => {
+            let picture = picture.clone();
+            evict();
+            Ok(picture)
+        }
+        // Not kept, so it's tried again next time
+        Err(e) => {
+            let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+            cache.retain(|e| !Arc::ptr_eq(e, &entry));
+            Err(e.clone())
+        }
+    }
+}
+
+/// Drop the least recently used pictures until the cache fits, leaving any
+/// that are being decoded or tiled
+fn evict() {
+    let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    let size = |e: &Entry| match e.picture.get() {
+        Some(Ok(picture)) => picture.bytes(),
+        _ => 0,
+    };
+    let in_use = |e: &Arc<Entry>| {
+        Arc::strong_count(e) > 1
+            || matches!(e.picture.get(), Some(Ok(picture)) if Arc::strong_count(picture) > 1)
+    };
+    let mut total: usize = cache.iter().map(|e| size(e)).sum();
+    let mut i = 0;
+    while total > CACHE_BYTES && i + 1 < cache.len() {
+        if !in_use(&cache[i]) {
+            if let Some(evicted) = cache.remove(i) {
+                total -= size(&evicted);
+            }
+        } else {
+            i += 1;
+        }
+    }
+}
+
+/// The pictures before and after this one in its folder
+fn neighbours(path: &Path) -> (Option<String>, Option<String>) {
+    let Some(dir) = path.parent() else {
+        return (None, None);
+    };
+    let Ok(pictures) = thumbnails::pictures_in(dir, usize::MAX) else {
+        return (None, None);
+    };
+    let Some(i) = pictures.iter().position(|p| p == path) else {
+        return (None, None);
+    };
+    let name = |p: &PathBuf| p.to_string_lossy().into_owned();
+    (
+        i.checked_sub(1).map(|i| name(&pictures[i])),
+        pictures.get(i + 1).map(name),
+    )
+}
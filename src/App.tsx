@@ -5,7 +5,7 @@ import { useState, useEffect } from 'react';
 import './App.css';
 
 // Stores
-import { useAppStore, useTabStore, useClipboardStore, useFolderPrefsStore } from '@store';
+import { useAppStore, useTabStore, useClipboardStore, useFolderPrefsStore, sortColumns } from '@store';
 
 // Hooks
 import { useFileOperations, useKeyboardShortcuts, useKeyboardNavigation, useContextMenu, type DialogType } from '@hooks';
@@ -23,6 +23,13 @@ import {
   PropertiesDialog
 } from '@components';
 
+// Services
+import { fileService } from '@services';
+
+// Dialog validation: explain invalid names before anything is sent to disk
+const validateName = (name: string) =>
+  fileService.validateName(name).then((error) => error?.message ?? null);
+
 function App() {
   // Dialog state (local since it's UI-only)
   const [dialog, setDialog] = useState<DialogType>(null);
@@ -223,6 +230,10 @@ function App() {
               onSort={(column) => {
                 const newOrder = currentState.sortBy === column && currentState.sortOrder === 'asc' ? 'desc' : 'asc';
                 useTabStore.getState().updateTabState(activeTabId, { sortBy: column, sortOrder: newOrder });
+                // Metadata sort keys need their column, which plain listings don't carry
+                if (column !== currentState.sortBy && sortColumns(column)) {
+                    useTabStore.getState().refresh();
+                }
               }}
               onSelect={handleSelect}
               onOpen={handleOpen}
@@ -266,6 +277,7 @@ function App() {
           title="New Folder"
           placeholder="Folder name"
           confirmLabel="Create"
+          validate={validateName}
           onConfirm={(name) => {
             handleNewFolder(name);
             setDialog(null);
@@ -279,6 +291,7 @@ function App() {
           title="Rename"
           initialValue={selectedFile.name}
           confirmLabel="Rename"
+          validate={validateName}
           onConfirm={(newName) => {
             handleRename(newName);
             setDialog(null);
@@ -6,6 +6,7 @@ interface DialogProps {
     placeholder?: string;
     confirmLabel?: string;
     confirmDanger?: boolean;
+    validate?: (value: string) => Promise<string | null>; // Resolves to an error message, or null if valid
     onConfirm: (value: string) => void;
     onCancel: () => void;
 }
@@ -16,10 +17,12 @@ export const InputDialog: FC<DialogProps> = ({
     placeholder = '',
     confirmLabel = 'OK',
     confirmDanger = false,
+    validate,
     onConfirm,
     onCancel,
 }) => {
     const [value, setValue] = useState(initialValue);
+    const [error, setError] = useState<string | null>(null);
     const inputRef = useRef<HTMLInputElement>(null);
 
     useEffect(() => {
@@ -27,9 +30,24 @@ export const InputDialog: FC<DialogProps> = ({
         inputRef.current?.select();
     }, []);
 
+    // Validate as the user types; stale answers are dropped
+    useEffect(() => {
+        if (!validate || !value.trim()) {
+            setError(null);
+            return;
+        }
+        let current = true;
+        validate(value.trim()).then((message) => {
+            if (current) setError(message);
+        });
+        return () => {
+            current = false;
+        };
+    }, [validate, value]);
+
     const handleSubmit = (e: React.FormEvent) => {
         e.preventDefault();
-        if (value.trim()) {
+        if (value.trim() && !error) {
             onConfirm(value.trim());
         }
     };
@@ -55,6 +73,9 @@ export const InputDialog: FC<DialogProps> = ({
                         className="dialog-input"
                         spellCheck={false}
                     />
+                    {error && (
+                        <p className="text-[12px] text-[var(--color-danger)] mt-2">{error}</p>
+                    )}
                     <div className="dialog-buttons">
                         <button type="button" onClick={onCancel} className="dialog-btn dialog-btn-secondary">
                             Cancel
@@ -62,7 +83,7 @@ export const InputDialog: FC<DialogProps> = ({
                         <button
                             type="submit"
                             className={`dialog-btn ${confirmDanger ? 'dialog-btn-danger' : 'dialog-btn-primary'}`}
-                            disabled={!value.trim()}
+                            disabled={!value.trim() || error !== null}
                         >
                             {confirmLabel}
                         </button>
@@ -1,8 +1,8 @@
 import { FC, useMemo, useRef } from 'react';
 import { useVirtualizer } from '@tanstack/react-virtual';
-import type { FileEntry, SortBy, SortOrder } from '@types';
+import type { FileEntry, MetadataSortBy, SortBy, SortOrder } from '@types';
 import { getFileIcon } from '@utils/icons';
-import { formatSize, getFileType } from '@utils/format';
+import { formatDuration, formatSize, getFileType } from '@utils/format';
 
 interface FileListProps {
     files: FileEntry[];
@@ -18,6 +18,49 @@ interface FileListProps {
 // Row height for virtualization
 const ROW_HEIGHT = 36;
 
+// Sort keys read from inside files, shown as an extra column while active
+const METADATA_SORTS: { key: MetadataSortBy; label: string }[] = [
+    { key: 'dimensions', label: 'Dimensions' },
+    { key: 'date_taken', label: 'Date taken' },
+    { key: 'duration', label: 'Duration' },
+    { key: 'bitrate', label: 'Bitrate' },
+    { key: 'title', label: 'Title' },
+    { key: 'artist', label: 'Artist' },
+    { key: 'album', label: 'Album' },
+    { key: 'track', label: 'Track' },
+];
+
+const isMetadataSort = (sortBy: SortBy): sortBy is MetadataSortBy =>
+    METADATA_SORTS.some((s) => s.key === sortBy);
+
+// Value of a metadata sort key; pixel count for dimensions
+const metadataValue = (file: FileEntry, key: MetadataSortBy): string | number | null => {
+    const m = file.metadata;
+    if (!m) return null;
+    switch (key) {
+        case 'dimensions':
+            return m.width !== null && m.height !== null ? m.width * m.height : null;
+        default:
+            return m[key];
+    }
+};
+
+// Text of a metadata sort key for the extra column
+const formatMetadata = (file: FileEntry, key: MetadataSortBy): string => {
+    const m = file.metadata;
+    if (!m) return '-';
+    switch (key) {
+        case 'dimensions':
+            return m.width !== null && m.height !== null ? `${m.width} × ${m.height}` : '-';
+        case 'duration':
+            return m.duration !== null ? formatDuration(m.duration) : '-';
+        case 'bitrate':
+            return m.bitrate !== null ? `${m.bitrate} kbps` : '-';
+        default:
+            return m[key] !== null ? String(m[key]) : '-';
+    }
+};
+
 // Sort arrow indicator
 const SortArrow: FC<{ direction: SortOrder }> = ({ direction }) => (
     <svg
@@ -57,17 +100,18 @@ const ColumnHeader: FC<{
 const FileRow: FC<{
     file: FileEntry;
     isSelected: boolean;
+    metadataSort: MetadataSortBy | null;
     style: React.CSSProperties;
     onSelect: (file: FileEntry, event: React.MouseEvent) => void;
     onOpen: (file: FileEntry) => void;
     onContextMenu: (e: React.MouseEvent, file: FileEntry) => void;
-}> = ({ file, isSelected, style, onSelect, onOpen, onContextMenu }) => (
+}> = ({ file, isSelected, metadataSort, style, onSelect, onOpen, onContextMenu }) => (
     <button
         style={style}
         onClick={(e) => onSelect(file, e)}
         onDoubleClick={() => onOpen(file)}
         onContextMenu={(e) => onContextMenu(e, file)}
-        className={`file-row absolute left-0 right-0 grid grid-cols-[1fr_150px_150px_100px] gap-4 px-6 items-center text-left transition-colors
+        className={`file-row absolute left-0 right-0 grid grid-cols-[1fr_150px_150px_100px_140px] gap-4 px-6 items-center text-left transition-colors
             ${isSelected ? 'selected bg-[var(--color-bg-selected-row)]' : 'hover:bg-[var(--color-bg-hover)]'}`}
     >
         {/* Name with icon */}
@@ -97,6 +141,11 @@ const FileRow: FC<{
         <div className="text-[12px] text-[var(--color-text-secondary)] text-right pr-2">
             {file.is_dir ? '-' : formatSize(file.size)}
         </div>
+
+        {/* Metadata sort key */}
+        <div className="text-[12px] text-[var(--color-text-secondary)] truncate">
+            {metadataSort && !file.is_dir ? formatMetadata(file, metadataSort) : ''}
+        </div>
     </button>
 );
 
@@ -113,6 +162,8 @@ export const FileList: FC<FileListProps> = ({
     // Ref for the scrollable container
     const parentRef = useRef<HTMLDivElement>(null);
 
+    const metadataSort = isMetadataSort(sortBy) ? sortBy : null;
+
     // Sort files with memoization
     const sortedFiles = useMemo(() => {
         return [...files].sort((a, b) => {
@@ -135,6 +186,18 @@ export const FileList: FC<FileListProps> = ({
                     const typeB = getFileType(b.extension, b.is_dir);
                     comparison = typeA.localeCompare(typeB);
                     break;
+                default: {
+                    // Files without the value go last either way
+                    const valueA = metadataValue(a, sortBy);
+                    const valueB = metadataValue(b, sortBy);
+                    if (valueA === null || valueB === null) {
+                        if (valueA === valueB) return 0;
+                        return valueA === null ? 1 : -1;
+                    }
+                    comparison = typeof valueA === 'number' && typeof valueB === 'number'
+                        ? valueA - valueB
+                        : String(valueA).localeCompare(String(valueB), undefined, { numeric: true, sensitivity: 'base' });
+                }
             }
             return sortOrder === 'asc' ? comparison : -comparison;
         });
@@ -164,7 +227,7 @@ export const FileList: FC<FileListProps> = ({
     return (
         <div className="flex-1 flex flex-col overflow-hidden">
             {/* Sticky Header */}
-            <div className="flex-shrink-0 grid grid-cols-[1fr_150px_150px_100px] gap-4 px-6 py-2 bg-[var(--color-bg-surface)] border-b border-[var(--color-border)] text-[11px] font-semibold text-[var(--color-text-muted)] uppercase tracking-wider">
+            <div className="flex-shrink-0 grid grid-cols-[1fr_150px_150px_100px_140px] gap-4 px-6 py-2 bg-[var(--color-bg-surface)] border-b border-[var(--color-border)] text-[11px] font-semibold text-[var(--color-text-muted)] uppercase tracking-wider">
                 <ColumnHeader
                     label="Name"
                     column="name"
@@ -195,6 +258,24 @@ export const FileList: FC<FileListProps> = ({
                     onClick={() => onSort('size')}
                     className="justify-end pr-2"
                 />
+                {/* Metadata column: pick a key, or click the active one to reverse */}
+                <div className="flex items-center min-w-0">
+                    <select
+                        value={metadataSort ?? ''}
+                        onChange={(e) => e.target.value && onSort(e.target.value as MetadataSortBy)}
+                        className={`bg-transparent uppercase tracking-wider font-semibold cursor-pointer outline-none min-w-0 truncate hover:text-[var(--color-text-primary)] ${metadataSort ? 'text-[var(--color-text-primary)]' : ''}`}
+                    >
+                        <option value="">More…</option>
+                        {METADATA_SORTS.map(({ key, label }) => (
+                            <option key={key} value={key}>{label}</option>
+                        ))}
+                    </select>
+                    {metadataSort && (
+                        <button onClick={() => onSort(metadataSort)} className="cursor-pointer">
+                            <SortArrow direction={sortOrder} />
+                        </button>
+                    )}
+                </div>
             </div>
 
             {/* Virtualized Rows Container */}
@@ -218,6 +299,7 @@ export const FileList: FC<FileListProps> = ({
                                 key={file.path}
                                 file={file}
                                 isSelected={isSelected}
+                                metadataSort={metadataSort}
                                 style={{
                                     height: `${virtualRow.size}px`,
                                     transform: `translateY(${virtualRow.start}px)`,
@@ -1,10 +1,19 @@
 // Thumbnail component - displays image thumbnails with lazy loading
-// Uses IntersectionObserver for viewport detection
+// Folders ask for a mosaic of their pictures and keep their icon until one
+// arrives, since most folders have none
+// Uses IntersectionObserver for viewport detection: visible items load first,
+// items near the viewport next, and items scrolled away cancel their request
 
 import { FC, useState, useRef, useEffect } from 'react';
 import { useThumbnail, isThumbnailSupported } from '@hooks/useThumbnail';
+import type { ThumbnailPriority } from '@services/thumbnailService';
+import { previewService } from '@services/previewService';
 import { getFileIcon } from '@utils/icons';
 
+// Images the webview decodes itself: shown from the file when this build has
+// no thumbnailer for them (AVIF without dav1d)
+const WEBVIEW_IMAGE_EXTENSIONS = ['avif'];
+
 interface ThumbnailProps {
     path: string;
     extension: string;
@@ -14,30 +23,51 @@ interface ThumbnailProps {
 
 export const Thumbnail: FC<ThumbnailProps> = ({ path, extension, isDir, size = 56 }) => {
     const [isVisible, setIsVisible] = useState(false);
+    const [isNear, setIsNear] = useState(false);
+    const [fileFailed, setFileFailed] = useState(false);
     const containerRef = useRef<HTMLDivElement>(null);
 
-    // Lazy loading with IntersectionObserver
+    useEffect(() => setFileFailed(false), [path]);
+
+    // Lazy loading with IntersectionObserver, one for the viewport and one
+    // for a margin around it
     useEffect(() => {
         const element = containerRef.current;
         if (!element) return;
 
-        const observer = new IntersectionObserver(
-            (entries) => {
-                if (entries[0].isIntersecting) {
-                    setIsVisible(true);
-                    observer.disconnect();
-                }
-            },
-            { rootMargin: '100px' } // Start loading 100px before visible
+        const visibleObserver = new IntersectionObserver((entries) => {
+            setIsVisible(entries[0].isIntersecting);
+        });
+        const nearObserver = new IntersectionObserver(
+            (entries) => setIsNear(entries[0].isIntersecting),
+            { rootMargin: '300px' } // Start loading 300px before visible
         );
 
-        observer.observe(element);
-        return () => observer.disconnect();
+        visibleObserver.observe(element);
+        nearObserver.observe(element);
+        return () => {
+            visibleObserver.disconnect();
+            nearObserver.disconnect();
+        };
     }, []);
 
-    // Only use thumbnail hook for supported files
-    const shouldLoadThumbnail = !isDir && isThumbnailSupported(extension);
-    const { thumbnail, isLoading } = useThumbnail(path, extension, isVisible && shouldLoadThumbnail);
+    // Only use thumbnail hook for folders and supported files
+    const shouldLoadThumbnail = isDir || isThumbnailSupported(extension);
+    const priority: ThumbnailPriority | null = !shouldLoadThumbnail
+        ? null
+        : isVisible
+            ? 'visible'
+            : isNear
+                ? 'near'
+                : null;
+    const { thumbnail: generated, isLoading } = useThumbnail(path, extension, priority, isDir);
+    const showFile =
+        !isDir &&
+        !shouldLoadThumbnail &&
+        !fileFailed &&
+        WEBVIEW_IMAGE_EXTENSIONS.includes(extension.toLowerCase()) &&
+        (isVisible || isNear);
+    const thumbnail = showFile ? previewService.fileContentUrl(path) : generated;
 
     // Dynamic container styles
     const containerStyle = {
@@ -45,31 +75,28 @@ export const Thumbnail: FC<ThumbnailProps> = ({ path, extension, isDir, size = 5
         height: size,
     };
 
-    // Show icon for directories or unsupported files
-    if (isDir || !shouldLoadThumbnail) {
-        return (
-            <div
-                ref={containerRef}
-                className="flex items-center justify-center icon-glow"
-                style={containerStyle}
-            >
-                {getFileIcon(extension, isDir, Math.min(size, 56))}
-            </div>
-        );
-    }
+    const showIcon = (!shouldLoadThumbnail && !showFile) || (isDir && !thumbnail);
 
+    // One container throughout, so the observers keep watching it
     return (
         <div
             ref={containerRef}
-            className="flex items-center justify-center overflow-hidden rounded-md"
+            className={
+                showIcon
+                    ? 'flex items-center justify-center icon-glow'
+                    : 'flex items-center justify-center overflow-hidden rounded-md'
+            }
             style={containerStyle}
         >
-            {thumbnail ? (
+            {showIcon ? (
+                getFileIcon(extension, isDir, Math.min(size, 56))
+            ) : thumbnail ? (
                 <img
                     src={thumbnail}
                     alt=""
                     className="w-full h-full object-contain transition-opacity duration-200"
                     loading="lazy"
+                    onError={showFile ? () => setFileFailed(true) : undefined}
                 />
             ) : isLoading ? (
                 <div
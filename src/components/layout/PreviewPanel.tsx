@@ -1,11 +1,14 @@
 // PreviewPanel component - shows file preview and metadata
 // Displays on the right side when a file is selected
 
-import { FC, useState, useEffect } from 'react';
+import { FC, Fragment, useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import type { UnlistenFn } from '@tauri-apps/api/event';
 import type { FileEntry } from '@types';
-import { formatSize, getFileType } from '@utils/format';
+import { fileService, previewService, type HighlightSpan, type StructuredPreview } from '@services';
+import { formatError, formatSize, getFileType } from '@utils/format';
 import { Thumbnail } from '../file-browser/Thumbnail';
+import { StructuredView } from './StructuredView';
 
 interface PreviewPanelProps {
     file: FileEntry | null;
@@ -14,32 +17,54 @@ interface PreviewPanelProps {
 }
 
 interface FilePreviewData {
-    content?: string;
-    lineCount?: number;
-    isTruncated?: boolean;
+    content: string;
+    line_count: number;
+    line_count_is_estimate: boolean; // Counted on part of a large file
+    is_truncated: boolean;
+    encoding: string; // "UTF-8", "UTF-16LE", "windows-1252", ...
+    has_bom: boolean;
 }
 
-// Image extensions that can be previewed
-const IMAGE_EXTENSIONS = ['jpg', 'jpeg', 'png', 'gif', 'bmp', 'webp', 'ico', 'svg'];
+// Image extensions that can be previewed; camera RAW and Photoshop files show
+// their embedded preview or composite
+const IMAGE_EXTENSIONS = [
+    'jpg', 'jpeg', 'png', 'gif', 'bmp', 'webp', 'ico', 'svg', 'tif', 'tiff', 'avif', 'heic',
+    'heif', 'qoi', 'tga', 'dds', 'psd', 'psb', 'dng', 'cr2', 'nef', 'nrw', 'arw', 'srf', 'sr2',
+    'orf', 'rw2', 'pef', 'srw', '3fr', 'erf', 'mef', 'mos', 'iiq', 'raf'
+];
 
 // Video extensions that can show thumbnails
 const VIDEO_EXTENSIONS = ['mp4', 'mkv', 'avi', 'mov', 'wmv', 'flv', 'webm', 'm4v', 'mpeg', 'mpg'];
 
+// Images and media the webview shows or plays itself, loaded from disk over
+// file-content://; the rest show their thumbnail
+const FULL_IMAGE_EXTENSIONS = ['jpg', 'jpeg', 'png', 'gif', 'bmp', 'webp', 'ico', 'svg', 'avif'];
+const PLAYABLE_VIDEO_EXTENSIONS = ['mp4', 'm4v', 'mov', 'webm', 'mkv', 'ogv'];
+const AUDIO_EXTENSIONS = ['mp3', 'm4a', 'aac', 'flac', 'ogg', 'oga', 'opus', 'wav'];
+
 // Text extensions that can be previewed
 const TEXT_EXTENSIONS = [
     'txt', 'md', 'json', 'js', 'ts', 'jsx', 'tsx', 'css', 'html', 'xml',
     'yaml', 'yml', 'toml', 'ini', 'cfg', 'conf', 'log', 'sh', 'bat', 'ps1',
     'py', 'rb', 'go', 'rs', 'c', 'cpp', 'h', 'hpp', 'java', 'kt', 'swift',
-    'sql', 'graphql', 'vue', 'svelte', 'astro'
+    'sql', 'graphql', 'vue', 'svelte', 'astro', 'csv', 'tsv'
 ];
 
+// Text extensions shown as a table, tree or rendered Markdown
+const STRUCTURED_EXTENSIONS = ['csv', 'tsv', 'json', 'yaml', 'yml', 'toml', 'md'];
+
 export const PreviewPanel: FC<PreviewPanelProps> = ({ file, isVisible, onClose }) => {
     const [previewData, setPreviewData] = useState<FilePreviewData | null>(null);
     const [isLoading, setIsLoading] = useState(false);
     const [error, setError] = useState<string | null>(null);
+    const [highlighted, setHighlighted] = useState<HighlightSpan[][] | null>(null);
+    const [structured, setStructured] = useState<StructuredPreview | null>(null);
+    const [mediaFailed, setMediaFailed] = useState(false); // Falls back to the thumbnail
 
     // Load preview when file changes
     useEffect(() => {
+        setStructured(null);
+        setMediaFailed(false);
         if (!file || file.is_dir) {
             setPreviewData(null);
             return;
@@ -53,8 +78,51 @@ export const PreviewPanel: FC<PreviewPanelProps> = ({ file, isVisible, onClose }
         } else {
             setPreviewData(null);
         }
+
+        // The plain text shows until (and if) the structured preview loads
+        if (!STRUCTURED_EXTENSIONS.includes(ext)) return;
+        let active = true;
+        previewService.readStructuredPreview(file.path)
+            .then((preview) => {
+                if (active) setStructured(preview);
+            })
+            .catch(() => undefined);
+        return () => {
+            active = false;
+        };
     }, [file?.path]);
 
+    // Highlight the previewed lines in the background; plain text shows meanwhile
+    useEffect(() => {
+        setHighlighted(null);
+        if (!file || !previewData?.content) return;
+
+        const jobId = `highlight-${crypto.randomUUID()}`;
+        const lines: HighlightSpan[][] = [];
+        let active = true;
+        let unlisten: UnlistenFn | undefined;
+
+        previewService.onHighlightChunk((chunk) => {
+            if (chunk.job_id !== jobId || !active) return;
+            lines.splice(chunk.first_line, chunk.lines.length, ...chunk.lines);
+            setHighlighted([...lines]);
+        }).then((stop) => {
+            unlisten = stop;
+            if (!active) {
+                stop();
+                return;
+            }
+            // Files without a grammar just stay plain
+            return previewService.highlightFile(jobId, file.path, undefined, 100);
+        }).catch(() => undefined);
+
+        return () => {
+            active = false;
+            unlisten?.();
+            fileService.cancelJob(jobId).catch(() => undefined);
+        };
+    }, [previewData]);
+
     const loadTextPreview = async (path: string) => {
         setIsLoading(true);
         setError(null);
@@ -66,7 +134,7 @@ export const PreviewPanel: FC<PreviewPanelProps> = ({ file, isVisible, onClose }
             });
             setPreviewData(result);
         } catch (err) {
-            setError(String(err));
+            setError(formatError(err));
             setPreviewData(null);
         } finally {
             setIsLoading(false);
@@ -80,6 +148,10 @@ export const PreviewPanel: FC<PreviewPanelProps> = ({ file, isVisible, onClose }
     const isVideo = VIDEO_EXTENSIONS.includes(ext);
     const isText = TEXT_EXTENSIONS.includes(ext);
     const hasLargeThumbnail = isImage || isVideo;
+    const showFullImage = FULL_IMAGE_EXTENSIONS.includes(ext) && !mediaFailed;
+    const playVideo = PLAYABLE_VIDEO_EXTENSIONS.includes(ext) && !mediaFailed;
+    const playAudio = AUDIO_EXTENSIONS.includes(ext) && !mediaFailed;
+    const contentUrl = file && !file.is_dir ? previewService.fileContentUrl(file.path) : '';
 
     return (
         <div className="w-80 shrink-0 border-l border-[var(--color-border)] bg-[var(--color-bg-surface)] flex flex-col">
@@ -112,17 +184,51 @@ export const PreviewPanel: FC<PreviewPanelProps> = ({ file, isVisible, onClose }
             ) : (
                 <div className="flex-1 overflow-y-auto">
                     {/* File Icon/Thumbnail - centered with proper padding */}
-                    <div className="pt-8 pb-6 flex justify-center">
-                        <div className={`${hasLargeThumbnail ? 'w-44 h-44' : 'w-20 h-20'} flex items-center justify-center ${hasLargeThumbnail ? 'rounded-xl overflow-hidden bg-[var(--color-bg-base)] border border-[var(--color-border)] shadow-sm' : ''}`}>
-                            <Thumbnail
-                                path={file.path}
-                                extension={file.extension}
-                                isDir={file.is_dir}
-                                size={hasLargeThumbnail ? 160 : 80}
+                    <div className="pt-8 pb-6 px-5 flex justify-center">
+                        {playVideo ? (
+                            <video
+                                key={file.path}
+                                src={contentUrl}
+                                controls
+                                preload="metadata"
+                                className="w-full max-h-56 rounded-xl bg-black border border-[var(--color-border)] shadow-sm"
+                                onError={() => setMediaFailed(true)}
                             />
-                        </div>
+                        ) : (
+                            <div className={`${hasLargeThumbnail ? 'w-44 h-44' : 'w-20 h-20'} flex items-center justify-center ${hasLargeThumbnail ? 'rounded-xl overflow-hidden bg-[var(--color-bg-base)] border border-[var(--color-border)] shadow-sm' : ''}`}>
+                                {showFullImage ? (
+                                    <img
+                                        key={file.path}
+                                        src={contentUrl}
+                                        alt=""
+                                        className="max-w-full max-h-full object-contain"
+                                        onError={() => setMediaFailed(true)}
+                                    />
+                                ) : (
+                                    <Thumbnail
+                                        path={file.path}
+                                        extension={file.extension}
+                                        isDir={file.is_dir}
+                                        size={hasLargeThumbnail ? 160 : 80}
+                                    />
+                                )}
+                            </div>
+                        )}
                     </div>
 
+                    {playAudio && (
+                        <div className="px-5 pb-4">
+                            <audio
+                                key={file.path}
+                                src={contentUrl}
+                                controls
+                                preload="metadata"
+                                className="w-full"
+                                onError={() => setMediaFailed(true)}
+                            />
+                        </div>
+                    )}
+
                     {/* File Name - with better padding */}
                     <div className="px-5 pb-6 text-center">
                         <h3 className="text-[14px] font-medium text-[var(--color-text-primary)] break-words leading-relaxed">
@@ -157,11 +263,20 @@ export const PreviewPanel: FC<PreviewPanelProps> = ({ file, isVisible, onClose }
                                         {file.modified || '-'}
                                     </td>
                                 </tr>
-                                {previewData?.lineCount && (
+                                {previewData && previewData.line_count > 0 && (
                                     <tr>
                                         <td className="text-[var(--color-text-muted)] align-middle">Lines</td>
                                         <td className="text-[var(--color-text-secondary)] text-right align-middle">
-                                            {previewData.lineCount}{previewData.isTruncated ? '+' : ''}
+                                            {previewData.line_count_is_estimate ? '~' : ''}
+                                            {previewData.line_count.toLocaleString()}
+                                        </td>
+                                    </tr>
+                                )}
+                                {previewData && (
+                                    <tr>
+                                        <td className="text-[var(--color-text-muted)] align-middle">Encoding</td>
+                                        <td className="text-[var(--color-text-secondary)] text-right align-middle">
+                                            {previewData.encoding}{previewData.has_bom ? ' (BOM)' : ''}
                                         </td>
                                     </tr>
                                 )}
@@ -189,17 +304,43 @@ export const PreviewPanel: FC<PreviewPanelProps> = ({ file, isVisible, onClose }
                                         <div className="text-[var(--color-danger)] text-[12px]">
                                             {error}
                                         </div>
+                                    ) : structured && (structured.kind !== 'tree' || structured.root) ? (
+                                        <StructuredView path={file.path} preview={structured} />
                                     ) : previewData?.content ? (
-                                        <pre className="text-[11px] text-[var(--color-text-secondary)] whitespace-pre-wrap break-words font-mono leading-relaxed">
-                                            {previewData.content}
-                                        </pre>
+                                        <>
+                                            {structured && <StructuredView path={file.path} preview={structured} />}
+                                            <pre className="text-[11px] text-[var(--color-text-secondary)] whitespace-pre-wrap break-words font-mono leading-relaxed">
+                                                {highlighted
+                                                    ? previewData.content.split('\n').map((line, i) => (
+                                                        <Fragment key={i}>
+                                                            {i > 0 && '\n'}
+                                                            {highlighted[i]
+                                                                ? highlighted[i].map((span, j) => (
+                                                                    <span
+                                                                        key={j}
+                                                                        style={{
+                                                                            color: span.color,
+                                                                            fontWeight: span.bold ? 600 : undefined,
+                                                                            fontStyle: span.italic ? 'italic' : undefined,
+                                                                            textDecoration: span.underline ? 'underline' : undefined,
+                                                                        }}
+                                                                    >
+                                                                        {span.text}
+                                                                    </span>
+                                                                ))
+                                                                : line}
+                                                        </Fragment>
+                                                    ))
+                                                    : previewData.content}
+                                            </pre>
+                                        </>
                                     ) : (
                                         <div className="text-[var(--color-text-muted)] text-[12px]">
                                             No preview available
                                         </div>
                                     )}
                                 </div>
-                                {previewData?.isTruncated && (
+                                {previewData?.is_truncated && !structured && (
                                     <div className="text-[11px] text-[var(--color-text-muted)] mt-2 text-center opacity-70">
                                         Showing first 100 lines...
                                     </div>
@@ -183,12 +183,11 @@ export const Sidebar: FC<SidebarProps> = ({ drives, quickAccess, currentPath, on
                 {thisPCExpanded && (
                     <nav className="space-y-0.5">
                         {drives.map((drive) => {
-                            const letter = drive.path.charAt(0);
                             const isActive = currentPath.toLowerCase() === drive.path.toLowerCase();
                             return (
                                 <TreeItem
                                     key={drive.path}
-                                    name={`Local Disk (${letter}:)`}
+                                    name={drive.name}
                                     path={drive.path}
                                     icon={<DriveIcon size={16} />}
                                     depth={1}
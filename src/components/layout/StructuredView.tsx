@@ -0,0 +1,142 @@
+// StructuredView component - renders a structured preview from the backend
+// Tables page through CSV/TSV, trees collapse JSON/YAML/TOML, Markdown is
+// already sanitized HTML
+
+import { FC, useState } from 'react';
+import { previewService, type StructuredPreview, type Table, type TreeNode } from '@services';
+
+interface StructuredViewProps {
+    path: string;
+    preview: StructuredPreview;
+}
+
+// Nodes nested deeper than this start collapsed
+const EXPANDED_DEPTH = 2;
+
+const TableView: FC<{ path: string; table: Table }> = ({ path, table }) => {
+    const [page, setPage] = useState(table);
+    const [isLoading, setIsLoading] = useState(false);
+
+    const loadMore = async () => {
+        if (page.next_offset === null) return;
+        setIsLoading(true);
+        try {
+            const next = await previewService.readStructuredPreview(path, undefined, {
+                offset: page.next_offset,
+                delimiter: page.delimiter,
+            });
+            if (next.kind === 'table') {
+                setPage({ ...next, rows: [...page.rows, ...next.rows] });
+            }
+        } finally {
+            setIsLoading(false);
+        }
+    };
+
+    return (
+        <div className="text-[11px]">
+            <table className="border-collapse font-mono">
+                {page.headers && (
+                    <thead>
+                        <tr>
+                            {page.headers.map((header, i) => (
+                                <th key={i} className="px-2 py-1 text-left font-semibold text-[var(--color-text-primary)] border-b border-[var(--color-border)] whitespace-nowrap">
+                                    {header}
+                                </th>
+                            ))}
+                        </tr>
+                    </thead>
+                )}
+                <tbody>
+                    {page.rows.map((row, i) => (
+                        <tr key={i} className="odd:bg-[var(--color-bg-hover)]">
+                            {row.map((field, j) => (
+                                <td key={j} className="px-2 py-0.5 text-[var(--color-text-secondary)] whitespace-nowrap">
+                                    {field}
+                                </td>
+                            ))}
+                        </tr>
+                    ))}
+                </tbody>
+            </table>
+            {page.next_offset !== null && (
+                <button
+                    onClick={loadMore}
+                    disabled={isLoading}
+                    className="mt-2 text-[var(--color-accent)] hover:underline disabled:opacity-50"
+                >
+                    {isLoading ? 'Loading...' : 'Show more rows'}
+                </button>
+            )}
+        </div>
+    );
+};
+
+const TreeNodeView: FC<{ node: TreeNode; depth: number }> = ({ node, depth }) => {
+    const [expanded, setExpanded] = useState(depth < EXPANDED_DEPTH);
+    const isParent = node.kind === 'object' || node.kind === 'array';
+    const hidden = node.child_count - node.children.length;
+
+    return (
+        <div style={{ paddingLeft: depth > 0 ? 12 : 0 }}>
+            <div
+                className={`whitespace-nowrap ${isParent ? 'cursor-pointer' : ''}`}
+                onClick={() => isParent && setExpanded(!expanded)}
+            >
+                {isParent && <span className="inline-block w-3 text-[var(--color-text-muted)]">{expanded ? '▾' : '▸'}</span>}
+                {node.key !== null && <span className="text-[var(--color-text-primary)]">{node.key}: </span>}
+                {node.tag && <span className="text-[var(--color-text-muted)]">{node.tag} </span>}
+                {isParent ? (
+                    <span className="text-[var(--color-text-muted)]">
+                        {node.kind === 'array' ? `[${node.child_count}]` : `{${node.child_count}}`}
+                    </span>
+                ) : (
+                    <span className={node.kind === 'string' ? 'text-[var(--color-accent)]' : 'text-[var(--color-text-secondary)]'}>
+                        {node.kind === 'string' ? JSON.stringify(node.value) : (node.value ?? 'null')}
+                    </span>
+                )}
+            </div>
+            {isParent && expanded && (
+                <>
+                    {node.children.map((child, i) => (
+                        <TreeNodeView key={i} node={child} depth={depth + 1} />
+                    ))}
+                    {hidden > 0 && (
+                        <div className="pl-3 text-[var(--color-text-muted)]">… {hidden} more</div>
+                    )}
+                </>
+            )}
+        </div>
+    );
+};
+
+export const StructuredView: FC<StructuredViewProps> = ({ path, preview }) => {
+    switch (preview.kind) {
+        case 'table':
+            return <TableView path={path} table={preview} />;
+        case 'tree':
+            return (
+                <div className="text-[11px] font-mono leading-relaxed">
+                    {preview.error && (
+                        <div className="text-[var(--color-danger)] mb-2">
+                            {preview.error.line !== null && `Line ${preview.error.line}, column ${preview.error.column}: `}
+                            {preview.error.message}
+                        </div>
+                    )}
+                    {preview.root && <TreeNodeView node={preview.root} depth={0} />}
+                    {preview.truncated && (
+                        <div className="mt-2 text-[var(--color-text-muted)] opacity-70">
+                            Showing the first {preview.node_count.toLocaleString()} values
+                        </div>
+                    )}
+                </div>
+            );
+        case 'markdown':
+            return (
+                <div
+                    className="text-[12px] text-[var(--color-text-secondary)] leading-relaxed [&_h1]:text-[15px] [&_h1]:font-semibold [&_h2]:text-[14px] [&_h2]:font-semibold [&_ul]:list-disc [&_ul]:pl-5 [&_ol]:list-decimal [&_ol]:pl-5 [&_code]:font-mono [&_pre]:overflow-auto [&_a]:text-[var(--color-accent)] [&_p]:mb-2"
+                    dangerouslySetInnerHTML={{ __html: preview.html }}
+                />
+            );
+    }
+};
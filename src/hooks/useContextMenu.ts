@@ -20,7 +20,7 @@ interface ContextMenuReturn {
 
 export function useContextMenu({ setDialog }: ContextMenuOptions): ContextMenuReturn {
     const contextFileRef = useRef<FileEntry | null>(null);
-    const { handleOpen, handleCut, handleCopy, handlePaste } = useFileOperations();
+    const { handleOpen, handleCut, handleCopy, handlePaste, handlePasteLink } = useFileOperations();
     const { activeTabId, setSelectedPaths, getCurrentState } = useTabStore();
     const hasClipboard = useClipboardStore((s) => s.clipboard !== null);
 
@@ -70,6 +70,7 @@ export function useContextMenu({ setDialog }: ContextMenuOptions): ContextMenuRe
         handleCut,
         handleCopy,
         handlePaste,
+        handlePasteLink,
         getCurrentState,
     });
 
@@ -79,9 +80,10 @@ export function useContextMenu({ setDialog }: ContextMenuOptions): ContextMenuRe
             handleCut,
             handleCopy,
             handlePaste,
+            handlePasteLink,
             getCurrentState,
         };
-    }, [handleOpen, handleCut, handleCopy, handlePaste, getCurrentState]);
+    }, [handleOpen, handleCut, handleCopy, handlePaste, handlePasteLink, getCurrentState]);
 
     // Listen for context menu actions from Tauri
     useEffect(() => {
@@ -110,6 +112,9 @@ export function useContextMenu({ setDialog }: ContextMenuOptions): ContextMenuRe
                 case 'paste':
                     handlers.handlePaste();
                     break;
+                case 'paste_link':
+                    handlers.handlePasteLink();
+                    break;
                 case 'new_folder':
                     setDialog('newFolder');
                     break;
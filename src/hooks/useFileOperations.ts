@@ -1,11 +1,12 @@
 // Hook for file operations
-// Encapsulates copy, cut, paste, create folder, rename, delete operations
+// Encapsulates copy, cut, paste, paste as link, create folder, rename, delete operations
 // Supports multi-selection with Shift+Click and Ctrl+Click
 
 import { useCallback } from 'react';
 import { fileService, systemService } from '@services';
 import { useTabStore, useClipboardStore } from '@store';
 import type { FileEntry } from '@types';
+import { formatError } from '@utils/format';
 
 export type DialogType = 'newFolder' | 'rename' | 'delete' | 'properties' | null;
 
@@ -13,6 +14,7 @@ interface FileOperationsReturn {
     handleCopy: () => void;
     handleCut: () => void;
     handlePaste: () => Promise<void>;
+    handlePasteLink: () => Promise<void>;
     handleNewFolder: (name: string) => Promise<void>;
     handleRename: (newName: string) => Promise<void>;
     handleDelete: () => Promise<void>;
@@ -67,17 +69,33 @@ export function useFileOperations(): FileOperationsReturn {
 
             useTabStore.getState().refresh();
         } catch (error) {
-            updateTabState(activeTabId, { error: String(error) });
+            updateTabState(activeTabId, { error: formatError(error) });
         }
     }, [activeTabId, clear, updateTabState]);
 
+    const handlePasteLink = useCallback(async () => {
+        const currentState = useTabStore.getState().getCurrentState();
+        const clipboardState = useClipboardStore.getState().clipboard;
+        if (!clipboardState) return;
+
+        try {
+            // Links leave the originals in place, so a cut is not consumed
+            for (const item of clipboardState.items) {
+                await fileService.createSymlink(item.path, currentState.path);
+            }
+            useTabStore.getState().refresh();
+        } catch (error) {
+            updateTabState(activeTabId, { error: formatError(error) });
+        }
+    }, [activeTabId, updateTabState]);
+
     const handleNewFolder = useCallback(async (name: string) => {
         const currentState = useTabStore.getState().getCurrentState();
         try {
             await fileService.createFolder(currentState.path, name);
             useTabStore.getState().refresh();
         } catch (error) {
-            updateTabState(activeTabId, { error: String(error) });
+            updateTabState(activeTabId, { error: formatError(error) });
         }
     }, [activeTabId, updateTabState]);
 
@@ -91,7 +109,7 @@ export function useFileOperations(): FileOperationsReturn {
             useTabStore.getState().refresh();
             clearSelection(activeTabId);
         } catch (error) {
-            updateTabState(activeTabId, { error: String(error) });
+            updateTabState(activeTabId, { error: formatError(error) });
         }
     }, [activeTabId, clearSelection, updateTabState, getSelectedFiles]);
 
@@ -107,7 +125,7 @@ export function useFileOperations(): FileOperationsReturn {
             useTabStore.getState().refresh();
             clearSelection(activeTabId);
         } catch (error) {
-            updateTabState(activeTabId, { error: String(error) });
+            updateTabState(activeTabId, { error: formatError(error) });
         }
     }, [activeTabId, clearSelection, updateTabState, getSelectedFiles]);
 
@@ -155,7 +173,8 @@ export function useFileOperations(): FileOperationsReturn {
     }, [activeTabId, setSelectedPaths]);
 
     const handleOpen = useCallback(async (file: FileEntry) => {
-        if (file.is_dir) {
+        // Archives are browsed in place like folders
+        if (file.is_dir || fileService.isArchive(file.name)) {
             navigateTo(file.path);
         } else {
             try {
@@ -163,7 +182,7 @@ export function useFileOperations(): FileOperationsReturn {
                 await openPath(file.path);
             } catch (error) {
                 console.error('Failed to open file:', error);
-                updateTabState(activeTabId, { error: `Failed to open: ${error}` });
+                updateTabState(activeTabId, { error: `Failed to open: ${formatError(error)}` });
             }
         }
     }, [activeTabId, navigateTo, updateTabState]);
@@ -184,6 +203,7 @@ export function useFileOperations(): FileOperationsReturn {
         handleCopy,
         handleCut,
         handlePaste,
+        handlePasteLink,
         handleNewFolder,
         handleRename,
         handleDelete,
@@ -1,8 +1,13 @@
 // useThumbnail hook - manages thumbnail loading with LRU cache
 // Following Single Responsibility: handles thumbnail state management
+// Requests go to the backend scheduler, which works on visible items first;
+// items that scroll away cancel their requests. Finished thumbnails are
+// loaded from their thumb:// URL, which is what the cache holds.
 
-import { useState, useEffect, useRef } from 'react';
-import { thumbnailService } from '@services/thumbnailService';
+import { useState, useEffect } from 'react';
+import type { UnlistenFn } from '@tauri-apps/api/event';
+import { thumbnailService, type ThumbnailPriority } from '@services/thumbnailService';
+import { formatError } from '@utils/format';
 
 // LRU Cache implementation
 class LRUCache<K, V> {
@@ -43,42 +48,98 @@ class LRUCache<K, V> {
 
 // Global cache shared across all hook instances
 const thumbnailCache = new LRUCache<string, string>(200);
-const pendingRequests = new Map<string, Promise<string>>();
-
-// Request queue to limit concurrent requests
-const MAX_CONCURRENT = 4;
-let activeRequests = 0;
-const requestQueue: Array<() => void> = [];
-
-function processQueue() {
-    while (activeRequests < MAX_CONCURRENT && requestQueue.length > 0) {
-        const next = requestQueue.shift();
-        if (next) {
-            activeRequests++;
-            next();
-        }
-    }
-}
 
-function queueRequest<T>(fn: () => Promise<T>): Promise<T> {
-    return new Promise((resolve, reject) => {
-        const execute = () => {
-            fn()
-                .then(resolve)
-                .catch(reject)
-                .finally(() => {
-                    activeRequests--;
-                    processQueue();
-                });
-        };
+// Rejection reason of requests dropped because no one wants them anymore
+const CANCELLED = 'cancelled';
 
-        if (activeRequests < MAX_CONCURRENT) {
-            activeRequests++;
-            execute();
-        } else {
-            requestQueue.push(execute);
+// Requests waiting for their "thumbnail-batch" result, by path
+interface Waiting {
+    promise: Promise<string>;
+    resolve: (url: string) => void;
+    reject: (reason: unknown) => void;
+    users: number; // Hook instances showing this path
+}
+const waiting = new Map<string, Waiting>();
+
+// Requests and cancellations are sent together, once per tick
+const pendingRequests = new Map<string, ThumbnailPriority>();
+const pendingCancels = new Set<string>();
+let flushScheduled = false;
+let listening: Promise<UnlistenFn> | null = null;
+
+function listenForResults(): Promise<UnlistenFn> {
+    listening ??= thumbnailService.onThumbnailBatch((results) => {
+        for (const result of results) {
+            // Keep thumbnails that finished after being cancelled, too
+            const url = result.error ? null : thumbnailService.thumbnailUrl(result.path, result.size);
+            if (url) thumbnailCache.set(result.path, url);
+            const entry = waiting.get(result.path);
+            if (!entry) continue;
+            waiting.delete(result.path);
+            if (url) {
+                entry.resolve(url);
+            } else {
+                entry.reject(result.error);
+            }
         }
     });
+    return listening;
+}
+
+function scheduleFlush() {
+    if (flushScheduled) return;
+    flushScheduled = true;
+    setTimeout(async () => {
+        flushScheduled = false;
+        // Subscribe before asking, so no result is missed
+        await listenForResults();
+        if (pendingCancels.size > 0) {
+            thumbnailService.cancelThumbnails([...pendingCancels]).catch(() => {});
+            pendingCancels.clear();
+        }
+        if (pendingRequests.size > 0) {
+            const requests = [...pendingRequests].map(([path, priority]) => ({ path, priority }));
+            pendingRequests.clear();
+            thumbnailService.requestThumbnails(requests).catch((err) => {
+                for (const { path } of requests) {
+                    const entry = waiting.get(path);
+                    waiting.delete(path);
+                    entry?.reject(err);
+                }
+            });
+        }
+    }, 0);
+}
+
+// Ask for a thumbnail, or raise the priority of a request already waiting
+function requestThumbnail(path: string, priority: ThumbnailPriority): Promise<string> {
+    let entry = waiting.get(path);
+    if (!entry) {
+        let resolve!: (url: string) => void;
+        let reject!: (reason: unknown) => void;
+        const promise = new Promise<string>((res, rej) => {
+            resolve = res;
+            reject = rej;
+        });
+        entry = { promise, resolve, reject, users: 0 };
+        waiting.set(path, entry);
+    }
+    entry.users++;
+    pendingCancels.delete(path);
+    pendingRequests.set(path, priority);
+    scheduleFlush();
+    return entry.promise;
+}
+
+// Give up on a request; it's cancelled once no one is waiting for it
+function releaseThumbnail(path: string) {
+    const entry = waiting.get(path);
+    if (!entry || --entry.users > 0) return;
+    waiting.delete(path);
+    entry.reject(CANCELLED);
+    pendingRequests.delete(path);
+    pendingCancels.add(path);
+    scheduleFlush();
 }
 
 interface UseThumbnailResult {
@@ -91,12 +152,14 @@ interface UseThumbnailResult {
  * Hook to load thumbnails with caching and lazy loading
  * @param path - File path
  * @param extension - File extension (to check if supported)
- * @param enabled - Whether to load thumbnail (for lazy loading)
+ * @param priority - How soon it's needed; null while the item is off screen
+ * @param isDir - Folders have a thumbnail whatever their extension
  */
 export function useThumbnail(
     path: string,
     extension: string,
-    enabled = true
+    priority: ThumbnailPriority | null = 'visible',
+    isDir = false
 ): UseThumbnailResult {
     const [thumbnail, setThumbnail] = useState<string | null>(() => {
         // Check cache on initial render
@@ -104,14 +167,6 @@ export function useThumbnail(
     });
     const [isLoading, setIsLoading] = useState(false);
     const [error, setError] = useState<string | null>(null);
-    const mountedRef = useRef(true);
-
-    useEffect(() => {
-        mountedRef.current = true;
-        return () => {
-            mountedRef.current = false;
-        };
-    }, []);
 
     useEffect(() => {
         // Reset when path changes
@@ -123,57 +178,35 @@ export function useThumbnail(
             return;
         }
 
-        // Don't load if disabled or not supported
-        if (!enabled || !thumbnailService.isThumbnailSupported(extension)) {
-            setThumbnail(null);
+        // Don't load if off screen or not supported
+        if (!priority || (!isDir && !thumbnailService.isThumbnailSupported(extension))) {
             setIsLoading(false);
             return;
         }
 
-        // Check if request already pending
-        const pending = pendingRequests.get(path);
-        if (pending) {
-            setIsLoading(true);
-            pending
-                .then((data) => {
-                    if (mountedRef.current) {
-                        setThumbnail(data);
-                        setIsLoading(false);
-                    }
-                })
-                .catch((err) => {
-                    if (mountedRef.current) {
-                        setError(String(err));
-                        setIsLoading(false);
-                    }
-                });
-            return;
-        }
-
-        // Load thumbnail
         setIsLoading(true);
         setError(null);
 
-        const request = queueRequest(() => thumbnailService.getThumbnail(path));
-        pendingRequests.set(path, request);
-
-        request
-            .then((data) => {
-                thumbnailCache.set(path, data);
-                pendingRequests.delete(path);
-                if (mountedRef.current) {
-                    setThumbnail(data);
+        let active = true;
+        requestThumbnail(path, priority)
+            .then((url) => {
+                if (active) {
+                    setThumbnail(url);
                     setIsLoading(false);
                 }
             })
             .catch((err) => {
-                pendingRequests.delete(path);
-                if (mountedRef.current) {
-                    setError(String(err));
+                if (active && err !== CANCELLED) {
+                    setError(formatError(err));
                     setIsLoading(false);
                 }
             });
-    }, [path, extension, enabled]);
+
+        return () => {
+            active = false;
+            releaseThumbnail(path);
+        };
+    }, [path, extension, priority, isDir]);
 
     return { thumbnail, isLoading, error };
 }
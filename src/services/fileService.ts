@@ -2,7 +2,8 @@
 // Follows Dependency Inversion Principle: components depend on this abstraction
 
 import { invoke } from '@tauri-apps/api/core';
-import type { FileEntry } from '../types';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import type { FileEntry, MetadataColumn } from '../types';
 
 export interface FileProperties {
     created: string;
@@ -12,32 +13,235 @@ export interface FileProperties {
     hidden: boolean;
 }
 
+export interface ExifData {
+    camera_make: string | null;
+    camera_model: string | null;
+    lens_model: string | null;
+    exposure_time: string | null; // "1/250" or "2.5" seconds
+    f_number: number | null;
+    iso: number | null;
+    focal_length: number | null; // mm
+    date_taken: string | null; // "YYYY-MM-DD HH:MM:SS", camera local time
+    orientation: number | null; // 1-8, as in EXIF
+    gps: { latitude: number; longitude: number; altitude: number | null } | null;
+}
+
+export interface ImageMetadata {
+    format: string | null; // "JPEG", "PNG", ...
+    width: number | null;
+    height: number | null;
+    color_type: string | null; // "RGB", "RGBA", "Grayscale", "CMYK", ...
+    bit_depth: number | null; // Bits per channel
+    exif: ExifData | null;
+    keywords: string[]; // From XMP and IPTC
+}
+
+export interface AudioStream {
+    codec: string | null; // "AAC", "MP3", "FLAC", "Opus", ...
+    sample_rate: number | null;
+    channels: number | null;
+    bits_per_sample: number | null; // Lossless and PCM only
+}
+
+export interface VideoStream {
+    codec: string | null; // "H.264", "VP9", "AV1", ...
+    width: number | null;
+    height: number | null;
+    frame_rate: number | null; // Average frames per second
+}
+
+export interface MediaMetadata {
+    container: string | null; // "MPEG-4", "Matroska", "FLAC", ...
+    duration: number | null; // Seconds
+    bitrate: number | null; // Overall, kbit/s
+    title: string | null;
+    artist: string | null;
+    album: string | null;
+    album_artist: string | null;
+    genre: string | null;
+    year: string | null;
+    track: number | null;
+    track_total: number | null;
+    audio: AudioStream | null; // The first audio stream
+    video: VideoStream | null; // The first video stream
+}
+
+// Search criteria on metadata; files without a field a criterion needs don't match
+export interface MetadataFilter {
+    min_width?: number;
+    max_width?: number;
+    min_height?: number;
+    max_height?: number;
+    camera?: string; // Part of the make or model, any case
+    lens?: string;
+    taken_after?: string; // "YYYY-MM-DD", inclusive
+    taken_before?: string;
+    min_iso?: number;
+    max_iso?: number;
+    has_location?: boolean;
+    keyword?: string; // One of the keywords, any case
+}
+
 export interface CloudDrive {
     name: string;
     path: string;
     provider: string; // "OneDrive", "iCloud", etc.
 }
 
+// Archives the backend can browse like folders (see src-tauri/src/archive)
+const ARCHIVE_SUFFIXES = ['.zip', '.tar', '.tar.gz', '.tgz', '.tar.xz', '.txz', '.tar.zst', '.tzst', '.7z'];
+
+export interface CopyOptions {
+    preserve_metadata?: boolean; // Keep timestamps, permissions, xattrs and symlinks
+    verify?: boolean; // Compare each copied file's hash with the source
+}
+
+export interface CopyMismatch {
+    source: string;
+    destination: string;
+    reason: string;
+}
+
+export interface CopyReport {
+    path: string;
+    files_copied: number;
+    bytes_copied: number;
+    files_verified: number;
+    mismatches: CopyMismatch[];
+    warnings: string[]; // Metadata that could not be preserved
+}
+
+export interface FileTemplate {
+    id: string; // "builtin:<name>" or the template file's path
+    name: string;
+    file_name: string; // Suggested name for the new file
+    extension: string;
+    source: 'builtin' | 'user' | 'app';
+}
+
+export type ArchiveFormat = 'zip' | 'tar' | 'tar.gz' | 'tar.xz' | 'tar.zst';
+
+export interface CompressOptions {
+    format?: ArchiveFormat; // Defaults to zip
+    method?: 'deflate' | 'zstd' | 'store'; // Zip only
+    level?: number; // gz/xz/deflate 0-9, zstd 1-22
+    password?: string; // Zip only, AES-256
+}
+
+export interface ExtractOptions {
+    password?: string;
+    on_conflict?: 'rename' | 'overwrite' | 'skip'; // Defaults to rename
+    extract_here?: boolean; // Skip the folder named after the archive
+}
+
+export interface ExtractReport {
+    path: string; // Folder the archive was extracted into
+    files_extracted: number;
+    skipped: string[];
+    rejected: string[]; // Members whose names would escape the destination
+}
+
+// Payload of "job-progress" events sent by long-running commands
+export interface JobProgress {
+    job_id: string;
+    processed_bytes: number;
+    total_bytes: number;
+    processed_items: number;
+    total_items: number;
+    current: string;
+}
+
+// Codes returned by the backend's name and path validation
+export type ValidationCode =
+    | 'empty'
+    | 'too_long'
+    | 'dot_name'
+    | 'separator'
+    | 'nul'
+    | 'control_character'
+    | 'reserved_character'
+    | 'reserved_name'
+    | 'trailing_dot_or_space'
+    | 'relative_path'
+    | 'parent_reference'
+    | 'path_too_long'
+    | 'inside_source';
+
+// Kinds of error every command rejects with (see src-tauri/src/error.rs)
+export type AppErrorKind =
+    | 'not_found'
+    | 'already_exists'
+    | 'permission_denied'
+    | 'not_a_directory'
+    | 'is_a_directory'
+    | 'directory_not_empty'
+    | 'read_only'
+    | 'disk_full'
+    | 'busy'
+    | 'invalid_name'
+    | 'unsupported'
+    | 'invalid_archive'
+    | 'password_required'
+    | 'wrong_password'
+    | 'source_not_removed'
+    | 'invalid_input'
+    | 'cancelled'
+    | 'io'
+    | 'other';
+
+export interface AppError {
+    kind: AppErrorKind;
+    message: string; // English fallback text
+    path?: string; // Affected path, when there is one
+    code?: ValidationCode; // invalid_name only
+    destination?: string; // source_not_removed only
+    errno?: number | null; // io only
+}
+
 export interface FileService {
-    readDirectory: (path: string) => Promise<FileEntry[]>;
+    readDirectory: (path: string, columns?: MetadataColumn[]) => Promise<FileEntry[]>;
     createFolder: (path: string, name: string) => Promise<void>;
+    createFile: (path: string, name: string, template?: string) => Promise<string>;
+    listTemplates: () => Promise<FileTemplate[]>;
     deleteItem: (path: string) => Promise<void>;
     renameItem: (oldPath: string, newName: string) => Promise<void>;
     copyItem: (source: string, destination: string) => Promise<void>;
+    copyItemWithOptions: (source: string, destination: string, options: CopyOptions) => Promise<CopyReport>;
     moveItem: (source: string, destination: string) => Promise<void>;
-    searchFiles: (path: string, query: string, maxResults?: number) => Promise<FileEntry[]>;
+    createSymlink: (target: string, destination: string, relative?: boolean) => Promise<string>;
+    createHardLink: (target: string, destination: string) => Promise<string>;
+    createShortcut: (target: string, destination: string, kind?: 'desktop' | 'url') => Promise<string>;
+    searchFiles: (path: string, query: string, maxResults?: number, filter?: MetadataFilter, columns?: MetadataColumn[]) => Promise<FileEntry[]>;
     getFileProperties: (path: string) => Promise<FileProperties>;
+    getImageMetadata: (path: string) => Promise<ImageMetadata>;
+    getMediaMetadata: (path: string) => Promise<MediaMetadata>;
     getCloudDrives: () => Promise<CloudDrive[]>;
     getFolderChildren: (path: string) => Promise<FileEntry[]>;
+    isArchive: (name: string) => boolean;
+    validateName: (name: string, platform?: 'windows' | 'unix') => Promise<AppError | null>;
+    isCaseSensitive: (path: string) => Promise<boolean>;
+    compressItems: (jobId: string, sources: string[], destination: string, name: string, options?: CompressOptions) => Promise<string>;
+    extractArchive: (jobId: string, archive: string, destination: string, options?: ExtractOptions) => Promise<ExtractReport>;
+    cancelJob: (jobId: string) => Promise<boolean>;
+    onJobProgress: (handler: (progress: JobProgress) => void) => Promise<UnlistenFn>;
 }
 
 export const fileService: FileService = {
-    readDirectory: (path: string) =>
-        invoke<FileEntry[]>('read_directory', { path }),
+    /**
+     * List a folder; columns adds metadata read from inside the files
+     */
+    readDirectory: (path: string, columns?: MetadataColumn[]) =>
+        invoke<FileEntry[]>('read_directory', { path, columns }),
 
     createFolder: (path: string, name: string) =>
         invoke('create_folder', { path, name }),
 
+    createFile: (path: string, name: string, template?: string) =>
+        invoke<string>('create_file', { path, name, template }),
+
+    listTemplates: () =>
+        invoke<FileTemplate[]>('list_templates'),
+
     deleteItem: (path: string) =>
         invoke('delete_item', { path }),
 
@@ -47,18 +251,75 @@ export const fileService: FileService = {
     copyItem: (source: string, destination: string) =>
         invoke('copy_item', { source, destination }),
 
+    copyItemWithOptions: (source: string, destination: string, options: CopyOptions) =>
+        invoke<CopyReport>('copy_item_with_options', { source, destination, options }),
+
     moveItem: (source: string, destination: string) =>
         invoke('move_item', { source, destination }),
 
-    searchFiles: (path: string, query: string, maxResults = 100) =>
-        invoke<FileEntry[]>('search_files', { path, query, maxResults }),
+    createSymlink: (target: string, destination: string, relative = false) =>
+        invoke<string>('create_symlink', { target, destination, relative }),
+
+    createHardLink: (target: string, destination: string) =>
+        invoke<string>('create_hard_link', { target, destination }),
+
+    createShortcut: (target: string, destination: string, kind?: 'desktop' | 'url') =>
+        invoke<string>('create_shortcut', { target, destination, kind }),
+
+    /**
+     * Find files whose name contains the query and that meet the filter, if any
+     */
+    searchFiles: (path: string, query: string, maxResults = 100, filter?: MetadataFilter, columns?: MetadataColumn[]) =>
+        invoke<FileEntry[]>('search_files', { path, query, maxResults, filter, columns }),
 
     getFileProperties: (path: string) =>
         invoke<FileProperties>('get_file_properties', { path }),
 
+    getImageMetadata: (path: string) =>
+        invoke<ImageMetadata>('get_image_metadata', { path }),
+
+    getMediaMetadata: (path: string) =>
+        invoke<MediaMetadata>('get_media_metadata', { path }),
+
     getCloudDrives: () =>
         invoke<CloudDrive[]>('get_cloud_drives'),
 
     getFolderChildren: (path: string) =>
         invoke<FileEntry[]>('get_folder_children', { path }),
+
+    /**
+     * Check if a file is an archive that can be opened as a virtual folder
+     */
+    isArchive: (name: string) => {
+        const lower = name.toLowerCase();
+        return ARCHIVE_SUFFIXES.some((suffix) => lower.endsWith(suffix) && lower.length > suffix.length);
+    },
+
+    /**
+     * Check a name before creating or renaming; resolves to null when it's valid
+     */
+    validateName: (name: string, platform?: 'windows' | 'unix') =>
+        invoke<void>('validate_name', { name, platform }).then(
+            () => null,
+            (error: AppError) => error
+        ),
+
+    /**
+     * Check whether a folder tells apart names that differ only in case
+     */
+    isCaseSensitive: (path: string) =>
+        invoke<boolean>('is_case_sensitive', { path }),
+
+    // Jobs resolve when done and reject with kind 'cancelled' after cancelJob
+    compressItems: (jobId: string, sources: string[], destination: string, name: string, options?: CompressOptions) =>
+        invoke<string>('compress_items', { jobId, sources, destination, name, options }),
+
+    extractArchive: (jobId: string, archive: string, destination: string, options?: ExtractOptions) =>
+        invoke<ExtractReport>('extract_archive', { jobId, archive, destination, options }),
+
+    cancelJob: (jobId: string) =>
+        invoke<boolean>('cancel_job', { jobId }),
+
+    onJobProgress: (handler: (progress: JobProgress) => void) =>
+        listen<JobProgress>('job-progress', (event) => handler(event.payload)),
 };
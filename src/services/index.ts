@@ -1,4 +1,62 @@
 // Services barrel export
-export { fileService, type FileService, type FileProperties, type CloudDrive } from './fileService';
+export {
+    fileService,
+    type FileService,
+    type FileProperties,
+    type ImageMetadata,
+    type ExifData,
+    type MediaMetadata,
+    type AudioStream,
+    type VideoStream,
+    type MetadataFilter,
+    type CloudDrive,
+    type CopyOptions,
+    type CopyMismatch,
+    type CopyReport,
+    type FileTemplate,
+    type ArchiveFormat,
+    type CompressOptions,
+    type ExtractOptions,
+    type ExtractReport,
+    type JobProgress,
+    type ValidationCode,
+    type AppErrorKind,
+    type AppError,
+} from './fileService';
 export { systemService, type SystemService, type ContextMenuParams } from './systemService';
-export { thumbnailService, type ThumbnailService } from './thumbnailService';
+export {
+    thumbnailService,
+    type ThumbnailService,
+    type ClearedCache,
+    type ThumbnailPriority,
+    type ThumbnailRequest,
+    type ThumbnailResult,
+} from './thumbnailService';
+export {
+    previewService,
+    type PreviewService,
+    type TextRange,
+    type LineIndexStatus,
+    type FollowEvent,
+    type HexRow,
+    type FileHeader,
+    type HexPage,
+    type HexQuery,
+    type HexMatch,
+    type HighlightSpan,
+    type HighlightChunk,
+    type HighlightResult,
+    type StructuredFormat,
+    type TableOptions,
+    type Table,
+    type TreeNode,
+    type ParseError,
+    type Tree,
+    type StructuredPreview,
+} from './previewService';
+export {
+    viewerService,
+    type ViewerService,
+    type ImageInfo,
+    type Region,
+} from './viewerService';
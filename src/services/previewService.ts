@@ -0,0 +1,231 @@
+// Preview service - paging through, jumping into and following text files,
+// syntax highlighting, structured previews of data files, and the hex view of
+// binary files
+// Offsets are byte offsets into the file, as returned by the backend
+
+import { convertFileSrc, invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+
+// A page of whole lines
+export interface TextRange {
+    content: string;
+    line_count: number;
+    start_offset: number;
+    end_offset: number;
+    first_line: number | null; // 1-based, null until the line index gets there
+    file_len: number;
+    at_start: boolean;
+    at_end: boolean;
+    encoding: string;
+}
+
+// Progress of a file's line index, also sent as "line-index-progress" events
+export interface LineIndexStatus {
+    path: string;
+    lines: number; // Lines found so far; the total once complete
+    indexed_bytes: number;
+    file_len: number;
+    complete: boolean;
+}
+
+// Payload of "file-follow" events
+export interface FollowEvent {
+    job_id: string;
+    content: string;
+    line_count: number;
+    start_offset: number;
+    end_offset: number;
+    file_len: number;
+    truncated: boolean; // File was truncated or rotated; content starts it over
+}
+
+// One row of the hex view
+export interface HexRow {
+    offset: number;
+    hex: string; // "7f 45 4c 46 ..."
+    ascii: string; // "." for anything not printable
+}
+
+// Decoded header of an ELF, PE, PNG or ZIP file
+export interface FileHeader {
+    format: 'ELF' | 'PE' | 'PNG' | 'ZIP';
+    fields: { name: string; value: string }[];
+}
+
+export interface HexPage {
+    offset: number;
+    rows: HexRow[];
+    bytes_per_row: number;
+    file_len: number;
+    header: FileHeader | null; // Only on the page at offset 0
+}
+
+// Hex: "4d 5a 90 00"; text is matched as UTF-8 and as UTF-16LE
+export type HexQuery = { kind: 'hex'; pattern: string } | { kind: 'text'; pattern: string };
+
+export interface HexMatch {
+    offset: number;
+    len: number;
+}
+
+// A run of highlighted text in one style
+export interface HighlightSpan {
+    text: string;
+    color: string; // "#rrggbb"
+    bold: boolean;
+    italic: boolean;
+    underline: boolean;
+}
+
+// Payload of "highlight-chunk" events
+export interface HighlightChunk {
+    job_id: string;
+    first_line: number; // 0-based
+    lines: HighlightSpan[][];
+}
+
+export interface HighlightResult {
+    language: string | null; // null: no grammar for this file, no chunks were sent
+    background: string;
+    foreground: string;
+    line_count: number;
+}
+
+export type StructuredFormat = 'csv' | 'tsv' | 'json' | 'yaml' | 'toml' | 'markdown';
+
+export interface TableOptions {
+    offset?: number; // A previous page's next_offset
+    max_rows?: number;
+    delimiter?: string; // Guessed from the first lines if not given
+    has_header?: boolean; // Defaults to true
+}
+
+// A page of rows from a CSV or TSV file
+export interface Table {
+    delimiter: string;
+    headers: string[] | null;
+    rows: string[][];
+    column_count: number; // Widest row, headers included
+    start_offset: number;
+    next_offset: number | null; // null on the last page
+    encoding: string;
+}
+
+export interface TreeNode {
+    key: string | null; // null for array items and the root
+    kind: 'object' | 'array' | 'string' | 'number' | 'bool' | 'null' | 'date';
+    value: string | null; // Scalars as text
+    tag: string | null; // YAML tag, like "!Ref"
+    children: TreeNode[];
+    child_count: number; // More than children.length when the tree was cut
+}
+
+export interface ParseError {
+    message: string;
+    line: number | null; // 1-based
+    column: number | null;
+}
+
+// A JSON, YAML or TOML document; multi-document YAML is an array
+export interface Tree {
+    root: TreeNode | null; // null if the document doesn't parse
+    error: ParseError | null;
+    node_count: number;
+    truncated: boolean;
+}
+
+export type StructuredPreview =
+    | ({ kind: 'table' } & Table)
+    | ({ kind: 'tree' } & Tree)
+    | { kind: 'markdown'; html: string }; // Sanitized
+
+export interface PreviewService {
+    readTextRange: (path: string, offset?: number, direction?: 'forward' | 'backward', maxLines?: number) => Promise<TextRange>;
+    readTextTail: (path: string, maxLines?: number) => Promise<TextRange>;
+    readTextAtLine: (path: string, line: number, maxLines?: number) => Promise<TextRange>;
+    getLineIndex: (path: string) => Promise<LineIndexStatus>;
+    onLineIndexProgress: (handler: (status: LineIndexStatus) => void) => Promise<UnlistenFn>;
+    followFile: (jobId: string, path: string, offset?: number) => Promise<void>;
+    onFileFollow: (handler: (event: FollowEvent) => void) => Promise<UnlistenFn>;
+    readFileHex: (path: string, offset?: number, rows?: number, bytesPerRow?: number) => Promise<HexPage>;
+    findInFile: (jobId: string, path: string, query: HexQuery, offset?: number, ignoreCase?: boolean) => Promise<HexMatch | null>;
+    highlightFile: (jobId: string, path: string, theme?: 'light' | 'dark', maxLines?: number) => Promise<HighlightResult>;
+    onHighlightChunk: (handler: (chunk: HighlightChunk) => void) => Promise<UnlistenFn>;
+    readStructuredPreview: (path: string, format?: StructuredFormat, options?: TableOptions) => Promise<StructuredPreview>;
+    fileContentUrl: (path: string) => string;
+}
+
+export const previewService: PreviewService = {
+    /**
+     * Read lines starting at an offset, or ending at it when going backward.
+     * Pass a page's end_offset to go forward, its start_offset to go backward.
+     */
+    readTextRange: (path: string, offset?: number, direction: 'forward' | 'backward' = 'forward', maxLines = 100) =>
+        invoke<TextRange>('read_text_range', { path, offset, direction, maxLines }),
+
+    /**
+     * Read the last lines of a file
+     */
+    readTextTail: (path: string, maxLines = 100) =>
+        invoke<TextRange>('read_text_range', { path, direction: 'backward', maxLines }),
+
+    /**
+     * Read lines starting at a 1-based line number; waits for the line index
+     * to get there, starting it if needed
+     */
+    readTextAtLine: (path: string, line: number, maxLines = 100) =>
+        invoke<TextRange>('read_text_at_line', { path, line, maxLines }),
+
+    getLineIndex: (path: string) =>
+        invoke<LineIndexStatus>('get_line_index', { path }),
+
+    onLineIndexProgress: (handler: (status: LineIndexStatus) => void) =>
+        listen<LineIndexStatus>('line-index-progress', (event) => handler(event.payload)),
+
+    /**
+     * Follow a file like `tail -f` until fileService.cancelJob(jobId).
+     * Defaults to the end of the file; pass a page's end_offset to continue it.
+     */
+    followFile: (jobId: string, path: string, offset?: number) =>
+        invoke<void>('follow_file', { jobId, path, offset }),
+
+    onFileFollow: (handler: (event: FollowEvent) => void) =>
+        listen<FollowEvent>('file-follow', (event) => handler(event.payload)),
+
+    /**
+     * Read rows of the hex view, starting at the row containing offset
+     */
+    readFileHex: (path: string, offset = 0, rows = 64, bytesPerRow = 16) =>
+        invoke<HexPage>('read_file_hex', { path, offset, rows, bytesPerRow }),
+
+    /**
+     * Find the next match at or after offset. Runs as a job: progress comes as
+     * "job-progress" events and fileService.cancelJob(jobId) stops it.
+     */
+    findInFile: (jobId: string, path: string, query: HexQuery, offset = 0, ignoreCase = false) =>
+        invoke<HexMatch | null>('find_in_file', { jobId, path, query, offset, options: { ignore_case: ignoreCase } }),
+
+    /**
+     * Highlight a file's first lines, sent as "highlight-chunk" events while the
+     * job runs. The theme defaults to the window's light or dark mode.
+     */
+    highlightFile: (jobId: string, path: string, theme?: 'light' | 'dark', maxLines?: number) =>
+        invoke<HighlightResult>('highlight_file', { jobId, path, theme, maxLines }),
+
+    onHighlightChunk: (handler: (chunk: HighlightChunk) => void) =>
+        listen<HighlightChunk>('highlight-chunk', (event) => handler(event.payload)),
+
+    /**
+     * Parse a CSV/TSV, JSON, YAML, TOML or Markdown file. The format is guessed
+     * from the extension unless given; options page through tables.
+     */
+    readStructuredPreview: (path: string, format?: StructuredFormat, options?: TableOptions) =>
+        invoke<StructuredPreview>('read_structured_preview', { path, format, options }),
+
+    /**
+     * URL that serves a file's bytes to <img>, <video> and <audio>, with range
+     * requests for seeking; files inside archives work too
+     */
+    fileContentUrl: (path: string) =>
+        convertFileSrc(path, 'file-content'),
+};
@@ -1,40 +1,89 @@
 // Thumbnail service - handles thumbnail generation requests
 // Follows Single Responsibility Principle: only handles thumbnails
 
-import { invoke } from '@tauri-apps/api/core';
+import { convertFileSrc, invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import type { AppError } from './fileService';
 
-// Supported extensions for thumbnails - images (via Rust image crate)
-const IMAGE_EXTENSIONS = new Set([
+// Extensions with a thumbnailer, which depend on the platform; asked from
+// the backend once, with plain images assumed until it answers
+let thumbnailExtensions = new Set([
     'jpg', 'jpeg', 'png', 'gif', 'bmp', 'webp', 'ico', 'tiff', 'tif'
 ]);
+invoke<string[]>('get_thumbnail_extensions')
+    .then((extensions) => {
+        thumbnailExtensions = new Set(extensions);
+    })
+    .catch(() => {});
 
-// Extensions that Windows Shell can generate thumbnails for (videos, PDFs, etc)
-const SHELL_THUMBNAIL_EXTENSIONS = new Set([
-    'mp4', 'mkv', 'avi', 'mov', 'wmv', 'flv', 'webm', 'm4v', 'mpeg', 'mpg',
-    'pdf', 'doc', 'docx', 'xls', 'xlsx', 'ppt', 'pptx'
-]);
+// What clearing the thumbnail cache removed
+export interface ClearedCache {
+    files: number;
+    bytes: number;
+}
+
+// Most urgent first; "near" is just outside the viewport
+export type ThumbnailPriority = 'visible' | 'near' | 'background';
+
+export interface ThumbnailRequest {
+    path: string;
+    size?: number;
+    priority: ThumbnailPriority;
+}
 
-// All supported extensions
-const THUMBNAIL_EXTENSIONS = new Set([...IMAGE_EXTENSIONS, ...SHELL_THUMBNAIL_EXTENSIONS]);
+// One finished request, delivered in "thumbnail-batch" events; without an
+// error, the thumbnail is ready at thumbnailUrl(path, size)
+export interface ThumbnailResult {
+    path: string;
+    size: number;
+    error: AppError | null;
+}
 
 export interface ThumbnailService {
-    getThumbnail: (path: string, size?: number) => Promise<string>;
+    thumbnailUrl: (path: string, size?: number) => string;
     isThumbnailSupported: (extension: string) => boolean;
+    clearCache: () => Promise<ClearedCache>;
+    requestThumbnails: (requests: ThumbnailRequest[]) => Promise<void>;
+    cancelThumbnails: (paths?: string[]) => Promise<number>;
+    onThumbnailBatch: (handler: (results: ThumbnailResult[]) => void) => Promise<UnlistenFn>;
 }
 
 export const thumbnailService: ThumbnailService = {
     /**
-     * Get a base64-encoded thumbnail for an image file
+     * URL of a file's PNG thumbnail, made on first load; use it as an <img> src
      * @param path - Absolute path to the file
-     * @param size - Optional thumbnail size (default 96)
-     * @returns Base64 data URL (data:image/png;base64,...)
+     * @param size - Optional thumbnail size (default 96); local files come back at
+     *   the cache size at or above it (128, 256, 512 or 1024), or smaller if the image is
      */
-    getThumbnail: (path: string, size = 96) =>
-        invoke<string>('get_thumbnail', { path, size }),
+    thumbnailUrl: (path: string, size = 96) =>
+        `${convertFileSrc(path, 'thumb')}?size=${size}`,
 
     /**
      * Check if a file extension supports thumbnail generation
      */
     isThumbnailSupported: (extension: string) =>
-        THUMBNAIL_EXTENSIONS.has(extension.toLowerCase()),
+        thumbnailExtensions.has(extension.toLowerCase()),
+
+    /**
+     * Delete the thumbnails this app cached; other apps' in the shared Linux
+     * cache are kept
+     */
+    clearCache: () =>
+        invoke<ClearedCache>('clear_thumbnail_cache'),
+
+    /**
+     * Queue thumbnails with the scheduler; they arrive as "thumbnail-batch" events.
+     * Requesting a queued path again changes its priority.
+     */
+    requestThumbnails: (requests: ThumbnailRequest[]) =>
+        invoke('request_thumbnails', { requests }),
+
+    /**
+     * Drop queued requests for these paths, or all of them; returns how many were dropped
+     */
+    cancelThumbnails: (paths?: string[]) =>
+        invoke<number>('cancel_thumbnails', { paths }),
+
+    onThumbnailBatch: (handler: (results: ThumbnailResult[]) => void) =>
+        listen<ThumbnailResult[]>('thumbnail-batch', (event) => handler(event.payload)),
 };
@@ -0,0 +1,53 @@
+// Viewer service - opens pictures at full size and serves them a region at a
+// time, scaled to the zoom level
+// Regions are in the picture's own pixels; the backend keeps decoded pictures
+// cached, so tiles after the first open come back quickly
+
+import { convertFileSrc, invoke } from '@tauri-apps/api/core';
+
+// What the viewer needs to lay out a picture
+export interface ImageInfo {
+    width: number;
+    height: number;
+    has_alpha: boolean;
+    previous: string | null; // Neighbouring pictures in the folder, by name
+    next: string | null;
+}
+
+// A region of a picture, in full-size pixels
+export interface Region {
+    x: number;
+    y: number;
+    width: number;
+    height: number;
+}
+
+export interface ViewerService {
+    openImage: (path: string) => Promise<ImageInfo>;
+    tileUrl: (path: string, scale: number, region?: Region) => string;
+}
+
+export const viewerService: ViewerService = {
+    /**
+     * Decode a picture for viewing; the pictures before and after it are
+     * decoded in the background for a slideshow
+     */
+    openImage: (path: string) =>
+        invoke<ImageInfo>('open_image', { path }),
+
+    /**
+     * URL of a JPEG, or PNG if the picture has alpha, of a region (all of it by
+     * default) scaled by scale, which is above 0 and at most 1; enlarge past
+     * that in the view
+     */
+    tileUrl: (path: string, scale: number, region?: Region) => {
+        const params = new URLSearchParams({ scale: String(scale) });
+        if (region) {
+            params.set('x', String(Math.round(region.x)));
+            params.set('y', String(Math.round(region.y)));
+            params.set('width', String(Math.round(region.width)));
+            params.set('height', String(Math.round(region.height)));
+        }
+        return `${convertFileSrc(path, 'image-tile')}?${params}`;
+    },
+};
@@ -1,5 +1,5 @@
 // Central export for all stores
 export { useAppStore } from './appStore';
 export { useClipboardStore } from './clipboardStore';
-export { useTabStore } from './tabStore';
+export { useTabStore, sortColumns } from './tabStore';
 export { useFolderPrefsStore } from './folderPrefsStore';
@@ -2,12 +2,26 @@
 // Manages multi-tab functionality with per-tab state
 
 import { create } from 'zustand';
-import type { Tab, TabState, FileEntry } from '@types';
+import type { Tab, TabState, FileEntry, MetadataColumn, SortBy } from '@types';
 import { fileService } from '@services';
+import { formatError } from '@utils/format';
 
 // Generate unique tab ID
 const generateId = () => Math.random().toString(36).substring(2, 9);
 
+// Metadata columns a sort key needs loaded with the listing
+export const sortColumns = (sortBy: SortBy): MetadataColumn[] | undefined => {
+    switch (sortBy) {
+        case 'name':
+        case 'date':
+        case 'size':
+        case 'type':
+            return undefined;
+        default:
+            return [sortBy];
+    }
+};
+
 interface TabStore {
     // State
     tabs: Tab[];
@@ -254,12 +268,12 @@ export const useTabStore = create<TabStore>((set, get) => ({
         get().updateTabState(tabId, { isLoading: true, error: null });
 
         try {
-            const entries = await fileService.readDirectory(path);
+            const entries = await fileService.readDirectory(path, sortColumns(get().tabStates[tabId]?.sortBy ?? 'name'));
             get().setFiles(tabId, entries);
             get().updateTabState(tabId, { selectedPaths: [], lastSelectedPath: null, isLoading: false });
         } catch (error) {
             get().updateTabState(tabId, {
-                error: String(error),
+                error: formatError(error),
                 isLoading: false
             });
             get().setFiles(tabId, []);
@@ -281,12 +295,18 @@ export const useTabStore = create<TabStore>((set, get) => ({
         get().updateTabState(activeTabId, { isSearching: true, isLoading: true, error: null });
 
         try {
-            const results = await fileService.searchFiles(currentState.path, query.trim());
+            const results = await fileService.searchFiles(
+                currentState.path,
+                query.trim(),
+                undefined,
+                undefined,
+                sortColumns(currentState.sortBy)
+            );
             get().setFiles(activeTabId, results);
             get().updateTabState(activeTabId, { isLoading: false });
         } catch (error) {
             get().updateTabState(activeTabId, {
-                error: String(error),
+                error: formatError(error),
                 isLoading: false
             });
             get().setFiles(activeTabId, []);
@@ -9,15 +9,84 @@ export interface FileEntry {
     modified: string;
     extension: string;
     is_cloud_placeholder: boolean; // Cloud file not yet downloaded
+    metadata: MetadataColumns | null; // Extra columns, when asked for
 }
 
+// Extra listing columns read from inside files
+export type MetadataColumn =
+    | 'dimensions'
+    | 'camera'
+    | 'lens'
+    | 'date_taken'
+    | 'exposure_time'
+    | 'f_number'
+    | 'iso'
+    | 'focal_length'
+    | 'orientation'
+    | 'location'
+    | 'keywords'
+    | 'duration'
+    | 'bitrate'
+    | 'title'
+    | 'artist'
+    | 'album'
+    | 'track'
+    | 'sample_rate'
+    | 'frame_rate'
+    | 'video_codec'
+    | 'audio_codec';
+
+// Only the columns asked for are filled, and only those the file has
+export interface MetadataColumns {
+    width: number | null; // Images and video
+    height: number | null;
+    camera: string | null; // Make and model, like "SONY ILCE-7M3"
+    lens: string | null;
+    date_taken: string | null; // "YYYY-MM-DD HH:MM:SS"
+    exposure_time: string | null;
+    f_number: number | null;
+    iso: number | null;
+    focal_length: number | null;
+    orientation: number | null;
+    latitude: number | null;
+    longitude: number | null;
+    keywords: string[] | null;
+    duration: number | null; // Seconds
+    bitrate: number | null; // kbit/s
+    title: string | null;
+    artist: string | null;
+    album: string | null;
+    track: number | null;
+    sample_rate: number | null;
+    frame_rate: number | null;
+    video_codec: string | null;
+    audio_codec: string | null;
+}
+
+// A mounted file system, or a drive letter on Windows
 export interface DriveInfo {
     name: string;
     path: string;
-    total_space: number;
-    free_space: number;
+    device: string; // Block device, network share or drive letter
+    file_system: string;
+    label: string | null;
+    read_only: boolean;
+    // null when unknown, as for network shares
+    total_space: number | null;
+    free_space: number | null;
+    available_space: number | null; // Free space usable without privileges
 }
 
 export type ViewMode = 'grid' | 'list';
-export type SortBy = 'name' | 'date' | 'size' | 'type';
+// Sort keys on metadata; listings sorted by one load the matching column
+export type MetadataSortBy =
+    | 'dimensions'
+    | 'date_taken'
+    | 'duration'
+    | 'bitrate'
+    | 'title'
+    | 'artist'
+    | 'album'
+    | 'track';
+export type SortBy = 'name' | 'date' | 'size' | 'type' | MetadataSortBy;
 export type SortOrder = 'asc' | 'desc';
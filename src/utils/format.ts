@@ -9,6 +9,23 @@ export function formatSize(bytes: number): string {
     return `${parseFloat((bytes / Math.pow(k, i)).toFixed(1))} ${units[i]}`;
 }
 
+// Text of an error thrown by a command (an AppError) or anything else
+export function formatError(error: unknown): string {
+    if (error && typeof error === 'object' && 'message' in error) {
+        return String(error.message);
+    }
+    return String(error);
+}
+
+// Format a duration in seconds as "m:ss" or "h:mm:ss"
+export function formatDuration(seconds: number): string {
+    const total = Math.round(seconds);
+    const h = Math.floor(total / 3600);
+    const m = Math.floor((total % 3600) / 60);
+    const s = String(total % 60).padStart(2, '0');
+    return h > 0 ? `${h}:${String(m).padStart(2, '0')}:${s}` : `${m}:${s}`;
+}
+
 // Format date string
 export function formatDate(dateStr: string): string {
     if (!dateStr || dateStr === '-') return '-';